@@ -0,0 +1,41 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{collections::HashMap, task::Poll};
+
+use super::dtype::DtypeVec;
+
+pub static INTERMEDIATES_MAP: Lazy<Mutex<HashMap<&'static str, DtypeVec>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub struct ComputeHandle {
+    result: Option<(DtypeVec, HashMap<&'static str, DtypeVec>)>,
+}
+
+impl ComputeHandle {
+    pub fn new(output: DtypeVec) -> Self {
+        let intermediates = INTERMEDIATES_MAP.lock().drain().collect();
+
+        Self {
+            result: Some((output, intermediates)),
+        }
+    }
+
+    pub fn join(mut self) -> (DtypeVec, HashMap<&'static str, DtypeVec>) {
+        unsafe { self.result.take().unwrap_unchecked() }
+    }
+}
+
+impl Future for ComputeHandle {
+    type Output = (DtypeVec, HashMap<&'static str, DtypeVec>);
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        _cx: &mut core::task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        Poll::Ready(
+            self.get_mut()
+                .result
+                .take()
+                .expect("GpuMat: ComputeHandle polled after completion"),
+        )
+    }
+}
@@ -0,0 +1,872 @@
+mod handle;
+mod quantized;
+mod tensor;
+mod vec;
+
+use super::{
+    Backend, Computed, dtype,
+    dtype::{Dtype, DtypeVec, Dtyped},
+    error::{self, GpuMatError},
+    ops::{self, Activation, CompareOp, UnaryOp},
+    quantization,
+};
+
+pub use handle::ComputeHandle;
+pub use quantization::{QuantBits, QuantFormat};
+pub use quantized::QuantizedTensor;
+pub use tensor::GpuTensor;
+
+pub struct Cpu;
+
+impl Backend for Cpu {
+    type Tensor = GpuTensor;
+    type Handle = ComputeHandle;
+    type Quantized = QuantizedTensor;
+
+    fn new<F: Dtyped>(shape: Vec<u32>, values: &[F]) -> Result<GpuTensor, GpuMatError> {
+        GpuTensor::try_new(shape, values)
+    }
+
+    fn with_capacity(capacity: u64) -> Result<GpuTensor, GpuMatError> {
+        Ok(GpuTensor::with_capacity(capacity))
+    }
+
+    fn upload(values: &DtypeVec) -> Result<GpuTensor, GpuMatError> {
+        Ok(GpuTensor::upload(values))
+    }
+
+    fn download(tensor: &GpuTensor) -> Result<DtypeVec, GpuMatError> {
+        Ok(tensor.download())
+    }
+
+    fn capacity(tensor: &GpuTensor) -> u64 {
+        tensor.capacity()
+    }
+
+    fn dtype(tensor: &GpuTensor) -> Dtype {
+        tensor.dtype()
+    }
+
+    fn add<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_add(lhs, rhs)
+    }
+
+    fn increment<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_increment(by)
+    }
+
+    fn mul<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_mul(lhs, rhs)
+    }
+
+    fn mul_in_place<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_mul_in_place(by)
+    }
+
+    fn sub<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_sub(lhs, rhs)
+    }
+
+    fn decrement<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_decrement(by)
+    }
+
+    fn rsub_in_place<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_rsub_in_place(lhs)
+    }
+
+    fn div<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_div(lhs, rhs)
+    }
+
+    fn div_in_place<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_div_in_place(by)
+    }
+
+    fn rdiv_in_place<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_rdiv_in_place(lhs)
+    }
+
+    fn add_scalar<'a, F: Dtyped>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        scalar: F,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_add_scalar(lhs, scalar)
+    }
+
+    fn mul_scalar<'a, F: Dtyped>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        scalar: F,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_mul_scalar(lhs, scalar)
+    }
+
+    fn shift<F: Dtyped>(tensor: &mut GpuTensor, scalar: F) -> Result<&mut GpuTensor, GpuMatError> {
+        tensor.try_shift(scalar)
+    }
+
+    fn scale<F: Dtyped>(tensor: &mut GpuTensor, scalar: F) -> Result<&mut GpuTensor, GpuMatError> {
+        tensor.try_scale(scalar)
+    }
+
+    fn minimum<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_minimum(lhs, rhs)
+    }
+
+    fn maximum<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_maximum(lhs, rhs)
+    }
+
+    fn minimum_in_place<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_minimum_in_place(by)
+    }
+
+    fn maximum_in_place<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_maximum_in_place(by)
+    }
+
+    fn clamp<'a>(
+        target: &'a mut GpuTensor,
+        src: &GpuTensor,
+        lo: &GpuTensor,
+        hi: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_clamp(src, lo, hi)
+    }
+
+    fn clamp_in_place<'a>(
+        target: &'a mut GpuTensor,
+        lo: &GpuTensor,
+        hi: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_clamp_in_place(lo, hi)
+    }
+
+    fn clamp_scalar<'a, F: Dtyped>(
+        target: &'a mut GpuTensor,
+        src: &GpuTensor,
+        lo: F,
+        hi: F,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_clamp_scalar(src, lo, hi)
+    }
+
+    fn clamp_scalar_in_place<F: Dtyped>(
+        tensor: &mut GpuTensor,
+        lo: F,
+        hi: F,
+    ) -> Result<&mut GpuTensor, GpuMatError> {
+        tensor.try_clamp_scalar_in_place(lo, hi)
+    }
+
+    fn compare<'a>(
+        target: &'a mut GpuTensor,
+        op: CompareOp,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_compare(op, lhs, rhs)
+    }
+
+    fn compare_scalar<'a, F: Dtyped>(
+        target: &'a mut GpuTensor,
+        op: CompareOp,
+        lhs: &GpuTensor,
+        scalar: F,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_compare_scalar(op, lhs, scalar)
+    }
+
+    fn select<'a>(
+        target: &'a mut GpuTensor,
+        mask: &GpuTensor,
+        a: &GpuTensor,
+        b: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_select(mask, a, b)
+    }
+
+    fn unary<'a>(
+        target: &'a mut GpuTensor,
+        op: UnaryOp,
+        src: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_unary(op, src)
+    }
+
+    fn unary_in_place(tensor: &mut GpuTensor, op: UnaryOp) -> Result<&mut GpuTensor, GpuMatError> {
+        tensor.try_unary_in_place(op)
+    }
+
+    fn activation<'a>(
+        target: &'a mut GpuTensor,
+        activation: Activation,
+        src: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_activation(activation, src)
+    }
+
+    fn activation_in_place(
+        tensor: &mut GpuTensor,
+        activation: Activation,
+    ) -> Result<&mut GpuTensor, GpuMatError> {
+        tensor.try_activation_in_place(activation)
+    }
+
+    fn cast<'a>(
+        target: &'a mut GpuTensor,
+        src: &GpuTensor,
+        to: Dtype,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_cast(src, to)
+    }
+
+    fn quantize(tensor: &GpuTensor, format: QuantFormat) -> Result<QuantizedTensor, GpuMatError> {
+        QuantizedTensor::try_quantize(tensor, format)
+    }
+
+    fn dequantize<'a>(
+        target: &'a mut GpuTensor,
+        quantized: &QuantizedTensor,
+        dtype: Dtype,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_dequantize(quantized, dtype)
+    }
+
+    fn add_quantized<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &QuantizedTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_add_quantized(lhs, rhs)
+    }
+
+    fn increment_quantized<'a>(
+        target: &'a mut GpuTensor,
+        by: &QuantizedTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_increment_quantized(by)
+    }
+
+    fn mul_quantized<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &QuantizedTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_mul_quantized(lhs, rhs)
+    }
+
+    fn mul_in_place_quantized<'a>(
+        target: &'a mut GpuTensor,
+        by: &QuantizedTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_mul_in_place_quantized(by)
+    }
+
+    fn save_intermediate(tensor: &GpuTensor, name: &'static str) -> Result<(), GpuMatError> {
+        tensor.save_intermediate(name);
+        Ok(())
+    }
+
+    fn compute(tensor: &GpuTensor) -> ComputeHandle {
+        tensor.compute()
+    }
+
+    fn join(handle: ComputeHandle) -> Result<Computed, GpuMatError> {
+        Ok(handle.join())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //env_logger::init_from_env(env_logger::Env::default().default_filter_or("warn"));
+
+    use super::*;
+    use crate::backends::test_util::array;
+
+    #[test]
+    fn add_f32() {
+        use pollster::FutureExt;
+
+        type F = f32;
+
+        let a = &tensor::GpuTensor::new::<F>(vec![2, 2], &[1.; 4]);
+        let b = &tensor::GpuTensor::new::<F>(vec![2, 2], &[2.; 4]);
+        let c = &tensor::GpuTensor::new::<F>(vec![2, 2], &[0.5; 4]);
+        let d = &mut tensor::GpuTensor::with_capacity(a.capacity());
+
+        assert!(
+            d.set(a + b + c).compute().block_on().0
+                == super::dtype::DtypeVec::F32(ndarray::Array::from_elem(
+                    ndarray::IxDyn(&[2, 2]),
+                    3.5
+                ))
+        );
+        assert!(
+            d.increment(a).compute().join().0
+                == super::dtype::DtypeVec::F32(ndarray::Array::from_elem(
+                    ndarray::IxDyn(&[2, 2]),
+                    4.5
+                ))
+        );
+    }
+
+    #[test]
+    fn add_one_million_f32() {
+        use pollster::FutureExt;
+
+        type F = f32;
+
+        let a = &tensor::GpuTensor::new::<F>(vec![100, 100, 100], &[1.; 1_000_000]);
+        let b = &tensor::GpuTensor::new::<F>(vec![100, 100, 100], &[2.; 1_000_000]);
+        let c = &tensor::GpuTensor::new::<F>(vec![100, 100, 100], &[0.5; 1_000_000]);
+        let d = &mut tensor::GpuTensor::with_capacity(a.capacity());
+
+        assert!(
+            d.set(a + b + c).compute().block_on().0
+                == super::dtype::DtypeVec::F32(ndarray::Array::from_elem(
+                    ndarray::IxDyn(&[100, 100, 100]),
+                    3.5
+                ))
+        );
+        assert!(
+            d.increment(a).compute().join().0
+                == super::dtype::DtypeVec::F32(ndarray::Array::from_elem(
+                    ndarray::IxDyn(&[100, 100, 100]),
+                    4.5
+                ))
+        );
+    }
+
+    #[test]
+    fn mul_add_f32() {
+        use pollster::FutureExt;
+
+        type F = f32;
+
+        let a = &tensor::GpuTensor::new::<F>(vec![2, 2], &[2.; 4]);
+        let b = &tensor::GpuTensor::new::<F>(vec![2, 2], &[2.; 4]);
+        let c = &tensor::GpuTensor::new::<F>(vec![2, 2], &[0.75; 4]);
+        let d = &mut tensor::GpuTensor::with_capacity(a.capacity());
+
+        assert!(
+            d.set(a * b * c + c).compute().block_on().0
+                == super::dtype::DtypeVec::F32(ndarray::Array::from_elem(
+                    ndarray::IxDyn(&[2, 2]),
+                    3.75
+                ))
+        );
+        assert!(
+            d.mul_in_place(a).compute().join().0
+                == super::dtype::DtypeVec::F32(ndarray::Array::from_elem(
+                    ndarray::IxDyn(&[2, 2]),
+                    7.5
+                ))
+        );
+    }
+
+    #[test]
+    fn integers_wrap() {
+        let a = &tensor::GpuTensor::new::<i32>(vec![3], &[i32::MAX, -7, 3]);
+        let b = &tensor::GpuTensor::new::<i32>(vec![3], &[1, 2, -4]);
+        let c = &mut tensor::GpuTensor::with_capacity(a.capacity());
+        assert!(c.set(a + b).download() == DtypeVec::I32(array(&[3], &[i32::MIN, -5, -1])));
+        assert!(c.mul_in_place(b).download() == DtypeVec::I32(array(&[3], &[i32::MIN, -10, 4])));
+
+        let a = &tensor::GpuTensor::new::<u32>(vec![2], &[u32::MAX, 5]);
+        let b = &tensor::GpuTensor::new::<u32>(vec![2], &[2, 3]);
+        let c = &mut tensor::GpuTensor::with_capacity(a.capacity());
+        assert!(c.set(a * b).compute().join().0 == DtypeVec::U32(array(&[2], &[u32::MAX - 1, 15])));
+        assert!(c.increment(b).download() == DtypeVec::U32(array(&[2], &[0, 18])));
+    }
+
+    #[test]
+    fn bf16_packed() {
+        use half::bf16;
+
+        let values = |values: &[f32]| {
+            values
+                .iter()
+                .map(|&v| bf16::from_f32(v))
+                .collect::<Vec<_>>()
+        };
+        let a_values = values(&[1.5, -2., 3.140625]);
+        let b_values = values(&[0.25, 1e-3, 3.]);
+
+        let a = &tensor::GpuTensor::new::<bf16>(vec![3], &a_values);
+        let b = &tensor::GpuTensor::new::<bf16>(vec![3], &b_values);
+        let c = &mut tensor::GpuTensor::with_capacity(a.capacity());
+
+        let expected = |op: fn(bf16, bf16) -> bf16| {
+            DtypeVec::BF16(ndarray::Array::from_shape_fn(ndarray::IxDyn(&[3]), |idx| {
+                op(a_values[idx[0]], b_values[idx[0]])
+            }))
+        };
+        assert!(c.set(a + b).download() == expected(|a, b| a + b));
+        assert!(c.set(a * b).compute().join().0 == expected(|a, b| a * b));
+        assert!(c.increment(b).download() == expected(|a, b| a * b + b));
+
+        let mask = &mut tensor::GpuTensor::with_capacity(12);
+        assert!(mask.gt(a, b).download() == DtypeVec::Bool(array(&[3], &[true, false, true])));
+    }
+
+    #[test]
+    fn quantized() {
+        let values = (0..100).map(|i| (i as f32 - 40.) / 8.).collect::<Vec<_>>();
+        let a = &tensor::GpuTensor::new::<f32>(vec![4, 25], &values);
+        let ones = &tensor::GpuTensor::new::<f32>(vec![4, 25], &[1.; 100]);
+        let b = &mut tensor::GpuTensor::with_capacity(a.capacity());
+
+        let close = |result: DtypeVec, tolerance: f32, expected: &dyn Fn(f32) -> f32| {
+            let DtypeVec::F32(result) = result else {
+                panic!("expected f32 values, got {result:?}");
+            };
+            assert!(result.shape() == [4, 25]);
+            result
+                .iter()
+                .zip(&values)
+                .all(|(&r, &v)| (r - expected(v)).abs() <= tolerance)
+        };
+
+        for (format, tolerance) in [
+            (QuantFormat::INT8, 7.4 / 127.),
+            (QuantFormat::INT4.with_block_size(16), 7.4 / 7.),
+            (QuantFormat::INT4.with_scale(Dtype::F16), 7.4 / 7.),
+        ] {
+            let q = &QuantizedTensor::quantize(a, format);
+            assert!(q.size() < a.capacity() / 2);
+
+            assert!(close(
+                b.dequantize(q, Dtype::F32).download(),
+                tolerance,
+                &|v| v
+            ));
+            assert!(close(b.set(ones + q).download(), tolerance, &|v| v + 1.));
+            assert!(close(b.set(a * q).download(), 8. * tolerance, &|v| v * v));
+            assert!(close(
+                b.set(a * ones + q).compute().join().0,
+                tolerance,
+                &|v| 2. * v
+            ));
+        }
+
+        let q = &QuantizedTensor::quantize(a, QuantFormat::INT8);
+        assert!(
+            b.dequantize(q, Dtype::BF16).dtype() == Dtype::BF16
+                && b.try_dequantize(q, Dtype::I32).err()
+                    == Some(error::GpuMatError::UnsupportedDtype(Dtype::I32))
+        );
+        assert!(matches!(
+            QuantizedTensor::try_quantize(a, QuantFormat::INT8.with_block_size(12)),
+            Err(error::GpuMatError::InvalidQuantization(_))
+        ));
+
+        let row = &tensor::GpuTensor::new::<f32>(vec![25], &[1.; 25]);
+        assert!(matches!(
+            b.try_add_quantized(row, q),
+            Err(error::GpuMatError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn compare_masks() {
+        let mask = |values: Vec<bool>| DtypeVec::Bool(array(&[4], &values));
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![4], &[1., 2., 3., f32::NAN]);
+        let b = &tensor::GpuTensor::new::<f32>(vec![4], &[2., 2., 2., 2.]);
+        let c = &mut tensor::GpuTensor::with_capacity(a.capacity());
+        assert!(c.lt(a, b).download() == mask(vec![true, false, false, false]));
+        assert!(c.eq(a, b).download() == mask(vec![false, true, false, false]));
+        assert!(c.ne(a, b).download() == mask(vec![true, false, true, true]));
+        assert!(c.ge(a, b).compute().join().0 == mask(vec![false, true, true, false]));
+        assert!(c.le_scalar(a, 2.).download() == mask(vec![true, true, false, false]));
+        assert!(c.ne_scalar(a, 3.).download() == mask(vec![true, true, false, true]));
+        let halves = [1., 2.5, 3., -1.].map(half::f16::from_f32);
+        let halves = &tensor::GpuTensor::new::<half::f16>(vec![4], &halves);
+        assert!(c.gt(halves, a).download() == mask(vec![false, true, false, false]));
+        assert!(
+            c.try_compare_scalar(CompareOp::Lt, a, 1i32).err()
+                == Some(error::GpuMatError::DtypeMismatch {
+                    lhs: Dtype::F32,
+                    rhs: Dtype::I32
+                })
+        );
+
+        let a = &tensor::GpuTensor::new::<i32>(vec![4], &[-3, 0, 5, 7]);
+        let b = &tensor::GpuTensor::new::<i32>(vec![4], &[0, 0, 0, 8]);
+        assert!(c.gt(a, b).download() == mask(vec![false, false, true, false]));
+        assert!(c.gt_scalar(a, 0i32).download() == mask(vec![false, false, true, true]));
+        assert!(c.le(a, b).download() == mask(vec![true, true, false, true]));
+
+        let d = &tensor::GpuTensor::upload(&mask(vec![true, true, false, false]));
+        let e = &mut tensor::GpuTensor::with_capacity(d.capacity());
+        assert!(e.eq(c, d).download() == mask(vec![true, true, true, false]));
+        assert!(c.try_add(d, d).err() == Some(error::GpuMatError::UnsupportedDtype(Dtype::Bool)));
+    }
+
+    #[test]
+    fn complex_values() {
+        use num_complex::Complex;
+
+        let a_values = [Complex::new(3., -4.), Complex::new(0., 1.)];
+        let b_values = [Complex::new(2., 0.5), Complex::new(0., 1.)];
+        let a = &tensor::GpuTensor::new::<Complex<f64>>(vec![2], &a_values);
+        let b = &tensor::GpuTensor::new::<Complex<f64>>(vec![2], &b_values);
+        let c = &mut tensor::GpuTensor::with_capacity(32);
+
+        assert!(
+            c.set(a * b + a).download()
+                == DtypeVec::C128(array(
+                    &[2],
+                    &[
+                        a_values[0] * b_values[0] + a_values[0],
+                        a_values[1] * b_values[1] + a_values[1]
+                    ]
+                ))
+        );
+        assert!(
+            c.conj(a).download()
+                == DtypeVec::C128(array(&[2], &[a_values[0].conj(), -a_values[1]]))
+        );
+        assert!(c.abs(a).download() == DtypeVec::F64(array(&[2], &[5., 1.])));
+        assert!(
+            c.try_compare(CompareOp::Ge, a, b).err()
+                == Some(error::GpuMatError::UnsupportedDtype(dtype::Dtype::C128))
+        );
+    }
+
+    #[test]
+    fn promotion() {
+        use dtype::Dtype;
+        use num_complex::Complex;
+
+        assert!(Dtype::F16.promote(Dtype::F32) == Some(Dtype::F32));
+        assert!(Dtype::F16.promote(Dtype::BF16) == Some(Dtype::F32));
+        assert!(Dtype::F32.promote(Dtype::F64) == Some(Dtype::F64));
+        assert!(Dtype::BF16.promote(Dtype::C64) == Some(Dtype::C64));
+        assert!(Dtype::F64.promote(Dtype::C64) == Some(Dtype::C128));
+        assert!(Dtype::I32.promote(Dtype::F32).is_none());
+        assert!(Dtype::U32.promote(Dtype::Bool).is_none());
+
+        let a = &tensor::GpuTensor::new::<half::f16>(vec![2], &[half::f16::from_f32(0.5); 2]);
+        let b = &tensor::GpuTensor::new::<f64>(vec![2], &[0.25, -1.]);
+        let c = &tensor::GpuTensor::new::<Complex<f32>>(vec![2], &[Complex::new(1., 1.); 2]);
+        let d = &mut tensor::GpuTensor::with_capacity(32);
+
+        assert!(d.set(a * b + a).download() == DtypeVec::F64(array(&[2], &[0.625, 0.])));
+        assert!(
+            d.set(c + b).download()
+                == DtypeVec::C128(array(&[2], &[Complex::new(1.25, 1.), Complex::new(0., 1.)]))
+        );
+    }
+
+    #[test]
+    fn sub_div() {
+        use num_complex::Complex;
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![3], &[6., 1., -3.]);
+        let b = &tensor::GpuTensor::new::<f32>(vec![3], &[2., 4., 0.5]);
+        let c = &tensor::GpuTensor::new::<half::f16>(vec![3], &[half::f16::ONE; 3]);
+        let out = &mut tensor::GpuTensor::with_capacity(12);
+        assert!(out.set(a * b - c).download() == DtypeVec::F32(array(&[3], &[11., 3., -2.5])));
+        assert!(out.set(c - a / b).download() == DtypeVec::F32(array(&[3], &[-2., 0.75, 7.])));
+        assert!(out.set(c / (b - c)).download() == DtypeVec::F32(array(&[3], &[1., 1. / 3., -2.])));
+
+        let a = &tensor::GpuTensor::new::<i32>(vec![4], &[7, -7, 5, i32::MIN]);
+        let b = &tensor::GpuTensor::new::<i32>(vec![4], &[2, 2, 0, -1]);
+        let out = &mut tensor::GpuTensor::with_capacity(16);
+        assert!(out.set(a / b).download() == DtypeVec::I32(array(&[4], &[3, -3, 5, i32::MIN])));
+        assert!(out.set(b - a).download() == DtypeVec::I32(array(&[4], &[-5, 9, -5, i32::MAX])));
+
+        let a = &tensor::GpuTensor::new::<Complex<f64>>(vec![1], &[Complex::new(1., 2.)]);
+        let b = &tensor::GpuTensor::new::<Complex<f64>>(vec![1], &[Complex::new(0., 1.)]);
+        let out = &mut tensor::GpuTensor::with_capacity(16);
+        assert!(out.set(a / b).download() == DtypeVec::C128(array(&[1], &[Complex::new(2., -1.)])));
+    }
+
+    #[test]
+    fn broadcasting() {
+        use error::GpuMatError;
+
+        let x = &tensor::GpuTensor::new::<i32>(vec![2, 3], &[1, 2, 3, 4, 5, 6]);
+        let bias = &tensor::GpuTensor::new::<i32>(vec![3], &[10, 20, 30]);
+        let column = &tensor::GpuTensor::new::<i32>(vec![2, 1], &[1, -1]);
+        let out = &mut tensor::GpuTensor::with_capacity(24);
+        assert!(
+            out.set(x * column + bias).download()
+                == DtypeVec::I32(array(&[2, 3], &[11, 22, 33, 6, 15, 24]))
+        );
+        let mask = &mut tensor::GpuTensor::with_capacity(24);
+        let expected = [false, true, true, true, true, true];
+        assert!(mask.gt(x, column).download() == DtypeVec::Bool(array(&[2, 3], &expected)));
+        assert!(
+            out.set(column - bias).download()
+                == DtypeVec::I32(array(&[2, 3], &[-9, -19, -29, -11, -21, -31]))
+        );
+
+        let out = &mut tensor::GpuTensor::new::<i32>(vec![3], &[0; 3]);
+        assert!(
+            out.try_increment(x).err()
+                == Some(GpuMatError::ShapeMismatch {
+                    lhs: vec![3],
+                    rhs: vec![2, 3]
+                })
+        );
+    }
+
+    #[test]
+    fn min_max_clamp_select() {
+        use dtype::Dtype;
+        use error::GpuMatError;
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![2, 3], &[1., -2., 4., f32::NAN, 0.5, -0.5]);
+        let b = &tensor::GpuTensor::new::<f32>(vec![3], &[0., f32::NAN, 1.]);
+        let out = &mut tensor::GpuTensor::with_capacity(24);
+        let DtypeVec::F32(min) = out.minimum(b, a).download() else {
+            panic!("expected f32 values");
+        };
+        let min = min
+            .iter()
+            .map(|&x| (!x.is_nan()).then_some(x))
+            .collect::<Vec<_>>();
+        assert!(min == [Some(0.), None, Some(1.), None, None, Some(-0.5)]);
+
+        let x = &tensor::GpuTensor::new::<f32>(vec![2, 2], &[1., -2., f32::NAN, 3.]);
+        let lo = &tensor::GpuTensor::new::<f32>(vec![2], &[-1., 0.]);
+        let hi = &tensor::GpuTensor::new::<f32>(vec![2, 1], &[0.5, 2.]);
+        let zeros = &tensor::GpuTensor::new::<f32>(vec![2, 2], &[0.; 4]);
+        let valid = &mut tensor::GpuTensor::with_capacity(16);
+        valid.eq(x, x);
+        let clipped = &mut tensor::GpuTensor::with_capacity(16);
+        clipped.set((x * 2f32).clamp(lo, hi));
+        assert!(
+            out.select(valid, clipped, zeros).download()
+                == DtypeVec::F32(array(&[2, 2], &[0.5, 0., 0., 2.]))
+        );
+        assert!(
+            out.clamp_scalar(zeros, 1f32, 2f32).download()
+                == DtypeVec::F32(array(&[2, 2], &[1.; 4]))
+        );
+
+        let ints = &tensor::GpuTensor::new::<i32>(vec![2], &[1, 2]);
+        assert!(
+            out.try_maximum(ints, ints).err() == Some(GpuMatError::UnsupportedDtype(Dtype::I32))
+        );
+        assert!(
+            out.try_select(x, x, zeros).err()
+                == Some(GpuMatError::DtypeMismatch {
+                    lhs: Dtype::Bool,
+                    rhs: Dtype::F32
+                })
+        );
+    }
+
+    #[test]
+    fn scalar_ops() {
+        use dtype::Dtype;
+        use error::GpuMatError;
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![3], &[1., -2., 4.]);
+        let out = &mut tensor::GpuTensor::with_capacity(12);
+        assert!(
+            out.set((a * 0.5f32 + 1f32) * 4f32 + a).download()
+                == DtypeVec::F32(array(&[3], &[7., -2., 16.]))
+        );
+        assert!(out.set(a * 0.5 + 1.).download() == DtypeVec::F32(array(&[3], &[1.5, 0., 3.])));
+        assert!(
+            out.try_add_scalar(a, 1i32).err()
+                == Some(GpuMatError::DtypeMismatch {
+                    lhs: Dtype::F32,
+                    rhs: Dtype::I32
+                })
+        );
+
+        let a = &mut tensor::GpuTensor::new::<u32>(vec![2], &[3, u32::MAX]);
+        assert!(a.shift(1u32).scale(3u32).download() == DtypeVec::U32(array(&[2], &[12, 0])));
+    }
+
+    #[test]
+    fn unary_math() {
+        use dtype::Dtype;
+        use error::GpuMatError;
+        use ops::UnaryOp;
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![4], &[0.5, -2.5, 1.5, 0.]);
+        let out = &mut tensor::GpuTensor::with_capacity(16);
+        assert!(out.round(a).download() == DtypeVec::F32(array(&[4], &[0., -2., 2., 0.])));
+        assert!(out.sign(a).download() == DtypeVec::F32(array(&[4], &[1., -1., 1., 0.])));
+        assert!(
+            out.abs(a).sqrt_in_place().download()
+                == out
+                    .unary(UnaryOp::Abs, a)
+                    .unary_in_place(UnaryOp::Sqrt)
+                    .download()
+        );
+        assert!(
+            out.neg(a).exp_in_place().download()
+                == DtypeVec::F32(array(&[4], &[0.5f32, -2.5, 1.5, 0.].map(|x| (-x).exp())))
+        );
+
+        let a = &tensor::GpuTensor::new::<i32>(vec![3], &[-3, 0, i32::MIN]);
+        let out = &mut tensor::GpuTensor::with_capacity(12);
+        assert!(out.abs(a).download() == DtypeVec::I32(array(&[3], &[3, 0, i32::MIN])));
+        assert!(out.sign(a).neg_in_place().download() == DtypeVec::I32(array(&[3], &[1, 0, 1])));
+        assert!(
+            out.try_unary(UnaryOp::Log, a).err() == Some(GpuMatError::UnsupportedDtype(Dtype::I32))
+        );
+    }
+
+    #[test]
+    fn activations() {
+        use dtype::Dtype;
+        use error::GpuMatError;
+        use half::f16;
+        use ops::Activation;
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![4], &[-2., 0., 0.5, 800.]);
+        let out = &mut tensor::GpuTensor::with_capacity(16);
+        assert!(out.relu(a).download() == DtypeVec::F32(array(&[4], &[0., 0., 0.5, 800.])));
+        assert!(
+            out.leaky_relu(a, 0.5).download() == DtypeVec::F32(array(&[4], &[-1., 0., 0.5, 800.]))
+        );
+        let sigmoid = [-2f32, 0., 0.5, 800.].map(|x| 1. / (1. + (-x).exp()));
+        assert!(out.sigmoid(a).download() == DtypeVec::F32(array(&[4], &sigmoid)));
+        let silu = [-2f32, 0., 0.5, 800.].map(|x| x / (1. + (-x).exp()));
+        assert!(out.silu(a).download() == DtypeVec::F32(array(&[4], &silu)));
+        // exp(800) overflows f32, which softplus must not.
+        let DtypeVec::F32(softplus) = out.softplus(a).download() else {
+            panic!("expected f32 values");
+        };
+        assert!(softplus[1] == 1f32.ln_1p() && softplus[3] == 800.);
+
+        let w = &tensor::GpuTensor::new::<f32>(vec![2], &[1., -2.]);
+        let b = &tensor::GpuTensor::new::<f32>(vec![2], &[-1., 1.]);
+        let x = &tensor::GpuTensor::new::<f32>(vec![2], &[2., 1.]);
+        let d = &mut tensor::GpuTensor::with_capacity(8);
+        assert!(
+            d.set((x * w + b).relu()).compute().join().0 == DtypeVec::F32(array(&[2], &[1., 0.]))
+        );
+
+        let a = &mut tensor::GpuTensor::new::<f16>(vec![2], &[f16::ONE, f16::NEG_ONE]);
+        let expected = [0.841192f32, -0.158808].map(f16::from_f32);
+        assert!(a.gelu_in_place().download() == DtypeVec::F16(array(&[2], &expected)));
+
+        let a = &tensor::GpuTensor::new::<u32>(vec![2], &[1, 2]);
+        assert!(
+            out.try_activation(Activation::Gelu, a).err()
+                == Some(GpuMatError::UnsupportedDtype(Dtype::U32))
+        );
+    }
+
+    #[test]
+    fn casts() {
+        use dtype::Dtype;
+        use half::f16;
+
+        let a = &tensor::GpuTensor::new::<f64>(vec![3], &[1. + 1e-12, -0.1, 1e5]);
+        let b = &mut tensor::GpuTensor::with_capacity(24);
+        let c = &mut tensor::GpuTensor::with_capacity(24);
+
+        b.set(a.cast_to(Dtype::F16));
+        c.cast(b, Dtype::F32);
+        assert!(
+            c.download()
+                == DtypeVec::F32(array(
+                    &[3],
+                    &[1., f16::from_f64(-0.1).to_f32(), f32::INFINITY]
+                ))
+        );
+        assert!(
+            b.try_cast(&tensor::GpuTensor::new::<u32>(vec![1], &[1]), Dtype::F32)
+                .err()
+                == Some(error::GpuMatError::UnsupportedDtype(Dtype::U32))
+        );
+    }
+
+    #[test]
+    fn invalid_operands() {
+        use error::GpuMatError;
+
+        assert!(matches!(
+            tensor::GpuTensor::try_new::<f32>(vec![2, 2], &[1.; 3]),
+            Err(GpuMatError::LengthMismatch { .. })
+        ));
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![2, 2], &[1.; 4]);
+        let b = &tensor::GpuTensor::new::<i32>(vec![2, 2], &[1; 4]);
+        let c = &tensor::GpuTensor::new::<f32>(vec![4], &[1.; 4]);
+        let d = &mut tensor::GpuTensor::with_capacity(8);
+
+        assert!(
+            d.try_add(a, b).err()
+                == Some(GpuMatError::DtypeMismatch {
+                    lhs: dtype::Dtype::F32,
+                    rhs: dtype::Dtype::I32
+                })
+        );
+        assert!(
+            d.try_mul(a, c).err()
+                == Some(GpuMatError::ShapeMismatch {
+                    lhs: vec![2, 2],
+                    rhs: vec![4]
+                })
+        );
+        assert!(
+            d.try_set(a + a).err()
+                == Some(GpuMatError::InsufficientCapacity {
+                    required: 4,
+                    capacity: 2
+                })
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use half::f16;
+use ndarray::{Array, IxDyn};
+
+use super::{
+    dtype::{Dtype, DtypeVec},
+    error::GpuMatError,
+    quantization::QuantFormat,
+    tensor::GpuTensor,
+};
+
+/// A read only tensor stored as blocks of small integers, see [`QuantFormat`].
+///
+/// Use it as the right hand side of [`GpuTensor::add_quantized`], [`GpuTensor::mul_quantized`]
+/// and their operator forms, or expand it with [`GpuTensor::dequantize`]. Those are the only ops
+/// taking a quantized operand, and they need the float tensor on the other side to have exactly
+/// its shape: quantized operands are not broadcast, a mismatch fails with
+/// [`GpuMatError::ShapeMismatch`]. Dequantize first to use any other op.
+///
+/// Integers are kept one per byte rather than packed like on the GPU, but round the same way.
+pub struct QuantizedTensor {
+    shape: Vec<u32>,
+    format: QuantFormat,
+    values: Vec<i8>,
+    scales: Vec<f32>,
+}
+
+impl QuantizedTensor {
+    pub fn quantize(tensor: &GpuTensor, format: QuantFormat) -> Self {
+        Self::try_quantize(tensor, format).unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_quantize(tensor: &GpuTensor, format: QuantFormat) -> Result<Self, GpuMatError> {
+        format.check()?;
+        GpuMatError::check_float(tensor.dtype())?;
+
+        let DtypeVec::F32(floats) = tensor.data().cast(Dtype::F32) else {
+            unreachable!("floats cast to f32");
+        };
+        let floats = floats.iter().copied().collect::<Vec<_>>();
+        let qmax = format.bits.max() as f32;
+
+        let mut values = Vec::with_capacity(floats.len());
+        let mut scales = Vec::with_capacity(floats.len().div_ceil(format.block_size as usize));
+        for block in floats.chunks(format.block_size as usize) {
+            let absmax = block.iter().fold(0f32, |max, value| max.max(value.abs()));
+            let scale = match format.scale {
+                Dtype::F16 => f16::from_f32(absmax / qmax).to_f32(),
+                _ => absmax / qmax,
+            };
+            let inverse = if scale > 0. { 1. / scale } else { 0. };
+
+            values.extend(
+                block
+                    .iter()
+                    .map(|value| (value * inverse).round_ties_even().clamp(-qmax, qmax) as i8),
+            );
+            scales.push(scale);
+        }
+
+        Ok(Self {
+            shape: tensor.data().shape(),
+            format,
+            values,
+            scales,
+        })
+    }
+
+    pub fn shape(&self) -> &[u32] {
+        &self.shape
+    }
+
+    pub fn format(&self) -> QuantFormat {
+        self.format
+    }
+
+    /// Bytes of memory held by the quantized values and their scales.
+    pub fn size(&self) -> u64 {
+        (self.values.len() + self.scales.len() * size_of::<f32>()) as u64
+    }
+
+    /// The values scaled back, converted to the float `dtype` like the GPU kernels do.
+    pub(super) fn dequantized(&self, dtype: Dtype) -> DtypeVec {
+        let block_size = self.format.block_size as usize;
+        let shape = self.shape.iter().map(|&n| n as usize).collect::<Vec<_>>();
+        let floats = self
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| value as f32 * self.scales[i / block_size])
+            .collect();
+
+        DtypeVec::F32(Array::from_shape_vec(IxDyn(&shape), floats).unwrap()).cast(dtype)
+    }
+}
@@ -0,0 +1,1272 @@
+use std::borrow::Cow;
+
+use super::{
+    dtype::Dtype,
+    dtype::DtypeVec,
+    dtype::Dtyped,
+    error::GpuMatError,
+    handle::{ComputeHandle, INTERMEDIATES_MAP},
+    ops::{Activation, CompareOp, UnaryOp, activation_methods, unary_methods},
+    quantized::QuantizedTensor,
+    vec::{self, CpuVec, Element, unary, zip_map, zip_mask},
+};
+
+pub struct GpuTensor {
+    shape: Vec<u32>,
+    buffer: CpuVec,
+}
+
+impl GpuTensor {
+    pub fn new<F: Dtyped>(shape: Vec<u32>, values: &[F]) -> Self {
+        Self::try_new(shape, values).unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_new<F: Dtyped>(shape: Vec<u32>, values: &[F]) -> Result<Self, GpuMatError> {
+        GpuMatError::check_len(&shape, values.len())?;
+
+        Ok(Self {
+            buffer: CpuVec::new_init(&shape, values),
+            shape,
+        })
+    }
+
+    pub fn with_capacity(capacity: u64) -> Self {
+        Self {
+            shape: Vec::new(),
+            buffer: CpuVec::new_uninit::<f32>(capacity),
+        }
+    }
+
+    pub fn upload(values: &DtypeVec) -> Self {
+        Self {
+            shape: values.shape(),
+            buffer: CpuVec::from_data(values.clone()),
+        }
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.buffer.capacity()
+    }
+
+    pub fn capacity_elements(&self) -> u64 {
+        self.buffer.capacity_elements()
+    }
+
+    pub fn dtype(&self) -> Dtype {
+        self.buffer.dtype()
+    }
+
+    pub fn compute(&self) -> ComputeHandle {
+        ComputeHandle::new(self.buffer.data().clone())
+    }
+
+    pub fn download(&self) -> DtypeVec {
+        self.buffer.data().clone()
+    }
+
+    pub(super) fn data(&self) -> &DtypeVec {
+        self.buffer.data()
+    }
+
+    pub fn save_intermediate(&self, name: &'static str) -> &Self {
+        INTERMEDIATES_MAP
+            .lock()
+            .insert(name, self.buffer.data().clone());
+
+        self
+    }
+
+    pub fn save_intermediate_mut(&mut self, name: &'static str) -> &mut Self {
+        self.save_intermediate(name);
+        self
+    }
+
+    pub fn set(&mut self, seter: GpuTensorSetterFn<impl SetterFn>) -> &mut Self {
+        self.try_set(seter)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_set(
+        &mut self,
+        seter: GpuTensorSetterFn<impl SetterFn>,
+    ) -> Result<&mut Self, GpuMatError> {
+        seter.0(self)?;
+        Ok(self)
+    }
+
+    /// Prepares the output of a binary operation on operands broadcast to a common shape, along
+    /// with the [`Dtype::Bool`] `mask` of a select, and returns their promoted dtype.
+    fn prepare_output(
+        &mut self,
+        lhs: &Self,
+        rhs: &Self,
+        mask: Option<&Self>,
+    ) -> Result<Dtype, GpuMatError> {
+        let dtype = GpuMatError::promote(lhs.dtype(), rhs.dtype())?;
+        GpuMatError::check_numeric(dtype)?;
+        self.prepare_broadcast(lhs, rhs, mask, dtype)?;
+        Ok(dtype)
+    }
+
+    /// Like [`GpuTensor::prepare_output`] for an output of `dtype` rather than the promoted one,
+    /// such as the mask of a comparison.
+    fn prepare_broadcast(
+        &mut self,
+        lhs: &Self,
+        rhs: &Self,
+        mask: Option<&Self>,
+        dtype: Dtype,
+    ) -> Result<(), GpuMatError> {
+        let mut shape = GpuMatError::broadcast(&lhs.shape, &rhs.shape)?;
+        if let Some(mask) = mask {
+            GpuMatError::check_dtypes(Dtype::Bool, mask.dtype())?;
+            shape = GpuMatError::broadcast(&mask.shape, &shape)?;
+        }
+        self.prepare_output_as(&shape, dtype)
+    }
+
+    fn prepare_output_as(&mut self, shape: &[u32], dtype: Dtype) -> Result<(), GpuMatError> {
+        GpuMatError::check_capacity(shape, self.capacity() / dtype.size() as u64)?;
+
+        self.shape.clear();
+        self.shape.extend_from_slice(shape);
+        Ok(())
+    }
+
+    fn prepare_unary(&mut self, src: &Self, dtype: Dtype) -> Result<(), GpuMatError> {
+        GpuMatError::check_capacity(&src.shape, self.capacity() / dtype.size() as u64)?;
+
+        self.shape.clear();
+        self.shape.extend_from_slice(&src.shape);
+        Ok(())
+    }
+
+    fn prepare_in_place(&self, by: &Self) -> Result<(), GpuMatError> {
+        GpuMatError::check_numeric(self.dtype())?;
+        if GpuMatError::promote(self.dtype(), by.dtype())? != self.dtype() {
+            return Err(GpuMatError::DtypeMismatch {
+                lhs: self.dtype(),
+                rhs: by.dtype(),
+            });
+        }
+        GpuMatError::check_broadcast_to(&self.shape, &by.shape)
+    }
+
+    /// The values of the tensor as `dtype`, converting them only if needed.
+    fn promoted(&self, dtype: Dtype) -> Cow<'_, DtypeVec> {
+        if self.dtype() == dtype {
+            Cow::Borrowed(self.buffer.data())
+        } else {
+            Cow::Owned(self.buffer.data().cast(dtype))
+        }
+    }
+
+    fn prepare_quantized(&mut self, lhs: &Self, rhs: &QuantizedTensor) -> Result<(), GpuMatError> {
+        GpuMatError::check_float(lhs.dtype())?;
+        GpuMatError::check_shapes(&lhs.shape, rhs.shape())?;
+        GpuMatError::check_capacity(&lhs.shape, self.capacity() / lhs.dtype().size() as u64)?;
+
+        self.shape.clear();
+        self.shape.extend_from_slice(&lhs.shape);
+        Ok(())
+    }
+
+    fn prepare_in_place_quantized(&self, by: &QuantizedTensor) -> Result<(), GpuMatError> {
+        GpuMatError::check_float(self.dtype())?;
+        GpuMatError::check_shapes(&self.shape, by.shape())
+    }
+
+    pub fn add(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_add(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_add(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        let dtype = self.prepare_output(lhs, rhs, None)?;
+        self.buffer.set_data(zip_map!(
+            &*lhs.promoted(dtype),
+            &*rhs.promoted(dtype),
+            Element::add
+        ));
+
+        Ok(self)
+    }
+
+    pub fn increment(&mut self, by: &Self) -> &mut Self {
+        self.try_increment(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_increment(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        self.prepare_in_place(by)?;
+        self.buffer.set_data(zip_map!(
+            self.buffer.data(),
+            &*by.promoted(self.dtype()),
+            Element::add
+        ));
+
+        Ok(self)
+    }
+
+    pub fn mul(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_mul(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_mul(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        let dtype = self.prepare_output(lhs, rhs, None)?;
+        self.buffer.set_data(zip_map!(
+            &*lhs.promoted(dtype),
+            &*rhs.promoted(dtype),
+            Element::mul
+        ));
+
+        Ok(self)
+    }
+
+    pub fn mul_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_mul_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_mul_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        self.prepare_in_place(by)?;
+        self.buffer.set_data(zip_map!(
+            self.buffer.data(),
+            &*by.promoted(self.dtype()),
+            Element::mul
+        ));
+
+        Ok(self)
+    }
+
+    pub fn sub(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_sub(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_sub(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        let dtype = self.prepare_output(lhs, rhs, None)?;
+        self.buffer.set_data(zip_map!(
+            &*lhs.promoted(dtype),
+            &*rhs.promoted(dtype),
+            Element::sub
+        ));
+
+        Ok(self)
+    }
+
+    pub fn decrement(&mut self, by: &Self) -> &mut Self {
+        self.try_decrement(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_decrement(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        self.prepare_in_place(by)?;
+        self.buffer.set_data(zip_map!(
+            self.buffer.data(),
+            &*by.promoted(self.dtype()),
+            Element::sub
+        ));
+
+        Ok(self)
+    }
+
+    pub fn rsub_in_place(&mut self, lhs: &Self) -> &mut Self {
+        self.try_rsub_in_place(lhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Replaces every element `x` of the tensor with `lhs - x`.
+    pub fn try_rsub_in_place(&mut self, lhs: &Self) -> Result<&mut Self, GpuMatError> {
+        self.prepare_in_place(lhs)?;
+        self.buffer.set_data(zip_map!(
+            &*lhs.promoted(self.dtype()),
+            self.buffer.data(),
+            Element::sub
+        ));
+
+        Ok(self)
+    }
+
+    pub fn div(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_div(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `lhs / rhs`. Integers round toward zero, and dividing one by zero gives `lhs` like
+    /// on the GPU.
+    pub fn try_div(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        let dtype = self.prepare_output(lhs, rhs, None)?;
+        self.buffer.set_data(zip_map!(
+            &*lhs.promoted(dtype),
+            &*rhs.promoted(dtype),
+            Element::div
+        ));
+
+        Ok(self)
+    }
+
+    pub fn div_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_div_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_div_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        self.prepare_in_place(by)?;
+        self.buffer.set_data(zip_map!(
+            self.buffer.data(),
+            &*by.promoted(self.dtype()),
+            Element::div
+        ));
+
+        Ok(self)
+    }
+
+    pub fn rdiv_in_place(&mut self, lhs: &Self) -> &mut Self {
+        self.try_rdiv_in_place(lhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Replaces every element `x` of the tensor with `lhs / x`.
+    pub fn try_rdiv_in_place(&mut self, lhs: &Self) -> Result<&mut Self, GpuMatError> {
+        self.prepare_in_place(lhs)?;
+        self.buffer.set_data(zip_map!(
+            &*lhs.promoted(self.dtype()),
+            self.buffer.data(),
+            Element::div
+        ));
+
+        Ok(self)
+    }
+
+    pub fn compare(&mut self, op: CompareOp, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_compare(op, lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes a [`Dtype::Bool`] mask holding `lhs op rhs` for every element. Operands promote and
+    /// broadcast like for [`GpuTensor::try_add`], and complex ones cannot be compared.
+    pub fn try_compare(
+        &mut self,
+        op: CompareOp,
+        lhs: &Self,
+        rhs: &Self,
+    ) -> Result<&mut Self, GpuMatError> {
+        let dtype = GpuMatError::promote(lhs.dtype(), rhs.dtype())?;
+        GpuMatError::check_ordered(dtype)?;
+        self.prepare_broadcast(lhs, rhs, None, Dtype::Bool)?;
+        self.buffer.set_data(zip_mask!(
+            &*lhs.promoted(dtype),
+            &*rhs.promoted(dtype),
+            |a, b| op.apply(a, b)
+        ));
+
+        Ok(self)
+    }
+
+    pub fn compare_scalar<F: Dtyped>(&mut self, op: CompareOp, lhs: &Self, scalar: F) -> &mut Self {
+        self.try_compare_scalar(op, lhs, scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes a [`Dtype::Bool`] mask holding `lhs op scalar` for every element, converting
+    /// `scalar` like [`GpuTensor::try_add_scalar`].
+    pub fn try_compare_scalar<F: Dtyped>(
+        &mut self,
+        op: CompareOp,
+        lhs: &Self,
+        scalar: F,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_ordered(lhs.dtype())?;
+        GpuMatError::check_scalar(lhs.dtype(), F::dtype())?;
+        self.prepare_unary(lhs, Dtype::Bool)?;
+        self.buffer.set_data(zip_mask!(
+            lhs.buffer.data(),
+            &vec::scalar(scalar, lhs.dtype()),
+            |a, b| op.apply(a, b)
+        ));
+
+        Ok(self)
+    }
+
+    pub fn eq(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Eq, lhs, rhs)
+    }
+
+    pub fn ne(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Ne, lhs, rhs)
+    }
+
+    pub fn lt(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Lt, lhs, rhs)
+    }
+
+    pub fn le(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Le, lhs, rhs)
+    }
+
+    pub fn gt(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Gt, lhs, rhs)
+    }
+
+    pub fn ge(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Ge, lhs, rhs)
+    }
+
+    pub fn eq_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Eq, lhs, scalar)
+    }
+
+    pub fn ne_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Ne, lhs, scalar)
+    }
+
+    pub fn lt_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Lt, lhs, scalar)
+    }
+
+    pub fn le_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Le, lhs, scalar)
+    }
+
+    pub fn gt_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Gt, lhs, scalar)
+    }
+
+    pub fn ge_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Ge, lhs, scalar)
+    }
+
+    pub fn cast(&mut self, src: &Self, to: Dtype) -> &mut Self {
+        self.try_cast(src, to)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Converts the float tensor `src` to the float dtype `to`, rounding to nearest.
+    pub fn try_cast(&mut self, src: &Self, to: Dtype) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(src.dtype())?;
+        GpuMatError::check_float(to)?;
+        self.prepare_unary(src, to)?;
+        self.buffer.set_data(src.buffer.data().cast(to));
+
+        Ok(self)
+    }
+
+    pub fn add_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.try_add_scalar(lhs, scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `lhs + scalar`. Float scalars convert to the float or complex dtype of `lhs`, so
+    /// `&a + 1.0` works on f32 tensors, while other scalars must have the dtype of `lhs`.
+    pub fn try_add_scalar<F: Dtyped>(
+        &mut self,
+        lhs: &Self,
+        scalar: F,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_scalar(lhs.dtype(), F::dtype())?;
+        self.prepare_unary(lhs, lhs.dtype())?;
+        self.buffer.set_data(zip_map!(
+            lhs.buffer.data(),
+            &vec::scalar(scalar, lhs.dtype()),
+            Element::add
+        ));
+
+        Ok(self)
+    }
+
+    pub fn mul_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.try_mul_scalar(lhs, scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `lhs * scalar`, converting `scalar` like [`GpuTensor::try_add_scalar`].
+    pub fn try_mul_scalar<F: Dtyped>(
+        &mut self,
+        lhs: &Self,
+        scalar: F,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_scalar(lhs.dtype(), F::dtype())?;
+        self.prepare_unary(lhs, lhs.dtype())?;
+        self.buffer.set_data(zip_map!(
+            lhs.buffer.data(),
+            &vec::scalar(scalar, lhs.dtype()),
+            Element::mul
+        ));
+
+        Ok(self)
+    }
+
+    pub fn shift<F: Dtyped>(&mut self, scalar: F) -> &mut Self {
+        self.try_shift(scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Adds `scalar` to every element, converting it like [`GpuTensor::try_add_scalar`].
+    pub fn try_shift<F: Dtyped>(&mut self, scalar: F) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_scalar(self.dtype(), F::dtype())?;
+        self.buffer.set_data(zip_map!(
+            self.buffer.data(),
+            &vec::scalar(scalar, self.dtype()),
+            Element::add
+        ));
+
+        Ok(self)
+    }
+
+    pub fn scale<F: Dtyped>(&mut self, scalar: F) -> &mut Self {
+        self.try_scale(scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Multiplies every element by `scalar`, converting it like [`GpuTensor::try_add_scalar`].
+    pub fn try_scale<F: Dtyped>(&mut self, scalar: F) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_scalar(self.dtype(), F::dtype())?;
+        self.buffer.set_data(zip_map!(
+            self.buffer.data(),
+            &vec::scalar(scalar, self.dtype()),
+            Element::mul
+        ));
+
+        Ok(self)
+    }
+
+    pub fn minimum(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_minimum(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the smaller of `lhs` and `rhs` for every element, which must be floats. Operands
+    /// promote and broadcast like for [`GpuTensor::try_add`], and a NaN in either one gives NaN.
+    pub fn try_minimum(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(GpuMatError::promote(lhs.dtype(), rhs.dtype())?)?;
+        let dtype = self.prepare_output(lhs, rhs, None)?;
+        self.buffer
+            .set_data(vec::minimum(&lhs.promoted(dtype), &rhs.promoted(dtype)));
+
+        Ok(self)
+    }
+
+    pub fn maximum(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_maximum(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the larger of `lhs` and `rhs` for every element, see [`GpuTensor::try_minimum`].
+    pub fn try_maximum(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(GpuMatError::promote(lhs.dtype(), rhs.dtype())?)?;
+        let dtype = self.prepare_output(lhs, rhs, None)?;
+        self.buffer
+            .set_data(vec::maximum(&lhs.promoted(dtype), &rhs.promoted(dtype)));
+
+        Ok(self)
+    }
+
+    pub fn minimum_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_minimum_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_minimum_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(self.dtype())?;
+        self.prepare_in_place(by)?;
+        self.buffer
+            .set_data(vec::minimum(self.buffer.data(), &by.promoted(self.dtype())));
+
+        Ok(self)
+    }
+
+    pub fn maximum_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_maximum_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_maximum_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(self.dtype())?;
+        self.prepare_in_place(by)?;
+        self.buffer
+            .set_data(vec::maximum(self.buffer.data(), &by.promoted(self.dtype())));
+
+        Ok(self)
+    }
+
+    pub fn clamp(&mut self, src: &Self, lo: &Self, hi: &Self) -> &mut Self {
+        self.try_clamp(src, lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `src` clamped between `lo` and `hi`, as the maximum with `lo` then the minimum with
+    /// `hi`. Elements where `lo > hi` therefore get `hi`, and `hi` must broadcast to the shape of
+    /// the first step.
+    pub fn try_clamp(
+        &mut self,
+        src: &Self,
+        lo: &Self,
+        hi: &Self,
+    ) -> Result<&mut Self, GpuMatError> {
+        self.try_maximum(src, lo)?.try_minimum_in_place(hi)
+    }
+
+    pub fn clamp_in_place(&mut self, lo: &Self, hi: &Self) -> &mut Self {
+        self.try_clamp_in_place(lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_clamp_in_place(&mut self, lo: &Self, hi: &Self) -> Result<&mut Self, GpuMatError> {
+        self.try_maximum_in_place(lo)?.try_minimum_in_place(hi)
+    }
+
+    pub fn clamp_scalar<F: Dtyped>(&mut self, src: &Self, lo: F, hi: F) -> &mut Self {
+        self.try_clamp_scalar(src, lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Like [`GpuTensor::try_clamp`] with scalar bounds, converted like
+    /// [`GpuTensor::try_add_scalar`].
+    pub fn try_clamp_scalar<F: Dtyped>(
+        &mut self,
+        src: &Self,
+        lo: F,
+        hi: F,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(src.dtype())?;
+        GpuMatError::check_scalar(src.dtype(), F::dtype())?;
+        self.prepare_unary(src, src.dtype())?;
+        self.buffer.set_data(vec::minimum(
+            &vec::maximum(src.buffer.data(), &vec::scalar(lo, src.dtype())),
+            &vec::scalar(hi, src.dtype()),
+        ));
+
+        Ok(self)
+    }
+
+    pub fn clamp_scalar_in_place<F: Dtyped>(&mut self, lo: F, hi: F) -> &mut Self {
+        self.try_clamp_scalar_in_place(lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_clamp_scalar_in_place<F: Dtyped>(
+        &mut self,
+        lo: F,
+        hi: F,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(self.dtype())?;
+        GpuMatError::check_scalar(self.dtype(), F::dtype())?;
+        self.buffer.set_data(vec::minimum(
+            &vec::maximum(self.buffer.data(), &vec::scalar(lo, self.dtype())),
+            &vec::scalar(hi, self.dtype()),
+        ));
+
+        Ok(self)
+    }
+
+    pub fn select(&mut self, mask: &Self, a: &Self, b: &Self) -> &mut Self {
+        self.try_select(mask, a, b)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the elements of `a` where the [`Dtype::Bool`] `mask` is set and those of `b`
+    /// elsewhere, like `where` in NumPy. The three broadcast to a common shape, and `a` and `b`
+    /// promote like for [`GpuTensor::try_add`].
+    pub fn try_select(
+        &mut self,
+        mask: &Self,
+        a: &Self,
+        b: &Self,
+    ) -> Result<&mut Self, GpuMatError> {
+        let dtype = self.prepare_output(a, b, Some(mask))?;
+        self.buffer.set_data(vec::select(
+            mask.buffer.data(),
+            &a.promoted(dtype),
+            &b.promoted(dtype),
+        ));
+
+        Ok(self)
+    }
+
+    pub fn unary(&mut self, op: UnaryOp, src: &Self) -> &mut Self {
+        self.try_unary(op, src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `op` applied to every element of `src`, see [`UnaryOp::supports`] for the dtypes
+    /// each op accepts.
+    pub fn try_unary(&mut self, op: UnaryOp, src: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_unary(op, src.dtype())?;
+        self.prepare_unary(src, src.dtype())?;
+        self.buffer.set_data(unary(src.buffer.data(), op));
+
+        Ok(self)
+    }
+
+    pub fn unary_in_place(&mut self, op: UnaryOp) -> &mut Self {
+        self.try_unary_in_place(op)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_unary_in_place(&mut self, op: UnaryOp) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_unary(op, self.dtype())?;
+        self.buffer.set_data(unary(self.buffer.data(), op));
+
+        Ok(self)
+    }
+
+    unary_methods! {
+        Exp => exp, exp_in_place;
+        Log => log, log_in_place;
+        Log1p => log1p, log1p_in_place;
+        Sqrt => sqrt, sqrt_in_place;
+        Rsqrt => rsqrt, rsqrt_in_place;
+        Neg => neg, neg_in_place;
+        Sin => sin, sin_in_place;
+        Cos => cos, cos_in_place;
+        Tan => tan, tan_in_place;
+        Tanh => tanh, tanh_in_place;
+        Floor => floor, floor_in_place;
+        Ceil => ceil, ceil_in_place;
+        Round => round, round_in_place;
+        Sign => sign, sign_in_place;
+    }
+
+    pub fn abs_in_place(&mut self) -> &mut Self {
+        self.unary_in_place(UnaryOp::Abs)
+    }
+
+    pub fn activation(&mut self, activation: Activation, src: &Self) -> &mut Self {
+        self.try_activation(activation, src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `activation` applied to every element of `src`, see [`Activation::supports`] for
+    /// the dtypes each activation accepts.
+    pub fn try_activation(
+        &mut self,
+        activation: Activation,
+        src: &Self,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_activation(activation, src.dtype())?;
+        self.prepare_unary(src, src.dtype())?;
+        self.buffer
+            .set_data(vec::activation(src.buffer.data(), activation));
+
+        Ok(self)
+    }
+
+    pub fn activation_in_place(&mut self, activation: Activation) -> &mut Self {
+        self.try_activation_in_place(activation)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_activation_in_place(
+        &mut self,
+        activation: Activation,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_activation(activation, self.dtype())?;
+        self.buffer
+            .set_data(vec::activation(self.buffer.data(), activation));
+
+        Ok(self)
+    }
+
+    activation_methods! {
+        Relu => relu, relu_in_place;
+        Gelu => gelu, gelu_in_place;
+        Silu => silu, silu_in_place;
+        Sigmoid => sigmoid, sigmoid_in_place;
+        Softplus => softplus, softplus_in_place;
+    }
+
+    pub fn leaky_relu(&mut self, src: &Self, slope: f32) -> &mut Self {
+        self.activation(Activation::LeakyRelu(slope), src)
+    }
+
+    pub fn leaky_relu_in_place(&mut self, slope: f32) -> &mut Self {
+        self.activation_in_place(Activation::LeakyRelu(slope))
+    }
+
+    pub fn conj(&mut self, src: &Self) -> &mut Self {
+        self.try_conj(src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the complex conjugate of every element of the complex tensor `src`.
+    pub fn try_conj(&mut self, src: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_complex(src.dtype())?;
+        self.prepare_unary(src, src.dtype())?;
+        self.buffer.set_data(match src.buffer.data() {
+            DtypeVec::C64(values) => DtypeVec::C64(values.mapv(|z| z.conj())),
+            DtypeVec::C128(values) => DtypeVec::C128(values.mapv(|z| z.conj())),
+            _ => unreachable!("only complex values are conjugated"),
+        });
+
+        Ok(self)
+    }
+
+    pub fn abs(&mut self, src: &Self) -> &mut Self {
+        self.try_abs(src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the absolute value of every element of `src`. Complex values give their magnitude,
+    /// as their [`Dtype::real`].
+    pub fn try_abs(&mut self, src: &Self) -> Result<&mut Self, GpuMatError> {
+        if !src.dtype().is_complex() {
+            return self.try_unary(UnaryOp::Abs, src);
+        }
+        self.prepare_unary(src, src.dtype().real())?;
+        self.buffer.set_data(match src.buffer.data() {
+            DtypeVec::C64(values) => DtypeVec::F32(values.mapv(|z| z.norm())),
+            DtypeVec::C128(values) => DtypeVec::F64(values.mapv(|z| z.norm())),
+            _ => unreachable!("only complex values have a magnitude"),
+        });
+
+        Ok(self)
+    }
+
+    pub fn arg(&mut self, src: &Self) -> &mut Self {
+        self.try_arg(src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the angle in `(-pi, pi]` of every element of the complex tensor `src`, as its
+    /// [`Dtype::real`].
+    pub fn try_arg(&mut self, src: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_complex(src.dtype())?;
+        self.prepare_unary(src, src.dtype().real())?;
+        self.buffer.set_data(match src.buffer.data() {
+            DtypeVec::C64(values) => DtypeVec::F32(values.mapv(|z| z.arg())),
+            DtypeVec::C128(values) => DtypeVec::F64(values.mapv(|z| z.arg())),
+            _ => unreachable!("only complex values have an angle"),
+        });
+
+        Ok(self)
+    }
+
+    pub fn dequantize(&mut self, quantized: &QuantizedTensor, dtype: Dtype) -> &mut Self {
+        self.try_dequantize(quantized, dtype)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Expands `quantized` into a regular tensor of the float `dtype`.
+    pub fn try_dequantize(
+        &mut self,
+        quantized: &QuantizedTensor,
+        dtype: Dtype,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(dtype)?;
+        GpuMatError::check_capacity(quantized.shape(), self.capacity() / dtype.size() as u64)?;
+
+        self.shape.clear();
+        self.shape.extend_from_slice(quantized.shape());
+        self.buffer.set_data(quantized.dequantized(dtype));
+
+        Ok(self)
+    }
+
+    pub fn add_quantized(&mut self, lhs: &Self, rhs: &QuantizedTensor) -> &mut Self {
+        self.try_add_quantized(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Like [`GpuTensor::try_add`], dequantizing `rhs` on the fly.
+    pub fn try_add_quantized(
+        &mut self,
+        lhs: &Self,
+        rhs: &QuantizedTensor,
+    ) -> Result<&mut Self, GpuMatError> {
+        self.prepare_quantized(lhs, rhs)?;
+        self.buffer.set_data(zip_map!(
+            lhs.buffer.data(),
+            &rhs.dequantized(lhs.dtype()),
+            Element::add
+        ));
+
+        Ok(self)
+    }
+
+    pub fn increment_quantized(&mut self, by: &QuantizedTensor) -> &mut Self {
+        self.try_increment_quantized(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_increment_quantized(
+        &mut self,
+        by: &QuantizedTensor,
+    ) -> Result<&mut Self, GpuMatError> {
+        self.prepare_in_place_quantized(by)?;
+        self.buffer.set_data(zip_map!(
+            self.buffer.data(),
+            &by.dequantized(self.dtype()),
+            Element::add
+        ));
+
+        Ok(self)
+    }
+
+    pub fn mul_quantized(&mut self, lhs: &Self, rhs: &QuantizedTensor) -> &mut Self {
+        self.try_mul_quantized(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Like [`GpuTensor::try_mul`], dequantizing `rhs` on the fly.
+    pub fn try_mul_quantized(
+        &mut self,
+        lhs: &Self,
+        rhs: &QuantizedTensor,
+    ) -> Result<&mut Self, GpuMatError> {
+        self.prepare_quantized(lhs, rhs)?;
+        self.buffer.set_data(zip_map!(
+            lhs.buffer.data(),
+            &rhs.dequantized(lhs.dtype()),
+            Element::mul
+        ));
+
+        Ok(self)
+    }
+
+    pub fn mul_in_place_quantized(&mut self, by: &QuantizedTensor) -> &mut Self {
+        self.try_mul_in_place_quantized(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_mul_in_place_quantized(
+        &mut self,
+        by: &QuantizedTensor,
+    ) -> Result<&mut Self, GpuMatError> {
+        self.prepare_in_place_quantized(by)?;
+        self.buffer.set_data(zip_map!(
+            self.buffer.data(),
+            &by.dequantized(self.dtype()),
+            Element::mul
+        ));
+
+        Ok(self)
+    }
+
+    /// Setter writing this tensor converted to `to`, see [`GpuTensor::cast`].
+    pub fn cast_to(&self, to: Dtype) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            target.try_cast(self, to)?;
+            Ok(())
+        })
+    }
+}
+
+pub struct GpuTensorSetterFn<Fn: SetterFn>(Fn);
+
+pub trait SetterFn: FnOnce(&mut GpuTensor) -> Result<(), GpuMatError> {}
+
+impl<Fn: FnOnce(&mut GpuTensor) -> Result<(), GpuMatError>> SetterFn for Fn {}
+
+impl<Fn: SetterFn> GpuTensorSetterFn<Fn> {
+    pub fn save_intermediate(self, name: &'static str) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.save_intermediate(name);
+            Ok(())
+        })
+    }
+
+    /// Applies `activation` in place once the expression is written, see
+    /// [`GpuTensor::activation`].
+    pub fn activation(self, activation: Activation) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_activation_in_place(activation)?;
+            Ok(())
+        })
+    }
+
+    pub fn relu(self) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::Relu)
+    }
+
+    pub fn leaky_relu(self, slope: f32) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::LeakyRelu(slope))
+    }
+
+    pub fn gelu(self) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::Gelu)
+    }
+
+    pub fn silu(self) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::Silu)
+    }
+
+    pub fn sigmoid(self) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::Sigmoid)
+    }
+
+    pub fn softplus(self) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::Softplus)
+    }
+
+    /// Keeps the smaller of the expression and `by`, see [`GpuTensor::minimum`].
+    pub fn minimum(self, by: &GpuTensor) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_minimum_in_place(by)?;
+            Ok(())
+        })
+    }
+
+    pub fn maximum(self, by: &GpuTensor) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_maximum_in_place(by)?;
+            Ok(())
+        })
+    }
+
+    /// Clamps the expression once written, see [`GpuTensor::clamp`].
+    pub fn clamp(self, lo: &GpuTensor, hi: &GpuTensor) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_clamp_in_place(lo, hi)?;
+            Ok(())
+        })
+    }
+
+    pub fn clamp_scalar<F: Dtyped>(self, lo: F, hi: F) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_clamp_scalar_in_place(lo, hi)?;
+            Ok(())
+        })
+    }
+}
+
+impl core::ops::Add for &GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+    fn add(self, rhs: Self) -> Self::Output {
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_add(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Add<&'a GpuTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn add(self, rhs: &'a GpuTensor) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_increment(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Add<GpuTensorSetterFn<Fn>> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+
+    fn add(self, rhs: GpuTensorSetterFn<Fn>) -> Self::Output {
+        rhs + self
+    }
+}
+
+impl core::ops::Mul for &GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+    fn mul(self, rhs: Self) -> Self::Output {
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_mul(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Mul<&'a GpuTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn mul(self, rhs: &'a GpuTensor) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_mul_in_place(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Mul<GpuTensorSetterFn<Fn>> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+
+    fn mul(self, rhs: GpuTensorSetterFn<Fn>) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl core::ops::Sub for &GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+    fn sub(self, rhs: Self) -> Self::Output {
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_sub(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Sub<&'a GpuTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn sub(self, rhs: &'a GpuTensor) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_decrement(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Sub<GpuTensorSetterFn<Fn>> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn sub(self, rhs: GpuTensorSetterFn<Fn>) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            rhs.0(target)?;
+
+            target.try_rsub_in_place(self)?;
+            Ok(())
+        })
+    }
+}
+
+impl core::ops::Div for &GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+    fn div(self, rhs: Self) -> Self::Output {
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_div(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Div<&'a GpuTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn div(self, rhs: &'a GpuTensor) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_div_in_place(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Div<GpuTensorSetterFn<Fn>> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn div(self, rhs: GpuTensorSetterFn<Fn>) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            rhs.0(target)?;
+
+            target.try_rdiv_in_place(self)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped> core::ops::Add<F> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn add(self, rhs: F) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            target.try_add_scalar(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<Fn: SetterFn, F: Dtyped> core::ops::Add<F> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+
+    fn add(self, rhs: F) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_shift(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped> core::ops::Mul<F> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn mul(self, rhs: F) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            target.try_mul_scalar(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<Fn: SetterFn, F: Dtyped> core::ops::Mul<F> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+
+    fn mul(self, rhs: F) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_scale(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a> core::ops::Add<&'a QuantizedTensor> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+    fn add(self, rhs: &'a QuantizedTensor) -> Self::Output {
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_add_quantized(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Add<&'a QuantizedTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn add(self, rhs: &'a QuantizedTensor) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_increment_quantized(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a> core::ops::Mul<&'a QuantizedTensor> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+    fn mul(self, rhs: &'a QuantizedTensor) -> Self::Output {
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_mul_quantized(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Mul<&'a QuantizedTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn mul(self, rhs: &'a QuantizedTensor) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_mul_in_place_quantized(rhs)?;
+            Ok(())
+        })
+    }
+}
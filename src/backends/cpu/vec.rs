@@ -0,0 +1,344 @@
+use ndarray::{ArrayD, Zip};
+
+use super::{
+    dtype::{Dtype, DtypeVec, Dtyped},
+    ops::{Activation, UnaryOp},
+};
+
+/// Applies `op` to pairs of elements of operands sharing a dtype, see [`zip_with`].
+macro_rules! zip_map {
+    ($lhs:expr, $rhs:expr, $op:expr) => {{
+        use $crate::backends::{cpu::vec::zip_with, dtype::DtypeVec};
+
+        match ($lhs, $rhs) {
+            (DtypeVec::F16(lhs), DtypeVec::F16(rhs)) => DtypeVec::F16(zip_with(lhs, rhs, $op)),
+            (DtypeVec::BF16(lhs), DtypeVec::BF16(rhs)) => DtypeVec::BF16(zip_with(lhs, rhs, $op)),
+            (DtypeVec::F32(lhs), DtypeVec::F32(rhs)) => DtypeVec::F32(zip_with(lhs, rhs, $op)),
+            (DtypeVec::F64(lhs), DtypeVec::F64(rhs)) => DtypeVec::F64(zip_with(lhs, rhs, $op)),
+            (DtypeVec::I32(lhs), DtypeVec::I32(rhs)) => DtypeVec::I32(zip_with(lhs, rhs, $op)),
+            (DtypeVec::U32(lhs), DtypeVec::U32(rhs)) => DtypeVec::U32(zip_with(lhs, rhs, $op)),
+            (DtypeVec::C64(lhs), DtypeVec::C64(rhs)) => DtypeVec::C64(zip_with(lhs, rhs, $op)),
+            (DtypeVec::C128(lhs), DtypeVec::C128(rhs)) => DtypeVec::C128(zip_with(lhs, rhs, $op)),
+            _ => unreachable!("operands must share a dtype"),
+        }
+    }};
+}
+
+macro_rules! zip_mask {
+    ($lhs:expr, $rhs:expr, $op:expr) => {{
+        use $crate::backends::{cpu::vec::zip_with, dtype::DtypeVec};
+
+        DtypeVec::Bool(match ($lhs, $rhs) {
+            (DtypeVec::F16(lhs), DtypeVec::F16(rhs)) => zip_with(lhs, rhs, $op),
+            (DtypeVec::BF16(lhs), DtypeVec::BF16(rhs)) => zip_with(lhs, rhs, $op),
+            (DtypeVec::F32(lhs), DtypeVec::F32(rhs)) => zip_with(lhs, rhs, $op),
+            (DtypeVec::F64(lhs), DtypeVec::F64(rhs)) => zip_with(lhs, rhs, $op),
+            (DtypeVec::I32(lhs), DtypeVec::I32(rhs)) => zip_with(lhs, rhs, $op),
+            (DtypeVec::U32(lhs), DtypeVec::U32(rhs)) => zip_with(lhs, rhs, $op),
+            (DtypeVec::Bool(lhs), DtypeVec::Bool(rhs)) => zip_with(lhs, rhs, $op),
+            _ => unreachable!("operands must share a dtype"),
+        })
+    }};
+}
+
+pub(super) use {zip_map, zip_mask};
+
+/// Arithmetic shared by every dtype, following WGSL: integers wrap on overflow, and dividing
+/// them by zero gives the dividend.
+pub trait Element: Copy {
+    fn add(self, rhs: Self) -> Self;
+    fn sub(self, rhs: Self) -> Self;
+    fn mul(self, rhs: Self) -> Self;
+    fn div(self, rhs: Self) -> Self;
+}
+
+macro_rules! float_element {
+    ($($ty:ty),*) => {$(
+        impl Element for $ty {
+            fn add(self, rhs: Self) -> Self {
+                self + rhs
+            }
+
+            fn sub(self, rhs: Self) -> Self {
+                self - rhs
+            }
+
+            fn mul(self, rhs: Self) -> Self {
+                self * rhs
+            }
+
+            fn div(self, rhs: Self) -> Self {
+                self / rhs
+            }
+        }
+    )*};
+}
+
+macro_rules! int_element {
+    ($($ty:ty),*) => {$(
+        impl Element for $ty {
+            fn add(self, rhs: Self) -> Self {
+                self.wrapping_add(rhs)
+            }
+
+            fn sub(self, rhs: Self) -> Self {
+                self.wrapping_sub(rhs)
+            }
+
+            fn mul(self, rhs: Self) -> Self {
+                self.wrapping_mul(rhs)
+            }
+
+            fn div(self, rhs: Self) -> Self {
+                self.checked_div(rhs).unwrap_or(self)
+            }
+        }
+    )*};
+}
+
+float_element!(
+    half::f16,
+    half::bf16,
+    f32,
+    f64,
+    num_complex::Complex<f32>,
+    num_complex::Complex<f64>
+);
+int_element!(i32, u32);
+
+macro_rules! float_unary {
+    ($name:ident, $ty:ty) => {
+        fn $name(op: UnaryOp, x: $ty) -> $ty {
+            match op {
+                UnaryOp::Exp => x.exp(),
+                UnaryOp::Log => x.ln(),
+                UnaryOp::Log1p => x.ln_1p(),
+                UnaryOp::Sqrt => x.sqrt(),
+                UnaryOp::Rsqrt => 1. / x.sqrt(),
+                UnaryOp::Abs => x.abs(),
+                UnaryOp::Neg => -x,
+                UnaryOp::Sin => x.sin(),
+                UnaryOp::Cos => x.cos(),
+                UnaryOp::Tan => x.tan(),
+                UnaryOp::Tanh => x.tanh(),
+                UnaryOp::Floor => x.floor(),
+                UnaryOp::Ceil => x.ceil(),
+                UnaryOp::Round => x.round_ties_even(),
+                UnaryOp::Sign if x == 0. || x.is_nan() => x,
+                UnaryOp::Sign => x.signum(),
+            }
+        }
+    };
+}
+
+float_unary!(unary_f32, f32);
+float_unary!(unary_f64, f64);
+
+/// Applies `op`, which must support the dtype of `data`. f16 and bf16 values are computed as f32
+/// like on the GPU.
+pub fn unary(data: &DtypeVec, op: UnaryOp) -> DtypeVec {
+    match data {
+        DtypeVec::F16(values) => {
+            DtypeVec::F16(values.mapv(|value| half::f16::from_f32(unary_f32(op, value.to_f32()))))
+        }
+        DtypeVec::BF16(values) => {
+            DtypeVec::BF16(values.mapv(|value| half::bf16::from_f32(unary_f32(op, value.to_f32()))))
+        }
+        DtypeVec::F32(values) => DtypeVec::F32(values.mapv(|value| unary_f32(op, value))),
+        DtypeVec::F64(values) => DtypeVec::F64(values.mapv(|value| unary_f64(op, value))),
+        DtypeVec::I32(values) => DtypeVec::I32(values.mapv(|value| match op {
+            UnaryOp::Abs => value.wrapping_abs(),
+            UnaryOp::Neg => value.wrapping_neg(),
+            UnaryOp::Sign => value.signum(),
+            _ => unreachable!("{op:?} is not defined for i32"),
+        })),
+        DtypeVec::U32(values) => match op {
+            UnaryOp::Abs => DtypeVec::U32(values.clone()),
+            _ => unreachable!("{op:?} is not defined for u32"),
+        },
+        DtypeVec::C64(values) if op == UnaryOp::Neg => DtypeVec::C64(values.mapv(|z| -z)),
+        DtypeVec::C128(values) if op == UnaryOp::Neg => DtypeVec::C128(values.mapv(|z| -z)),
+        _ => unreachable!("{op:?} is not defined for {:?}", data.dtype()),
+    }
+}
+
+macro_rules! float_activation {
+    ($name:ident, $ty:ty) => {
+        fn $name(activation: Activation, x: $ty) -> $ty {
+            let sigmoid = |x: $ty| 1. / (1. + (-x).exp());
+            match activation {
+                Activation::Relu if x < 0. => 0.,
+                Activation::LeakyRelu(slope) if x < 0. => x * slope as $ty,
+                Activation::Relu | Activation::LeakyRelu(_) => x,
+                Activation::Gelu => {
+                    let t = (2. / core::f64::consts::PI).sqrt() as $ty * (x + 0.044715 * x * x * x);
+                    0.5 * x * (1. + t.tanh())
+                }
+                Activation::Silu => x * sigmoid(x),
+                Activation::Sigmoid => sigmoid(x),
+                Activation::Softplus => x.max(0.) + (-x.abs()).exp().ln_1p(),
+            }
+        }
+    };
+}
+
+float_activation!(activation_f32, f32);
+float_activation!(activation_f64, f64);
+
+/// Applies `activation` to float values, f16 and bf16 values are computed as f32.
+pub fn activation(data: &DtypeVec, activation: Activation) -> DtypeVec {
+    match data {
+        DtypeVec::F16(values) => DtypeVec::F16(
+            values.mapv(|value| half::f16::from_f32(activation_f32(activation, value.to_f32()))),
+        ),
+        DtypeVec::BF16(values) => DtypeVec::BF16(
+            values.mapv(|value| half::bf16::from_f32(activation_f32(activation, value.to_f32()))),
+        ),
+        DtypeVec::F32(values) => {
+            DtypeVec::F32(values.mapv(|value| activation_f32(activation, value)))
+        }
+        DtypeVec::F64(values) => {
+            DtypeVec::F64(values.mapv(|value| activation_f64(activation, value)))
+        }
+        _ => unreachable!("activations only apply to floats"),
+    }
+}
+
+/// Applies `op` to the elements of `lhs` and `rhs` broadcast to their common shape, which callers
+/// check they have, see [`GpuMatError::broadcast`](super::error::GpuMatError::broadcast).
+pub fn zip_with<A: Copy, B>(lhs: &ArrayD<A>, rhs: &ArrayD<A>, op: impl Fn(A, A) -> B) -> ArrayD<B> {
+    let shape = common_shape(lhs.shape(), rhs.shape());
+    let lhs = lhs.broadcast(shape.as_slice());
+    let rhs = rhs.broadcast(shape.as_slice());
+    let (Some(lhs), Some(rhs)) = (lhs, rhs) else {
+        unreachable!("operand shapes must broadcast");
+    };
+    Zip::from(&lhs).and(&rhs).map_collect(|&a, &b| op(a, b))
+}
+
+/// The shape `lhs` and `rhs` broadcast to, assuming they do.
+fn common_shape(lhs: &[usize], rhs: &[usize]) -> Vec<usize> {
+    let rank = lhs.len().max(rhs.len());
+    let dim = |shape: &[usize], axis: usize| {
+        (axis + shape.len())
+            .checked_sub(rank)
+            .map_or(1, |axis| shape[axis])
+    };
+    (0..rank)
+        .map(|axis| match dim(lhs, axis) {
+            1 => dim(rhs, axis),
+            size => size,
+        })
+        .collect()
+}
+
+/// Applies `op` to pairs of elements of float operands sharing a dtype, see [`zip_with`].
+macro_rules! float_zip {
+    ($lhs:expr, $rhs:expr, $op:expr) => {
+        match ($lhs, $rhs) {
+            (DtypeVec::F16(lhs), DtypeVec::F16(rhs)) => DtypeVec::F16(zip_with(lhs, rhs, $op)),
+            (DtypeVec::BF16(lhs), DtypeVec::BF16(rhs)) => DtypeVec::BF16(zip_with(lhs, rhs, $op)),
+            (DtypeVec::F32(lhs), DtypeVec::F32(rhs)) => DtypeVec::F32(zip_with(lhs, rhs, $op)),
+            (DtypeVec::F64(lhs), DtypeVec::F64(rhs)) => DtypeVec::F64(zip_with(lhs, rhs, $op)),
+            _ => unreachable!("operands must share a float dtype"),
+        }
+    };
+}
+
+/// A value not ordered with itself is NaN.
+fn is_nan<T: PartialOrd>(value: T) -> bool {
+    value.partial_cmp(&value).is_none()
+}
+
+/// The smaller of the elements of float operands, where a NaN in either one gives NaN like on
+/// the GPU.
+pub fn minimum(lhs: &DtypeVec, rhs: &DtypeVec) -> DtypeVec {
+    float_zip!(lhs, rhs, |a, b| if a < b || is_nan(a) { a } else { b })
+}
+
+/// The larger of the elements of float operands, see [`minimum`].
+pub fn maximum(lhs: &DtypeVec, rhs: &DtypeVec) -> DtypeVec {
+    float_zip!(lhs, rhs, |a, b| if b < a || is_nan(a) { a } else { b })
+}
+
+/// The elements of `a` where the [`Dtype::Bool`] `mask` is set and those of `b` elsewhere, all
+/// three broadcast to their common shape like in [`zip_with`].
+pub fn select(mask: &DtypeVec, a: &DtypeVec, b: &DtypeVec) -> DtypeVec {
+    let DtypeVec::Bool(mask) = mask else {
+        unreachable!("masks are Bool");
+    };
+    macro_rules! select {
+        ($($variant:ident),*) => {
+            match (a, b) {
+                $((DtypeVec::$variant(a), DtypeVec::$variant(b)) => {
+                    let shape = common_shape(mask.shape(), &common_shape(a.shape(), b.shape()));
+                    let (Some(mask), Some(a), Some(b)) =
+                        (mask.broadcast(&*shape), a.broadcast(&*shape), b.broadcast(&*shape))
+                    else {
+                        unreachable!("operand shapes must broadcast");
+                    };
+                    DtypeVec::$variant(
+                        Zip::from(&mask)
+                            .and(&a)
+                            .and(&b)
+                            .map_collect(|&m, &a, &b| if m { a } else { b }),
+                    )
+                })*
+                _ => unreachable!("operands must share a dtype"),
+            }
+        };
+    }
+    select!(F16, BF16, F32, F64, I32, U32, C64, C128)
+}
+
+/// A 0-d vector holding `value` as `dtype`, to operate on every element of a tensor with
+/// [`zip_map`].
+pub fn scalar<F: Dtyped>(value: F, dtype: Dtype) -> DtypeVec {
+    DtypeVec::scalar(value).cast(dtype)
+}
+
+pub struct CpuVec {
+    data: DtypeVec,
+    capacity: u64,
+}
+
+impl CpuVec {
+    pub fn new_init<F: Dtyped>(shape: &[u32], value: &[F]) -> Self {
+        Self {
+            data: F::dtype().to_vec(bytemuck::cast_slice(value), shape),
+            capacity: core::mem::size_of_val(value) as u64,
+        }
+    }
+
+    pub fn new_uninit<F: Dtyped>(size: u64) -> Self {
+        Self {
+            data: F::dtype().to_vec(&[], &[0]),
+            capacity: size,
+        }
+    }
+
+    pub fn from_data(data: DtypeVec) -> Self {
+        let capacity = (data.shape().iter().product::<u32>() as usize * data.dtype().size()) as u64;
+        Self { data, capacity }
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    pub fn capacity_elements(&self) -> u64 {
+        self.capacity / self.dtype().size() as u64
+    }
+
+    pub fn set_data(&mut self, data: DtypeVec) {
+        self.data = data;
+    }
+
+    pub fn dtype(&self) -> Dtype {
+        self.data.dtype()
+    }
+
+    pub fn data(&self) -> &DtypeVec {
+        &self.data
+    }
+}
@@ -0,0 +1,294 @@
+use bytemuck::{AnyBitPattern, NoUninit};
+use half::{bf16, f16};
+use ndarray::{Array, IxDyn};
+use num_complex::Complex;
+use std::fmt::Debug;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dtype {
+    F16,
+    /// bfloat16, computed as f32 on the GPU and stored in pairs.
+    BF16,
+    F32,
+    F64,
+    /// Integer arithmetic wraps on overflow, on every backend.
+    I32,
+    U32,
+    /// Comparison masks, one u32 per element holding 0 or 1.
+    Bool,
+    /// Complex numbers as interleaved f32 real and imaginary parts.
+    C64,
+    /// Complex numbers as interleaved f64 real and imaginary parts, emulated like [`Dtype::F64`]
+    /// on devices without shader f64.
+    C128,
+}
+
+impl Dtype {
+    pub fn to_vec(self, data: &[u8], shape: &[u32]) -> DtypeVec {
+        let data = &data[..shape.iter().product::<u32>() as usize * self.size()];
+        match self {
+            Dtype::F16 => DtypeVec::F16(
+                Array::from_shape_vec(
+                    IxDyn(&shape.iter().map(|&idx| idx as usize).collect::<Vec<_>>()),
+                    bytemuck::pod_collect_to_vec(data),
+                )
+                .unwrap(),
+            ),
+            Dtype::BF16 => DtypeVec::BF16(
+                Array::from_shape_vec(
+                    IxDyn(&shape.iter().map(|&idx| idx as usize).collect::<Vec<_>>()),
+                    bytemuck::pod_collect_to_vec(data),
+                )
+                .unwrap(),
+            ),
+            Dtype::F32 => DtypeVec::F32(
+                Array::from_shape_vec(
+                    IxDyn(&shape.iter().map(|&idx| idx as usize).collect::<Vec<_>>()),
+                    bytemuck::pod_collect_to_vec(data),
+                )
+                .unwrap(),
+            ),
+            Dtype::F64 => DtypeVec::F64(
+                Array::from_shape_vec(
+                    IxDyn(&shape.iter().map(|&idx| idx as usize).collect::<Vec<_>>()),
+                    bytemuck::pod_collect_to_vec(data),
+                )
+                .unwrap(),
+            ),
+            Dtype::I32 => DtypeVec::I32(
+                Array::from_shape_vec(
+                    IxDyn(&shape.iter().map(|&idx| idx as usize).collect::<Vec<_>>()),
+                    bytemuck::pod_collect_to_vec(data),
+                )
+                .unwrap(),
+            ),
+            Dtype::U32 => DtypeVec::U32(
+                Array::from_shape_vec(
+                    IxDyn(&shape.iter().map(|&idx| idx as usize).collect::<Vec<_>>()),
+                    bytemuck::pod_collect_to_vec(data),
+                )
+                .unwrap(),
+            ),
+            Dtype::Bool => DtypeVec::Bool(
+                Array::from_shape_vec(
+                    IxDyn(&shape.iter().map(|&idx| idx as usize).collect::<Vec<_>>()),
+                    bytemuck::pod_collect_to_vec::<u8, u32>(data)
+                        .into_iter()
+                        .map(|value| value != 0)
+                        .collect(),
+                )
+                .unwrap(),
+            ),
+            Dtype::C64 => DtypeVec::C64(
+                Array::from_shape_vec(
+                    IxDyn(&shape.iter().map(|&idx| idx as usize).collect::<Vec<_>>()),
+                    bytemuck::pod_collect_to_vec(data),
+                )
+                .unwrap(),
+            ),
+            Dtype::C128 => DtypeVec::C128(
+                Array::from_shape_vec(
+                    IxDyn(&shape.iter().map(|&idx| idx as usize).collect::<Vec<_>>()),
+                    bytemuck::pod_collect_to_vec(data),
+                )
+                .unwrap(),
+            ),
+        }
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            Dtype::F16 => core::mem::size_of::<f16>(),
+            Dtype::BF16 => core::mem::size_of::<bf16>(),
+            Dtype::F32 => core::mem::size_of::<f32>(),
+            Dtype::F64 => core::mem::size_of::<f64>(),
+            Dtype::I32 => core::mem::size_of::<i32>(),
+            Dtype::U32 | Dtype::Bool => core::mem::size_of::<u32>(),
+            Dtype::C64 => core::mem::size_of::<Complex<f32>>(),
+            Dtype::C128 => core::mem::size_of::<Complex<f64>>(),
+        }
+    }
+
+    /// Whether arithmetic is defined on the dtype, which excludes masks.
+    pub fn is_numeric(self) -> bool {
+        self != Dtype::Bool
+    }
+
+    pub fn is_float(self) -> bool {
+        matches!(self, Dtype::F16 | Dtype::BF16 | Dtype::F32 | Dtype::F64)
+    }
+
+    pub fn is_complex(self) -> bool {
+        matches!(self, Dtype::C64 | Dtype::C128)
+    }
+
+    /// The dtype binary operations on `self` and `other` produce, if they can be combined.
+    ///
+    /// Like numpy, floats promote to the wider of the two and complex values absorb floats,
+    /// keeping the wider precision of their parts, so f32 with c64 gives c64 and f64 with c64
+    /// gives c128. f16 and bf16 meet at f32. Integers and masks only combine with themselves.
+    pub fn promote(self, other: Dtype) -> Option<Dtype> {
+        if self == other {
+            return Some(self);
+        }
+        let is_real_or_complex = |dtype: Dtype| dtype.is_float() || dtype.is_complex();
+        if !is_real_or_complex(self) || !is_real_or_complex(other) {
+            return None;
+        }
+
+        let real = match (self.real(), other.real()) {
+            (lhs, rhs) if lhs == rhs => lhs,
+            (Dtype::F64, _) | (_, Dtype::F64) => Dtype::F64,
+            _ => Dtype::F32,
+        };
+        Some(match real {
+            _ if !self.is_complex() && !other.is_complex() => real,
+            Dtype::F64 => Dtype::C128,
+            _ => Dtype::C64,
+        })
+    }
+
+    /// The dtype of the real and imaginary parts of a complex dtype, other dtypes are their own.
+    pub fn real(self) -> Dtype {
+        match self {
+            Dtype::C64 => Dtype::F32,
+            Dtype::C128 => Dtype::F64,
+            dtype => dtype,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DtypeVec {
+    F16(Array<f16, IxDyn>),
+    BF16(Array<bf16, IxDyn>),
+    F32(Array<f32, IxDyn>),
+    F64(Array<f64, IxDyn>),
+    I32(Array<i32, IxDyn>),
+    U32(Array<u32, IxDyn>),
+    Bool(Array<bool, IxDyn>),
+    C64(Array<Complex<f32>, IxDyn>),
+    C128(Array<Complex<f64>, IxDyn>),
+}
+
+impl DtypeVec {
+    pub fn dtype(&self) -> Dtype {
+        match self {
+            DtypeVec::F16(_) => Dtype::F16,
+            DtypeVec::BF16(_) => Dtype::BF16,
+            DtypeVec::F32(_) => Dtype::F32,
+            DtypeVec::F64(_) => Dtype::F64,
+            DtypeVec::I32(_) => Dtype::I32,
+            DtypeVec::U32(_) => Dtype::U32,
+            DtypeVec::Bool(_) => Dtype::Bool,
+            DtypeVec::C64(_) => Dtype::C64,
+            DtypeVec::C128(_) => Dtype::C128,
+        }
+    }
+
+    pub fn shape(&self) -> Vec<u32> {
+        let shape = match self {
+            DtypeVec::F16(array) => array.shape(),
+            DtypeVec::BF16(array) => array.shape(),
+            DtypeVec::F32(array) => array.shape(),
+            DtypeVec::F64(array) => array.shape(),
+            DtypeVec::I32(array) => array.shape(),
+            DtypeVec::U32(array) => array.shape(),
+            DtypeVec::Bool(array) => array.shape(),
+            DtypeVec::C64(array) => array.shape(),
+            DtypeVec::C128(array) => array.shape(),
+        };
+        shape.iter().map(|&dim| dim as u32).collect()
+    }
+
+    /// A 0-d vector holding `value`.
+    pub(crate) fn scalar<F: Dtyped>(value: F) -> Self {
+        F::dtype().to_vec(bytemuck::bytes_of(&value), &[])
+    }
+
+    /// Converts float and complex values through `Complex<f64>`, which holds every other one
+    /// exactly, rounding once to the target dtype. Imaginary parts are dropped when converting to
+    /// a float dtype.
+    pub(crate) fn cast(&self, to: Dtype) -> Self {
+        if self.dtype() == to {
+            return self.clone();
+        }
+        let values = match self {
+            DtypeVec::F16(values) => values.mapv(|value| Complex::from(value.to_f64())),
+            DtypeVec::BF16(values) => values.mapv(|value| Complex::from(value.to_f64())),
+            DtypeVec::F32(values) => values.mapv(|value| Complex::from(f64::from(value))),
+            DtypeVec::F64(values) => values.mapv(Complex::from),
+            DtypeVec::C64(values) => {
+                values.mapv(|value| Complex::new(value.re.into(), value.im.into()))
+            }
+            DtypeVec::C128(values) => values.clone(),
+            _ => unreachable!("only floats and complex values are cast"),
+        };
+        match to {
+            Dtype::F16 => {
+                DtypeVec::F16(values.mapv(|value| f16::from_f32(f32_rounded_to_odd(value.re))))
+            }
+            Dtype::BF16 => {
+                DtypeVec::BF16(values.mapv(|value| bf16::from_f32(f32_rounded_to_odd(value.re))))
+            }
+            Dtype::F32 => DtypeVec::F32(values.mapv(|value| value.re as f32)),
+            Dtype::F64 => DtypeVec::F64(values.mapv(|value| value.re)),
+            Dtype::C64 => {
+                DtypeVec::C64(values.mapv(|value| Complex::new(value.re as f32, value.im as f32)))
+            }
+            Dtype::C128 => DtypeVec::C128(values),
+            _ => unreachable!("only floats and complex values are cast"),
+        }
+    }
+}
+
+pub trait Dtyped: NoUninit + AnyBitPattern + Debug {
+    fn dtype() -> Dtype;
+
+    /// The values of `vec` if it holds this type, or `vec` itself otherwise.
+    fn from_dtype_vec(vec: DtypeVec) -> Result<Array<Self, IxDyn>, DtypeVec>;
+}
+
+macro_rules! dtyped {
+    ($($ty:ty => $variant:ident),* $(,)?) => {$(
+        impl Dtyped for $ty {
+            fn dtype() -> Dtype {
+                Dtype::$variant
+            }
+
+            fn from_dtype_vec(vec: DtypeVec) -> Result<Array<Self, IxDyn>, DtypeVec> {
+                match vec {
+                    DtypeVec::$variant(values) => Ok(values),
+                    vec => Err(vec),
+                }
+            }
+        }
+    )*};
+}
+
+dtyped!(
+    f16 => F16,
+    bf16 => BF16,
+    f32 => F32,
+    f64 => F64,
+    i32 => I32,
+    u32 => U32,
+    Complex<f32> => C64,
+    Complex<f64> => C128,
+);
+
+/// `value` as f32 rounded to odd: an inexact value becomes the neighbour whose last bit is set.
+/// Rounding that again to f16 or bf16 gives `value` rounded once, which `half` does not guarantee
+/// for f64, as it may go through f32 or truncate.
+fn f32_rounded_to_odd(value: f64) -> f32 {
+    let rounded = value as f32;
+    let bits = rounded.to_bits();
+    if f64::from(rounded) == value || bits & 1 == 1 || value.is_nan() {
+        return rounded;
+    }
+    f32::from_bits(if value.abs() > f64::from(rounded).abs() {
+        bits + 1
+    } else {
+        bits - 1
+    })
+}
@@ -0,0 +1,213 @@
+use std::fmt::Display;
+
+use super::{
+    dtype::Dtype,
+    ops::{Activation, UnaryOp},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GpuMatError {
+    /// No adapter matched the requested backends and options.
+    NoAdapter(String),
+    /// The adapter was found but cannot run compute shaders.
+    NoComputeShaders { adapter: String },
+    /// The adapter refused to create a device.
+    RequestDevice(String),
+    /// The number of values does not match the number of elements in the shape.
+    LengthMismatch { shape: Vec<u32>, len: usize },
+    /// The operands of an operation have different dtypes.
+    DtypeMismatch { lhs: Dtype, rhs: Dtype },
+    /// The shapes of the operands of an operation do not broadcast.
+    ShapeMismatch { lhs: Vec<u32>, rhs: Vec<u32> },
+    /// The output tensor cannot hold the result, both counts are in elements.
+    InsufficientCapacity { required: u64, capacity: u64 },
+    /// The tensors of an operation were created on different contexts.
+    ContextMismatch,
+    /// The device could not allocate a buffer.
+    OutOfMemory(String),
+    /// wgpu rejected a call, usually because the device lacks a feature the kernel needs.
+    Validation(String),
+    /// The device was lost, every tensor created on its context is unusable.
+    DeviceLost(String),
+    /// Waiting for or mapping the results of a computation failed.
+    Download(String),
+    /// An external buffer cannot back a tensor.
+    InvalidBuffer(String),
+    /// The operation is not defined for the dtype of its operands.
+    UnsupportedDtype(Dtype),
+    /// The quantization format cannot be used, for example because of its block size.
+    InvalidQuantization(String),
+}
+
+impl Display for GpuMatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuMatError::NoAdapter(reason) => write!(f, "Could not get a wgpu adapter: {reason}"),
+            GpuMatError::NoComputeShaders { adapter } => {
+                write!(f, "wgpu adapter {adapter} does not support compute shaders")
+            }
+            GpuMatError::RequestDevice(reason) => {
+                write!(f, "Failed to create wgpu device: {reason}")
+            }
+            GpuMatError::LengthMismatch { shape, len } => write!(
+                f,
+                "shape {shape:?} has {} elements but {len} values were given",
+                shape.iter().product::<u32>()
+            ),
+            GpuMatError::DtypeMismatch { lhs, rhs } => {
+                write!(f, "dtype mismatch between {lhs:?} and {rhs:?}")
+            }
+            GpuMatError::ShapeMismatch { lhs, rhs } => {
+                write!(f, "shape mismatch between {lhs:?} and {rhs:?}")
+            }
+            GpuMatError::InsufficientCapacity { required, capacity } => write!(
+                f,
+                "output needs {required} elements but only has capacity for {capacity}"
+            ),
+            GpuMatError::ContextMismatch => write!(f, "tensors belong to different contexts"),
+            GpuMatError::OutOfMemory(reason) => write!(f, "out of device memory: {reason}"),
+            GpuMatError::Validation(reason) => write!(f, "wgpu validation error: {reason}"),
+            GpuMatError::DeviceLost(reason) => write!(f, "device lost: {reason}"),
+            GpuMatError::Download(reason) => write!(f, "failed to download results: {reason}"),
+            GpuMatError::InvalidBuffer(reason) => write!(f, "invalid external buffer: {reason}"),
+            GpuMatError::UnsupportedDtype(dtype) => {
+                write!(f, "operation is not defined for {dtype:?}")
+            }
+            GpuMatError::InvalidQuantization(reason) => {
+                write!(f, "invalid quantization format: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GpuMatError {}
+
+impl GpuMatError {
+    pub(crate) fn check_len(shape: &[u32], len: usize) -> Result<(), Self> {
+        if shape.iter().product::<u32>() as usize != len {
+            return Err(GpuMatError::LengthMismatch {
+                shape: shape.to_vec(),
+                len,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_dtypes(lhs: Dtype, rhs: Dtype) -> Result<(), Self> {
+        if lhs != rhs {
+            return Err(GpuMatError::DtypeMismatch { lhs, rhs });
+        }
+        Ok(())
+    }
+
+    /// The dtype a binary operation on `lhs` and `rhs` produces, see [`Dtype::promote`].
+    pub(crate) fn promote(lhs: Dtype, rhs: Dtype) -> Result<Dtype, Self> {
+        lhs.promote(rhs)
+            .ok_or(GpuMatError::DtypeMismatch { lhs, rhs })
+    }
+
+    pub(crate) fn check_numeric(dtype: Dtype) -> Result<(), Self> {
+        if !dtype.is_numeric() {
+            return Err(GpuMatError::UnsupportedDtype(dtype));
+        }
+        Ok(())
+    }
+
+    /// A scalar operand converts to the dtype of the tensor it applies to, which must be numeric.
+    /// Float scalars convert to any float or complex dtype, so unsuffixed literals apply to f32
+    /// tensors, while other scalars must match the tensor.
+    pub(crate) fn check_scalar(dtype: Dtype, scalar: Dtype) -> Result<(), Self> {
+        Self::check_numeric(dtype)?;
+        if scalar.is_float() && (dtype.is_float() || dtype.is_complex()) {
+            return Ok(());
+        }
+        Self::check_dtypes(dtype, scalar)
+    }
+
+    pub(crate) fn check_float(dtype: Dtype) -> Result<(), Self> {
+        if !dtype.is_float() {
+            return Err(GpuMatError::UnsupportedDtype(dtype));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_unary(op: UnaryOp, dtype: Dtype) -> Result<(), Self> {
+        if !op.supports(dtype) {
+            return Err(GpuMatError::UnsupportedDtype(dtype));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_activation(activation: Activation, dtype: Dtype) -> Result<(), Self> {
+        if !activation.supports(dtype) {
+            return Err(GpuMatError::UnsupportedDtype(dtype));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_complex(dtype: Dtype) -> Result<(), Self> {
+        if !dtype.is_complex() {
+            return Err(GpuMatError::UnsupportedDtype(dtype));
+        }
+        Ok(())
+    }
+
+    /// Complex numbers have no order, so they cannot be compared.
+    pub(crate) fn check_ordered(dtype: Dtype) -> Result<(), Self> {
+        if dtype.is_complex() {
+            return Err(GpuMatError::UnsupportedDtype(dtype));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_shapes(lhs: &[u32], rhs: &[u32]) -> Result<(), Self> {
+        if lhs != rhs {
+            return Err(GpuMatError::ShapeMismatch {
+                lhs: lhs.to_vec(),
+                rhs: rhs.to_vec(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The shape of the result of a binary operation on `lhs` and `rhs`. Like in NumPy, their
+    /// trailing dimensions are aligned and those of size 1 are repeated to match the other.
+    pub(crate) fn broadcast(lhs: &[u32], rhs: &[u32]) -> Result<Vec<u32>, Self> {
+        let rank = lhs.len().max(rhs.len());
+        let dim = |shape: &[u32], axis: usize| {
+            (axis + shape.len())
+                .checked_sub(rank)
+                .map_or(1, |axis| shape[axis])
+        };
+        (0..rank)
+            .map(|axis| match (dim(lhs, axis), dim(rhs, axis)) {
+                (a, b) if a == b || b == 1 => Ok(a),
+                (1, b) => Ok(b),
+                _ => Err(GpuMatError::ShapeMismatch {
+                    lhs: lhs.to_vec(),
+                    rhs: rhs.to_vec(),
+                }),
+            })
+            .collect()
+    }
+
+    /// Like [`GpuMatError::broadcast`], for an in place operation whose result keeps the shape of
+    /// `target`.
+    pub(crate) fn check_broadcast_to(target: &[u32], operand: &[u32]) -> Result<(), Self> {
+        if Self::broadcast(target, operand)? != target {
+            return Err(GpuMatError::ShapeMismatch {
+                lhs: target.to_vec(),
+                rhs: operand.to_vec(),
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_capacity(shape: &[u32], capacity: u64) -> Result<(), Self> {
+        let required = shape.iter().product::<u32>() as u64;
+        if required > capacity {
+            return Err(GpuMatError::InsufficientCapacity { required, capacity });
+        }
+        Ok(())
+    }
+}
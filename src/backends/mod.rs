@@ -1,5 +1,701 @@
+use std::collections::HashMap;
+
+use dtype::{Dtype, DtypeVec, Dtyped};
+use error::GpuMatError;
+use ops::{Activation, CompareOp, UnaryOp};
+use quantization::QuantFormat;
+
+pub mod dtype;
+pub mod error;
+pub mod ops;
+pub mod quantization;
+#[cfg(test)]
+mod test_util;
+#[cfg(any(feature = "backend-cpu", feature = "backend-wgpu"))]
+pub mod typed;
+
+#[cfg(feature = "backend-cpu")]
+pub mod cpu;
 #[cfg(feature = "backend-wgpu")]
 pub mod wgpu;
 
+#[cfg(all(feature = "backend-cpu", not(feature = "backend-wgpu")))]
+pub use cpu as backend;
 #[cfg(feature = "backend-wgpu")]
 pub use wgpu as backend;
+
+/// The backend used by [`typed::GpuTensorOf`] unless another one is named.
+#[cfg(all(feature = "backend-cpu", not(feature = "backend-wgpu")))]
+pub type DefaultBackend = cpu::Cpu;
+#[cfg(feature = "backend-wgpu")]
+pub type DefaultBackend = wgpu::Wgpu;
+
+/// Output of a computation: the target tensor followed by every intermediate saved on the way.
+pub type Computed = (DtypeVec, HashMap<&'static str, DtypeVec>);
+
+/// An execution engine for tensors.
+///
+/// Every backend module exposes its own `GpuTensor`, and a marker type implementing this trait
+/// so code can be written once and run on any of them. Tensors never move between backends
+/// implicitly, use [`transfer`] to copy one over.
+pub trait Backend {
+    type Tensor;
+    type Handle: Future<Output = Computed>;
+    /// A read only tensor stored as blocks of small integers, see [`QuantFormat`].
+    ///
+    /// Only [`Backend::add_quantized`], [`Backend::mul_quantized`] and their in place forms take
+    /// it as an operand, with a float tensor of exactly the same shape.
+    type Quantized;
+
+    fn new<F: Dtyped>(shape: Vec<u32>, values: &[F]) -> Result<Self::Tensor, GpuMatError>;
+    fn with_capacity(capacity: u64) -> Result<Self::Tensor, GpuMatError>;
+    fn upload(values: &DtypeVec) -> Result<Self::Tensor, GpuMatError>;
+    fn download(tensor: &Self::Tensor) -> Result<DtypeVec, GpuMatError>;
+
+    fn capacity(tensor: &Self::Tensor) -> u64;
+    fn dtype(tensor: &Self::Tensor) -> Dtype;
+
+    fn add<'a>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+        rhs: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn increment<'a>(
+        target: &'a mut Self::Tensor,
+        by: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn mul<'a>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+        rhs: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn mul_in_place<'a>(
+        target: &'a mut Self::Tensor,
+        by: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn sub<'a>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+        rhs: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn decrement<'a>(
+        target: &'a mut Self::Tensor,
+        by: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    /// `target = lhs - target`.
+    fn rsub_in_place<'a>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn div<'a>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+        rhs: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn div_in_place<'a>(
+        target: &'a mut Self::Tensor,
+        by: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    /// `target = lhs / target`.
+    fn rdiv_in_place<'a>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+
+    fn add_scalar<'a, F: Dtyped>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+        scalar: F,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn mul_scalar<'a, F: Dtyped>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+        scalar: F,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn shift<F: Dtyped>(
+        tensor: &mut Self::Tensor,
+        scalar: F,
+    ) -> Result<&mut Self::Tensor, GpuMatError>;
+    fn scale<F: Dtyped>(
+        tensor: &mut Self::Tensor,
+        scalar: F,
+    ) -> Result<&mut Self::Tensor, GpuMatError>;
+
+    fn minimum<'a>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+        rhs: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn maximum<'a>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+        rhs: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn minimum_in_place<'a>(
+        target: &'a mut Self::Tensor,
+        by: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn maximum_in_place<'a>(
+        target: &'a mut Self::Tensor,
+        by: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn clamp<'a>(
+        target: &'a mut Self::Tensor,
+        src: &Self::Tensor,
+        lo: &Self::Tensor,
+        hi: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn clamp_in_place<'a>(
+        target: &'a mut Self::Tensor,
+        lo: &Self::Tensor,
+        hi: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn clamp_scalar<'a, F: Dtyped>(
+        target: &'a mut Self::Tensor,
+        src: &Self::Tensor,
+        lo: F,
+        hi: F,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn clamp_scalar_in_place<F: Dtyped>(
+        tensor: &mut Self::Tensor,
+        lo: F,
+        hi: F,
+    ) -> Result<&mut Self::Tensor, GpuMatError>;
+
+    fn compare<'a>(
+        target: &'a mut Self::Tensor,
+        op: CompareOp,
+        lhs: &Self::Tensor,
+        rhs: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn compare_scalar<'a, F: Dtyped>(
+        target: &'a mut Self::Tensor,
+        op: CompareOp,
+        lhs: &Self::Tensor,
+        scalar: F,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    /// `target = mask ? a : b` for every element, with a [`Dtype::Bool`] mask.
+    fn select<'a>(
+        target: &'a mut Self::Tensor,
+        mask: &Self::Tensor,
+        a: &Self::Tensor,
+        b: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+
+    fn unary<'a>(
+        target: &'a mut Self::Tensor,
+        op: UnaryOp,
+        src: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn unary_in_place(
+        tensor: &mut Self::Tensor,
+        op: UnaryOp,
+    ) -> Result<&mut Self::Tensor, GpuMatError>;
+    fn activation<'a>(
+        target: &'a mut Self::Tensor,
+        activation: Activation,
+        src: &Self::Tensor,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn activation_in_place(
+        tensor: &mut Self::Tensor,
+        activation: Activation,
+    ) -> Result<&mut Self::Tensor, GpuMatError>;
+
+    fn cast<'a>(
+        target: &'a mut Self::Tensor,
+        src: &Self::Tensor,
+        to: Dtype,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+
+    fn quantize(tensor: &Self::Tensor, format: QuantFormat)
+    -> Result<Self::Quantized, GpuMatError>;
+    /// Expands `quantized` into `target` as the float `dtype`.
+    fn dequantize<'a>(
+        target: &'a mut Self::Tensor,
+        quantized: &Self::Quantized,
+        dtype: Dtype,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    /// `target = lhs + rhs`, dequantizing `rhs` on the fly.
+    fn add_quantized<'a>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+        rhs: &Self::Quantized,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn increment_quantized<'a>(
+        target: &'a mut Self::Tensor,
+        by: &Self::Quantized,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    /// `target = lhs * rhs`, dequantizing `rhs` on the fly.
+    fn mul_quantized<'a>(
+        target: &'a mut Self::Tensor,
+        lhs: &Self::Tensor,
+        rhs: &Self::Quantized,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+    fn mul_in_place_quantized<'a>(
+        target: &'a mut Self::Tensor,
+        by: &Self::Quantized,
+    ) -> Result<&'a mut Self::Tensor, GpuMatError>;
+
+    fn save_intermediate(tensor: &Self::Tensor, name: &'static str) -> Result<(), GpuMatError>;
+    fn compute(tensor: &Self::Tensor) -> Self::Handle;
+    fn join(handle: Self::Handle) -> Result<Computed, GpuMatError>;
+}
+
+/// Copies `tensor` from backend `From` into a new tensor on backend `To`.
+///
+/// Pending work on `From` is flushed first, intermediates saved there are left untouched.
+pub fn transfer<From: Backend, To: Backend>(
+    tensor: &From::Tensor,
+) -> Result<To::Tensor, GpuMatError> {
+    To::upload(&From::download(tensor)?)
+}
+
+#[cfg(all(test, feature = "backend-cpu", feature = "backend-wgpu"))]
+mod tests {
+    use super::*;
+    use test_util::array;
+
+    fn mul_add<B: Backend>() -> DtypeVec {
+        type F = f32;
+
+        let a = &B::new::<F>(vec![2, 2], &[1., 2., 3., 4.]).unwrap();
+        let b = &B::new::<F>(vec![2, 2], &[2.; 4]).unwrap();
+        let c = &B::new::<F>(vec![2, 2], &[0.5; 4]).unwrap();
+        let d = &mut B::with_capacity(B::capacity(a)).unwrap();
+
+        B::mul(d, a, b).unwrap();
+        B::increment(d, c).unwrap();
+        B::mul_in_place(d, b).unwrap();
+        assert!(matches!(
+            B::add(d, a, &B::new::<F>(vec![4], &[0.; 4]).unwrap()),
+            Err(GpuMatError::ShapeMismatch { .. })
+        ));
+
+        B::join(B::compute(d)).unwrap().0
+    }
+
+    #[test]
+    fn backends_agree() {
+        assert!(mul_add::<wgpu::Wgpu>() == mul_add::<cpu::Cpu>());
+    }
+
+    fn broadcast<B: Backend>() -> DtypeVec {
+        use half::f16;
+
+        let a = &B::new::<f32>(vec![1, 2, 1, 1, 3], &[1., 2., 3., 4., 5., 6.]).unwrap();
+        let b = &B::new::<f16>(vec![2, 1], &[f16::ONE, f16::from_f32(-2.)]).unwrap();
+        let c = &B::new::<f32>(vec![3], &[0.5, 0.25, 0.125]).unwrap();
+        let d = &mut B::with_capacity(48).unwrap();
+
+        B::mul(d, a, b).unwrap();
+        B::increment(d, c).unwrap();
+        B::rsub_in_place(d, b).unwrap();
+        assert!(matches!(
+            B::increment(d, &B::new::<f32>(vec![2, 2, 3], &[0.; 12]).unwrap()),
+            Err(GpuMatError::ShapeMismatch { .. })
+        ));
+
+        B::join(B::compute(d)).unwrap().0
+    }
+
+    #[test]
+    fn broadcasting_backends_agree() {
+        let expected = broadcast::<cpu::Cpu>();
+        assert!(expected.shape() == [1, 2, 1, 2, 3]);
+        assert!(broadcast::<wgpu::Wgpu>() == expected);
+    }
+
+    fn clip_and_mask<B: Backend>() -> DtypeVec {
+        let g = &B::new::<f32>(vec![2, 3], &[0.5, -3., f32::NAN, 2., -0.25, 1.]).unwrap();
+        let lo = &B::new::<f32>(vec![3], &[-1., -1., 0.]).unwrap();
+        let hi = &B::new::<f32>(vec![2, 1], &[1., 0.5]).unwrap();
+        let zeros = &B::new::<f32>(vec![2, 3], &[0.; 6]).unwrap();
+        let clipped = &mut B::with_capacity(24).unwrap();
+        let valid = &mut B::with_capacity(24).unwrap();
+        let d = &mut B::with_capacity(24).unwrap();
+
+        B::clamp(clipped, g, lo, hi).unwrap();
+        B::compare(valid, CompareOp::Eq, clipped, clipped).unwrap();
+        B::select(d, valid, clipped, zeros).unwrap();
+
+        B::join(B::compute(d)).unwrap().0
+    }
+
+    #[test]
+    fn clip_and_mask_backends_agree() {
+        let expected = clip_and_mask::<cpu::Cpu>();
+        assert!(expected == DtypeVec::F32(array(&[2, 3], &[0.5, -1., 0., 0.5, -0.25, 0.5])));
+        assert!(clip_and_mask::<wgpu::Wgpu>() == expected);
+    }
+
+    fn select_broadcast<B: Backend>() -> DtypeVec {
+        use half::f16;
+
+        let x = &B::new::<f32>(vec![2, 3], &[1., -2., f32::NAN, 4., f32::NAN, -6.]).unwrap();
+        let zero = &B::new::<f16>(vec![], &[f16::ZERO]).unwrap();
+        let rows = &B::upload(&DtypeVec::Bool(array(&[2, 1], &[true, false]))).unwrap();
+        let bias = &B::new::<f32>(vec![3], &[10., 20., 30.]).unwrap();
+        let valid = &mut B::with_capacity(24).unwrap();
+        let cleaned = &mut B::with_capacity(24).unwrap();
+        let d = &mut B::with_capacity(24).unwrap();
+
+        B::compare(valid, CompareOp::Eq, x, x).unwrap();
+        B::select(cleaned, valid, x, zero).unwrap();
+        B::select(d, rows, cleaned, bias).unwrap();
+        assert!(matches!(
+            B::select(d, rows, x, &B::new::<f32>(vec![2], &[0.; 2]).unwrap()),
+            Err(GpuMatError::ShapeMismatch { .. })
+        ));
+
+        B::join(B::compute(d)).unwrap().0
+    }
+
+    #[test]
+    fn select_broadcasting_backends_agree() {
+        let expected = select_broadcast::<cpu::Cpu>();
+        assert!(expected == DtypeVec::F32(array(&[2, 3], &[1., -2., 0., 10., 20., 30.])));
+        assert!(select_broadcast::<wgpu::Wgpu>() == expected);
+    }
+
+    fn promoted_masks<B: Backend>() -> DtypeVec {
+        use half::f16;
+
+        let halves = [0.1, 0.5, -1., 2.].map(f16::from_f32);
+        let halves = &B::new::<f16>(vec![4], &halves).unwrap();
+        let doubles = &B::new::<f64>(vec![4], &[0.1, 0.5, -1., 3.]).unwrap();
+        let lt = &mut B::with_capacity(16).unwrap();
+        let le = &mut B::with_capacity(16).unwrap();
+        let d = &mut B::with_capacity(16).unwrap();
+
+        B::compare(lt, CompareOp::Lt, halves, doubles).unwrap();
+        B::compare_scalar(le, CompareOp::Le, halves, 0.5).unwrap();
+        B::compare(d, CompareOp::Eq, lt, le).unwrap();
+
+        B::join(B::compute(d)).unwrap().0
+    }
+
+    #[test]
+    fn promoted_masks_backends_agree() {
+        let expected = promoted_masks::<cpu::Cpu>();
+        assert!(expected == DtypeVec::Bool(array(&[4], &[true, false, false, false])));
+        assert!(promoted_masks::<wgpu::Wgpu>() == expected);
+    }
+
+    /// log1p of values far below the f32 epsilon and around the ends of its series, and softplus
+    /// of inputs whose `exp(-|x|)` is tiny.
+    fn log1p_and_softplus<B: Backend>() -> Vec<f32> {
+        let values = [1e-8, -3e-8, 9e-8, -5e-10, 0.3, -0.2, 0.75, -0.6];
+        let a = &B::new::<f32>(vec![8], &values).unwrap();
+        let x = &B::new::<f32>(vec![3], &[-20., -17.5, 30.]).unwrap();
+        let log1p = &mut B::with_capacity(32).unwrap();
+        let softplus = &mut B::with_capacity(12).unwrap();
+
+        B::unary(log1p, UnaryOp::Log1p, a).unwrap();
+        B::activation(softplus, Activation::Softplus, x).unwrap();
+
+        [log1p, softplus]
+            .into_iter()
+            .flat_map(|tensor| match B::join(B::compute(tensor)).unwrap().0 {
+                DtypeVec::F32(values) => values.into_raw_vec_and_offset().0,
+                values => panic!("expected f32 values, got {:?}", values.dtype()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn log1p_backends_agree() {
+        let expected = log1p_and_softplus::<cpu::Cpu>();
+        for (actual, expected) in log1p_and_softplus::<wgpu::Wgpu>().into_iter().zip(expected) {
+            assert!(
+                (actual - expected).abs() <= expected.abs() * 1e-6,
+                "{actual} != {expected}"
+            );
+        }
+    }
+
+    /// f64 arithmetic on `a` and `b`, and c128 arithmetic mixing `z` with them.
+    fn f64_ops<B: Backend>(
+        a: &B::Tensor,
+        b: &B::Tensor,
+        z: &B::Tensor,
+        out: &mut B::Tensor,
+    ) -> Vec<DtypeVec> {
+        let mut results = Vec::new();
+        let mut push = |out: &B::Tensor| results.push(B::join(B::compute(out)).unwrap().0);
+
+        push(B::add(out, a, b).unwrap());
+        push(B::sub(out, a, b).unwrap());
+        push(B::mul(out, a, b).unwrap());
+        push(B::div(out, a, b).unwrap());
+        push(B::increment(B::mul(out, a, b).unwrap(), a).unwrap());
+        push(B::unary(out, UnaryOp::Sqrt, b).unwrap());
+        push(B::mul(out, z, a).unwrap());
+        push(B::div(out, z, b).unwrap());
+
+        results
+    }
+
+    #[test]
+    fn df64_matches_cpu() {
+        use num_complex::Complex;
+
+        let a = [1. + 1e-12, -3.25e-7, 12345.678901234, 0.1];
+        let b = [3. - 1e-12, 2.5e3, 1. / 3., 7.];
+        let z = [1. / 3., -2.5, 1e-3, 0.2].map(|re| Complex::new(re, 1. - re));
+
+        let expected = f64_ops::<cpu::Cpu>(
+            &cpu::GpuTensor::new(vec![4], &a),
+            &cpu::GpuTensor::new(vec![4], &b),
+            &cpu::GpuTensor::new(vec![4], &z),
+            &mut cpu::GpuTensor::with_capacity(64),
+        );
+
+        let context = &wgpu::GpuContextBuilder::from_env()
+            .emulate_f64(true)
+            .build()
+            .unwrap();
+        assert!(context.emulates_f64());
+        let actual = f64_ops::<wgpu::Wgpu>(
+            &wgpu::GpuTensor::new_in(context, vec![4], &a),
+            &wgpu::GpuTensor::new_in(context, vec![4], &b),
+            &wgpu::GpuTensor::new_in(context, vec![4], &z),
+            &mut wgpu::GpuTensor::with_capacity_in(context, 64),
+        );
+
+        // Uploads round to 48 bits and each operation adds an error below 2^-44.
+        let tolerance = 1e-13;
+        for (actual, expected) in actual.into_iter().zip(expected) {
+            match (actual, expected) {
+                (DtypeVec::F64(actual), DtypeVec::F64(expected)) => {
+                    for (actual, expected) in actual.iter().zip(&expected) {
+                        assert!(
+                            (actual - expected).abs() <= expected.abs() * tolerance,
+                            "{actual} != {expected}"
+                        );
+                    }
+                }
+                (DtypeVec::C128(actual), DtypeVec::C128(expected)) => {
+                    for (actual, expected) in actual.iter().zip(&expected) {
+                        assert!(
+                            (actual - expected).norm() <= expected.norm() * tolerance,
+                            "{actual} != {expected}"
+                        );
+                    }
+                }
+                (actual, expected) => panic!("{:?} != {:?}", actual.dtype(), expected.dtype()),
+            }
+        }
+    }
+
+    fn f64_activations<B: Backend>() -> Vec<DtypeVec> {
+        let a = &B::new::<f64>(vec![5], &[-30., -1. - 1e-12, 1e-3, 0.75, 20.]).unwrap();
+        let out = &mut B::with_capacity(40).unwrap();
+
+        [
+            Activation::Gelu,
+            Activation::Silu,
+            Activation::Sigmoid,
+            Activation::Softplus,
+        ]
+        .into_iter()
+        .map(|activation| B::download(B::activation(out, activation, a).unwrap()).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn f64_activations_backends_agree() {
+        let expected = f64_activations::<cpu::Cpu>();
+        // The wgpu backend computes these activations in f32.
+        for (actual, expected) in f64_activations::<wgpu::Wgpu>().into_iter().zip(expected) {
+            let (DtypeVec::F64(actual), DtypeVec::F64(expected)) = (actual, expected) else {
+                panic!("expected f64 values");
+            };
+            for (actual, expected) in actual.iter().zip(&expected) {
+                assert!(
+                    (actual - expected).abs() <= expected.abs().max(1e-3) * 1e-6,
+                    "{actual} != {expected}"
+                );
+            }
+        }
+    }
+
+    fn f64_unary_math<B: Backend>() -> Vec<DtypeVec> {
+        let a = &B::new::<f64>(vec![5], &[1e-3, 0.4, 1. + 1e-12, 2.5, 12.]).unwrap();
+        let out = &mut B::with_capacity(40).unwrap();
+
+        [
+            UnaryOp::Exp,
+            UnaryOp::Log,
+            UnaryOp::Log1p,
+            UnaryOp::Sin,
+            UnaryOp::Cos,
+            UnaryOp::Tan,
+            UnaryOp::Tanh,
+        ]
+        .into_iter()
+        .map(|op| B::download(B::unary(out, op, a).unwrap()).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn f64_unary_math_backends_agree() {
+        let expected = f64_unary_math::<cpu::Cpu>();
+        // The wgpu backend computes these functions in f32, and WGSL only bounds the absolute
+        // error of most of them near zero.
+        for (actual, expected) in f64_unary_math::<wgpu::Wgpu>().into_iter().zip(expected) {
+            let (DtypeVec::F64(actual), DtypeVec::F64(expected)) = (actual, expected) else {
+                panic!("expected f64 values");
+            };
+            for (actual, expected) in actual.iter().zip(&expected) {
+                assert!(
+                    (actual - expected).abs() <= expected.abs().max(1.) * 1e-5,
+                    "{actual} != {expected}"
+                );
+            }
+        }
+    }
+
+    /// f64 values just above the midpoint of two f16 or bf16 values, which round to that midpoint
+    /// as f32 and then to even.
+    fn narrowing_casts<B: Backend>() -> [DtypeVec; 2] {
+        let tail = 2f64.powi(-40);
+        let values = [
+            1. + 2f64.powi(-11) + tail,
+            -(1. + 2f64.powi(-11) + tail),
+            1. + 2f64.powi(-11),
+            1. + 2f64.powi(-8) + tail,
+            -(3. + 2f64.powi(-7) + tail),
+            0.1,
+        ];
+        let a = &B::new::<f64>(vec![6], &values).unwrap();
+
+        [Dtype::F16, Dtype::BF16].map(|to| {
+            let out = &mut B::with_capacity(12).unwrap();
+            B::cast(out, a, to).unwrap();
+            B::join(B::compute(out)).unwrap().0
+        })
+    }
+
+    #[test]
+    fn narrowing_casts_round_once() {
+        use half::{bf16, f16};
+
+        let expected = narrowing_casts::<cpu::Cpu>();
+        assert!(
+            expected[0]
+                == DtypeVec::F16(array(
+                    &[6],
+                    &[
+                        1. + 2f32.powi(-10),
+                        -(1. + 2f32.powi(-10)),
+                        1.,
+                        1. + 2f32.powi(-8),
+                        -(3. + 2f32.powi(-7)),
+                        0.1
+                    ]
+                    .map(f16::from_f32)
+                ))
+        );
+        assert!(
+            expected[1]
+                == DtypeVec::BF16(array(
+                    &[6],
+                    &[1., -1., 1., 1. + 2f32.powi(-7), -(3. + 2f32.powi(-6)), 0.1]
+                        .map(bf16::from_f32)
+                ))
+        );
+        assert!(narrowing_casts::<wgpu::Wgpu>() == expected);
+    }
+
+    fn quantized<B: Backend>() -> Vec<DtypeVec> {
+        // The first block of 8 holds exact halves of its int4 scale of 1, which round to even.
+        let mut values = vec![7., 2.5, -1.5, 0.5, -3.5, 6., 0., -7.];
+        values.extend((0..21).map(|i| (i as f32 - 11.) / 3.));
+        let a = &B::new::<f32>(vec![29], &values).unwrap();
+        let out = &mut B::with_capacity(29 * 4).unwrap();
+
+        let mut results = Vec::new();
+        for format in [
+            QuantFormat::INT4.with_block_size(8),
+            QuantFormat::INT8,
+            QuantFormat::INT4.with_block_size(16).with_scale(Dtype::F16),
+        ] {
+            let q = &B::quantize(a, format).unwrap();
+            B::dequantize(out, q, Dtype::F32).unwrap();
+            results.push(B::download(out).unwrap());
+            B::dequantize(out, q, Dtype::BF16).unwrap();
+            results.push(B::download(out).unwrap());
+            B::add_quantized(out, a, q).unwrap();
+            B::mul_in_place_quantized(out, q).unwrap();
+            results.push(B::download(out).unwrap());
+        }
+        results
+    }
+
+    #[test]
+    fn quantized_backends_agree() {
+        let expected = quantized::<cpu::Cpu>();
+        let DtypeVec::F32(first) = &expected[0] else {
+            panic!("expected f32 values, got {:?}", expected[0]);
+        };
+        assert!(
+            first
+                .iter()
+                .take(8)
+                .eq(&[7., 2., -2., 0., -4., 6., 0., -7.])
+        );
+        assert!(quantized::<wgpu::Wgpu>() == expected);
+    }
+
+    fn typed_mul_add<B: Backend>() -> ndarray::Array<f32, ndarray::IxDyn> {
+        use typed::GpuTensorOf;
+
+        let a = &GpuTensorOf::<f32, B>::new(vec![2, 2], &[1., 2., 3., 4.]);
+        let b = &GpuTensorOf::<f32, B>::new(vec![2, 2], &[2.; 4]);
+        let c = &GpuTensorOf::<f32, B>::new(vec![2, 2], &[0.5; 4]);
+        let d = &mut GpuTensorOf::<f32, B>::with_capacity(a.capacity());
+        d.set((a * b + c) * b);
+        let s = &mut GpuTensorOf::<f32, B>::with_capacity(16);
+        assert!(s.set(c * 4. + 1.).download().iter().eq(&[3.; 4]));
+        assert!(
+            s.set((c * 4.).clamp_scalar(0., 1.))
+                .download()
+                .iter()
+                .eq(&[1.; 4])
+        );
+
+        let h = &GpuTensorOf::<half::f16, B>::new(vec![2], &[half::f16::ONE; 2]);
+        let e = &mut GpuTensorOf::<f32, B>::with_capacity(8);
+        assert!(e.cast(h).download().iter().eq(&[1., 1.]));
+
+        let untyped = B::new::<f32>(vec![1], &[0.]).unwrap();
+        assert!(matches!(
+            GpuTensorOf::<f64, B>::from_untyped(untyped),
+            Err(GpuMatError::DtypeMismatch { .. })
+        ));
+
+        d.compute().join()
+    }
+
+    #[test]
+    fn typed_backends_agree() {
+        let expected = [5., 9., 13., 17.];
+        assert!(typed_mul_add::<wgpu::Wgpu>().iter().eq(&expected));
+        assert!(typed_mul_add::<cpu::Cpu>().iter().eq(&expected));
+    }
+
+    #[test]
+    fn transfer_between_backends() {
+        type F = f32;
+
+        let a = &cpu::GpuTensor::new::<F>(vec![3], &[1., 2., 3.]);
+        let b = &wgpu::GpuTensor::new::<F>(vec![3], &[0.5; 3]);
+        let c = &mut wgpu::GpuTensor::with_capacity(b.capacity());
+
+        let a = &transfer::<cpu::Cpu, wgpu::Wgpu>(a).unwrap();
+        c.set(a + b);
+
+        let d = transfer::<wgpu::Wgpu, cpu::Cpu>(c).unwrap();
+        assert!(d.compute().join().0 == DtypeVec::F32(array(&[3], &[1.5, 2.5, 3.5])));
+    }
+}
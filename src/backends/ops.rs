@@ -0,0 +1,132 @@
+use super::dtype::Dtype;
+
+/// Elementwise comparisons, producing a [`Dtype::Bool`](super::dtype::Dtype::Bool) mask.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    #[cfg(feature = "backend-cpu")]
+    pub(crate) fn apply<T: PartialOrd>(self, lhs: T, rhs: T) -> bool {
+        match self {
+            CompareOp::Eq => lhs == rhs,
+            CompareOp::Ne => lhs != rhs,
+            CompareOp::Lt => lhs < rhs,
+            CompareOp::Le => lhs <= rhs,
+            CompareOp::Gt => lhs > rhs,
+            CompareOp::Ge => lhs >= rhs,
+        }
+    }
+}
+
+/// Elementwise math functions, applied in place or into a target of the same dtype.
+///
+/// Every function is defined for float dtypes. `Abs`, `Neg` and `Sign` also accept signed
+/// integers, `Abs` accepts u32, and `Neg` accepts complex values, see [`UnaryOp::supports`].
+///
+/// WGSL has no f64 transcendental functions, so the wgpu backend computes `Exp`, `Log`, `Log1p`,
+/// `Sin`, `Cos`, `Tan` and `Tanh` in f32 and its f64 results only have f32 precision and range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    Exp,
+    Log,
+    Log1p,
+    Sqrt,
+    Rsqrt,
+    Abs,
+    Neg,
+    Sin,
+    Cos,
+    Tan,
+    Tanh,
+    Floor,
+    Ceil,
+    /// Rounds half to even, like WGSL.
+    Round,
+    /// -1, 0 or 1.
+    Sign,
+}
+
+impl UnaryOp {
+    pub fn supports(self, dtype: Dtype) -> bool {
+        match self {
+            UnaryOp::Exp
+            | UnaryOp::Log
+            | UnaryOp::Log1p
+            | UnaryOp::Sqrt
+            | UnaryOp::Rsqrt
+            | UnaryOp::Sin
+            | UnaryOp::Cos
+            | UnaryOp::Tan
+            | UnaryOp::Tanh
+            | UnaryOp::Floor
+            | UnaryOp::Ceil
+            | UnaryOp::Round => dtype.is_float(),
+            UnaryOp::Abs => dtype.is_float() || matches!(dtype, Dtype::I32 | Dtype::U32),
+            UnaryOp::Sign => dtype.is_float() || dtype == Dtype::I32,
+            UnaryOp::Neg => dtype.is_float() || dtype.is_complex() || dtype == Dtype::I32,
+        }
+    }
+}
+
+/// Neural network activations, applied in place or into a target of the same float dtype.
+///
+/// Except for `Relu` and `LeakyRelu`, the wgpu backend computes activations in f32, so its f64
+/// results only have f32 precision.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Activation {
+    Relu,
+    /// `x` for positive values and `slope * x` otherwise.
+    LeakyRelu(f32),
+    /// The tanh approximation `0.5 * x * (1 + tanh(sqrt(2 / pi) * (x + 0.044715 * x^3)))`.
+    Gelu,
+    /// `x * sigmoid(x)`.
+    Silu,
+    Sigmoid,
+    /// `log(1 + exp(x))`.
+    Softplus,
+}
+
+impl Activation {
+    pub fn supports(self, dtype: Dtype) -> bool {
+        dtype.is_float()
+    }
+}
+
+/// Shorthands for every [`UnaryOp`] on a tensor type with `unary` and `unary_in_place` methods,
+/// like `eq` and friends for comparisons.
+macro_rules! unary_methods {
+    ($($op:ident => $name:ident, $in_place:ident;)*) => {$(
+        pub fn $name(&mut self, src: &Self) -> &mut Self {
+            self.unary($crate::backends::ops::UnaryOp::$op, src)
+        }
+
+        pub fn $in_place(&mut self) -> &mut Self {
+            self.unary_in_place($crate::backends::ops::UnaryOp::$op)
+        }
+    )*};
+}
+
+pub(crate) use unary_methods;
+
+/// Shorthands for every [`Activation`] but `LeakyRelu`, on a tensor type with `activation` and
+/// `activation_in_place` methods.
+macro_rules! activation_methods {
+    ($($activation:ident => $name:ident, $in_place:ident;)*) => {$(
+        pub fn $name(&mut self, src: &Self) -> &mut Self {
+            self.activation($crate::backends::ops::Activation::$activation, src)
+        }
+
+        pub fn $in_place(&mut self) -> &mut Self {
+            self.activation_in_place($crate::backends::ops::Activation::$activation)
+        }
+    )*};
+}
+
+pub(crate) use activation_methods;
@@ -0,0 +1,73 @@
+use super::{dtype::Dtype, error::GpuMatError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QuantBits {
+    /// Values in `-127..=127`, four to a word.
+    Int8,
+    /// Values in `-7..=7`, eight to a word.
+    Int4,
+}
+
+impl QuantBits {
+    pub(crate) fn bits(self) -> u32 {
+        match self {
+            QuantBits::Int8 => 8,
+            QuantBits::Int4 => 4,
+        }
+    }
+
+    pub(crate) fn max(self) -> u32 {
+        (1 << (self.bits() - 1)) - 1
+    }
+}
+
+/// How a quantized tensor is laid out: blocks of `block_size` consecutive elements share a
+/// scale, set so the largest magnitude in the block maps to the largest integer.
+///
+/// Values are rounded half to even against the scale as stored, so every backend quantizes a
+/// tensor to the same integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct QuantFormat {
+    pub bits: QuantBits,
+    /// A multiple of 8.
+    pub block_size: u32,
+    /// [`Dtype::F16`] or [`Dtype::F32`]. f16 scales do not need shader f16 support.
+    pub scale: Dtype,
+}
+
+impl QuantFormat {
+    pub const INT8: Self = Self {
+        bits: QuantBits::Int8,
+        block_size: 32,
+        scale: Dtype::F32,
+    };
+    pub const INT4: Self = Self {
+        bits: QuantBits::Int4,
+        block_size: 32,
+        scale: Dtype::F32,
+    };
+
+    pub fn with_block_size(self, block_size: u32) -> Self {
+        Self { block_size, ..self }
+    }
+
+    pub fn with_scale(self, scale: Dtype) -> Self {
+        Self { scale, ..self }
+    }
+
+    pub(crate) fn check(self) -> Result<(), GpuMatError> {
+        if self.block_size == 0 || !self.block_size.is_multiple_of(8) {
+            return Err(GpuMatError::InvalidQuantization(format!(
+                "block size {} is not a positive multiple of 8",
+                self.block_size
+            )));
+        }
+        if !matches!(self.scale, Dtype::F16 | Dtype::F32) {
+            return Err(GpuMatError::InvalidQuantization(format!(
+                "scales cannot be stored as {:?}",
+                self.scale
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+use ndarray::{Array, ArrayD, IxDyn};
+
+/// An array of `shape` holding `values`, to compare downloads against.
+pub fn array<T: Clone>(shape: &[usize], values: &[T]) -> ArrayD<T> {
+    Array::from_shape_vec(IxDyn(shape), values.to_vec()).unwrap()
+}
@@ -0,0 +1,696 @@
+use ndarray::{Array, IxDyn};
+use std::{marker::PhantomData, task::Poll};
+
+use super::{
+    Backend, DefaultBackend,
+    dtype::Dtyped,
+    error::GpuMatError,
+    ops::{Activation, UnaryOp},
+};
+
+/// A tensor of the backend `B` whose element type `F` is known at compile time.
+///
+/// Operands must share `F`, so dtype mismatches are caught by the compiler, and results come back
+/// as `Array<F, IxDyn>` instead of a [`DtypeVec`](super::dtype::DtypeVec). The untyped tensor
+/// remains reachable through [`GpuTensorOf::as_untyped`] for everything else.
+pub struct GpuTensorOf<F: Dtyped, B: Backend = DefaultBackend> {
+    tensor: B::Tensor,
+    dtype: PhantomData<F>,
+}
+
+impl<F: Dtyped, B: Backend> GpuTensorOf<F, B> {
+    pub fn new(shape: Vec<u32>, values: &[F]) -> Self {
+        Self::try_new(shape, values).unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_new(shape: Vec<u32>, values: &[F]) -> Result<Self, GpuMatError> {
+        Ok(Self::wrap(B::new(shape, values)?))
+    }
+
+    /// An output tensor of `capacity` bytes, like the untyped `with_capacity`.
+    pub fn with_capacity(capacity: u64) -> Self {
+        Self::try_with_capacity(capacity).unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_with_capacity(capacity: u64) -> Result<Self, GpuMatError> {
+        Ok(Self::wrap(B::with_capacity(capacity)?))
+    }
+
+    /// Checks that `tensor` holds `F` values.
+    pub fn from_untyped(tensor: B::Tensor) -> Result<Self, GpuMatError> {
+        GpuMatError::check_dtypes(F::dtype(), B::dtype(&tensor))?;
+        Ok(Self::wrap(tensor))
+    }
+
+    fn wrap(tensor: B::Tensor) -> Self {
+        Self {
+            tensor,
+            dtype: PhantomData,
+        }
+    }
+
+    pub fn as_untyped(&self) -> &B::Tensor {
+        &self.tensor
+    }
+
+    pub fn into_untyped(self) -> B::Tensor {
+        self.tensor
+    }
+
+    pub fn capacity(&self) -> u64 {
+        B::capacity(&self.tensor)
+    }
+
+    pub fn add(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_add(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_add(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        B::add(&mut self.tensor, &lhs.tensor, &rhs.tensor)?;
+        Ok(self)
+    }
+
+    pub fn increment(&mut self, by: &Self) -> &mut Self {
+        self.try_increment(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_increment(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        B::increment(&mut self.tensor, &by.tensor)?;
+        Ok(self)
+    }
+
+    pub fn mul(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_mul(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_mul(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        B::mul(&mut self.tensor, &lhs.tensor, &rhs.tensor)?;
+        Ok(self)
+    }
+
+    pub fn mul_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_mul_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_mul_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        B::mul_in_place(&mut self.tensor, &by.tensor)?;
+        Ok(self)
+    }
+
+    pub fn sub(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_sub(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_sub(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        B::sub(&mut self.tensor, &lhs.tensor, &rhs.tensor)?;
+        Ok(self)
+    }
+
+    pub fn decrement(&mut self, by: &Self) -> &mut Self {
+        self.try_decrement(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_decrement(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        B::decrement(&mut self.tensor, &by.tensor)?;
+        Ok(self)
+    }
+
+    pub fn rsub_in_place(&mut self, lhs: &Self) -> &mut Self {
+        self.try_rsub_in_place(lhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_rsub_in_place(&mut self, lhs: &Self) -> Result<&mut Self, GpuMatError> {
+        B::rsub_in_place(&mut self.tensor, &lhs.tensor)?;
+        Ok(self)
+    }
+
+    pub fn div(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_div(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_div(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        B::div(&mut self.tensor, &lhs.tensor, &rhs.tensor)?;
+        Ok(self)
+    }
+
+    pub fn div_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_div_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_div_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        B::div_in_place(&mut self.tensor, &by.tensor)?;
+        Ok(self)
+    }
+
+    pub fn rdiv_in_place(&mut self, lhs: &Self) -> &mut Self {
+        self.try_rdiv_in_place(lhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_rdiv_in_place(&mut self, lhs: &Self) -> Result<&mut Self, GpuMatError> {
+        B::rdiv_in_place(&mut self.tensor, &lhs.tensor)?;
+        Ok(self)
+    }
+
+    pub fn add_scalar(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.try_add_scalar(lhs, scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_add_scalar(&mut self, lhs: &Self, scalar: F) -> Result<&mut Self, GpuMatError> {
+        B::add_scalar(&mut self.tensor, &lhs.tensor, scalar)?;
+        Ok(self)
+    }
+
+    pub fn mul_scalar(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.try_mul_scalar(lhs, scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_mul_scalar(&mut self, lhs: &Self, scalar: F) -> Result<&mut Self, GpuMatError> {
+        B::mul_scalar(&mut self.tensor, &lhs.tensor, scalar)?;
+        Ok(self)
+    }
+
+    pub fn shift(&mut self, scalar: F) -> &mut Self {
+        self.try_shift(scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_shift(&mut self, scalar: F) -> Result<&mut Self, GpuMatError> {
+        B::shift(&mut self.tensor, scalar)?;
+        Ok(self)
+    }
+
+    pub fn scale(&mut self, scalar: F) -> &mut Self {
+        self.try_scale(scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_scale(&mut self, scalar: F) -> Result<&mut Self, GpuMatError> {
+        B::scale(&mut self.tensor, scalar)?;
+        Ok(self)
+    }
+
+    pub fn minimum(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_minimum(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Fails unless `F` is a float type. A NaN in either operand gives NaN.
+    pub fn try_minimum(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        B::minimum(&mut self.tensor, &lhs.tensor, &rhs.tensor)?;
+        Ok(self)
+    }
+
+    pub fn maximum(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_maximum(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_maximum(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        B::maximum(&mut self.tensor, &lhs.tensor, &rhs.tensor)?;
+        Ok(self)
+    }
+
+    pub fn minimum_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_minimum_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_minimum_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        B::minimum_in_place(&mut self.tensor, &by.tensor)?;
+        Ok(self)
+    }
+
+    pub fn maximum_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_maximum_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_maximum_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        B::maximum_in_place(&mut self.tensor, &by.tensor)?;
+        Ok(self)
+    }
+
+    pub fn clamp(&mut self, src: &Self, lo: &Self, hi: &Self) -> &mut Self {
+        self.try_clamp(src, lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Takes the maximum with `lo` then the minimum with `hi`, so elements where `lo > hi` get
+    /// `hi`.
+    pub fn try_clamp(
+        &mut self,
+        src: &Self,
+        lo: &Self,
+        hi: &Self,
+    ) -> Result<&mut Self, GpuMatError> {
+        B::clamp(&mut self.tensor, &src.tensor, &lo.tensor, &hi.tensor)?;
+        Ok(self)
+    }
+
+    pub fn clamp_in_place(&mut self, lo: &Self, hi: &Self) -> &mut Self {
+        self.try_clamp_in_place(lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_clamp_in_place(&mut self, lo: &Self, hi: &Self) -> Result<&mut Self, GpuMatError> {
+        B::clamp_in_place(&mut self.tensor, &lo.tensor, &hi.tensor)?;
+        Ok(self)
+    }
+
+    pub fn clamp_scalar(&mut self, src: &Self, lo: F, hi: F) -> &mut Self {
+        self.try_clamp_scalar(src, lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_clamp_scalar(&mut self, src: &Self, lo: F, hi: F) -> Result<&mut Self, GpuMatError> {
+        B::clamp_scalar(&mut self.tensor, &src.tensor, lo, hi)?;
+        Ok(self)
+    }
+
+    pub fn clamp_scalar_in_place(&mut self, lo: F, hi: F) -> &mut Self {
+        self.try_clamp_scalar_in_place(lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_clamp_scalar_in_place(&mut self, lo: F, hi: F) -> Result<&mut Self, GpuMatError> {
+        B::clamp_scalar_in_place(&mut self.tensor, lo, hi)?;
+        Ok(self)
+    }
+
+    pub fn unary(&mut self, op: UnaryOp, src: &Self) -> &mut Self {
+        self.try_unary(op, src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Fails if `op` does not [support](UnaryOp::supports) `F`.
+    pub fn try_unary(&mut self, op: UnaryOp, src: &Self) -> Result<&mut Self, GpuMatError> {
+        B::unary(&mut self.tensor, op, &src.tensor)?;
+        Ok(self)
+    }
+
+    pub fn unary_in_place(&mut self, op: UnaryOp) -> &mut Self {
+        self.try_unary_in_place(op)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_unary_in_place(&mut self, op: UnaryOp) -> Result<&mut Self, GpuMatError> {
+        B::unary_in_place(&mut self.tensor, op)?;
+        Ok(self)
+    }
+
+    pub fn activation(&mut self, activation: Activation, src: &Self) -> &mut Self {
+        self.try_activation(activation, src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Fails unless `F` is a float type.
+    pub fn try_activation(
+        &mut self,
+        activation: Activation,
+        src: &Self,
+    ) -> Result<&mut Self, GpuMatError> {
+        B::activation(&mut self.tensor, activation, &src.tensor)?;
+        Ok(self)
+    }
+
+    pub fn activation_in_place(&mut self, activation: Activation) -> &mut Self {
+        self.try_activation_in_place(activation)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_activation_in_place(
+        &mut self,
+        activation: Activation,
+    ) -> Result<&mut Self, GpuMatError> {
+        B::activation_in_place(&mut self.tensor, activation)?;
+        Ok(self)
+    }
+
+    pub fn cast<G: Dtyped>(&mut self, src: &GpuTensorOf<G, B>) -> &mut Self {
+        self.try_cast(src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Converts `src` to `F`, which fails unless both are float types.
+    pub fn try_cast<G: Dtyped>(
+        &mut self,
+        src: &GpuTensorOf<G, B>,
+    ) -> Result<&mut Self, GpuMatError> {
+        B::cast(&mut self.tensor, &src.tensor, F::dtype())?;
+        Ok(self)
+    }
+
+    pub fn set(&mut self, setter: GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B>>) -> &mut Self {
+        self.try_set(setter)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_set(
+        &mut self,
+        setter: GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B>>,
+    ) -> Result<&mut Self, GpuMatError> {
+        setter.0(&mut self.tensor)?;
+        Ok(self)
+    }
+
+    /// Saves the current values under `name`, they are returned with the untyped results of a
+    /// later computation.
+    pub fn save_intermediate(&self, name: &'static str) -> &Self {
+        self.try_save_intermediate(name)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_save_intermediate(&self, name: &'static str) -> Result<&Self, GpuMatError> {
+        B::save_intermediate(&self.tensor, name)?;
+        Ok(self)
+    }
+
+    pub fn download(&self) -> Array<F, IxDyn> {
+        self.try_download()
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_download(&self) -> Result<Array<F, IxDyn>, GpuMatError> {
+        typed(B::download(&self.tensor)?)
+    }
+
+    pub fn compute(&self) -> ComputeHandleOf<F, B> {
+        ComputeHandleOf {
+            handle: B::compute(&self.tensor),
+            dtype: PhantomData,
+        }
+    }
+}
+
+fn typed<F: Dtyped>(values: super::dtype::DtypeVec) -> Result<Array<F, IxDyn>, GpuMatError> {
+    F::from_dtype_vec(values).map_err(|values| GpuMatError::DtypeMismatch {
+        lhs: F::dtype(),
+        rhs: values.dtype(),
+    })
+}
+
+/// A pending computation of a [`GpuTensorOf`], resolving to its values.
+pub struct ComputeHandleOf<F: Dtyped, B: Backend = DefaultBackend> {
+    handle: B::Handle,
+    dtype: PhantomData<fn() -> F>,
+}
+
+impl<F: Dtyped, B: Backend> ComputeHandleOf<F, B> {
+    pub fn join(self) -> Array<F, IxDyn> {
+        self.try_join()
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_join(self) -> Result<Array<F, IxDyn>, GpuMatError> {
+        typed(B::join(self.handle)?.0)
+    }
+}
+
+impl<F: Dtyped, B: Backend<Handle: Unpin>> Future for ComputeHandleOf<F, B> {
+    type Output = Array<F, IxDyn>;
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        core::pin::Pin::new(&mut self.get_mut().handle)
+            .poll(cx)
+            .map(|(values, _)| typed(values).unwrap_or_else(|err| panic!("GpuMat: {err}")))
+    }
+}
+
+/// A deferred write to a [`GpuTensorOf`] holding `F` values, built by its operators.
+pub struct GpuTensorOfSetterFn<F: Dtyped, B: Backend, Fn: TypedSetterFn<B>>(
+    Fn,
+    PhantomData<(F, B)>,
+);
+
+pub trait TypedSetterFn<B: Backend>: FnOnce(&mut B::Tensor) -> Result<(), GpuMatError> {}
+
+impl<B: Backend, Fn: FnOnce(&mut B::Tensor) -> Result<(), GpuMatError>> TypedSetterFn<B> for Fn {}
+
+impl<F: Dtyped, B: Backend, Fn: TypedSetterFn<B>> GpuTensorOfSetterFn<F, B, Fn> {
+    fn new(setter: Fn) -> Self {
+        Self(setter, PhantomData)
+    }
+
+    pub fn save_intermediate(
+        self,
+        name: &'static str,
+    ) -> GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B>> {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            self.0(target)?;
+
+            B::save_intermediate(target, name)
+        })
+    }
+
+    /// Applies `activation` in place once the expression is written.
+    pub fn activation(
+        self,
+        activation: Activation,
+    ) -> GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B>> {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            self.0(target)?;
+
+            B::activation_in_place(target, activation)?;
+            Ok(())
+        })
+    }
+
+    /// Clamps the expression once written, see [`GpuTensorOf::clamp`].
+    pub fn clamp<'a>(
+        self,
+        lo: &'a GpuTensorOf<F, B>,
+        hi: &'a GpuTensorOf<F, B>,
+    ) -> GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B>> {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            self.0(target)?;
+
+            B::clamp_in_place(target, &lo.tensor, &hi.tensor)?;
+            Ok(())
+        })
+    }
+
+    pub fn clamp_scalar(self, lo: F, hi: F) -> GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B>> {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            self.0(target)?;
+
+            B::clamp_scalar_in_place(target, lo, hi)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend> core::ops::Add for &'a GpuTensorOf<F, B> {
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+    fn add(self, rhs: Self) -> Self::Output {
+        GpuTensorOfSetterFn::new(|target: &mut B::Tensor| {
+            B::add(target, &self.tensor, &rhs.tensor)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend, Fn: TypedSetterFn<B> + 'a> core::ops::Add<&'a GpuTensorOf<F, B>>
+    for GpuTensorOfSetterFn<F, B, Fn>
+{
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+
+    fn add(self, rhs: &'a GpuTensorOf<F, B>) -> Self::Output {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            self.0(target)?;
+
+            B::increment(target, &rhs.tensor)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend, Fn: TypedSetterFn<B> + 'a>
+    core::ops::Add<GpuTensorOfSetterFn<F, B, Fn>> for &'a GpuTensorOf<F, B>
+{
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+
+    fn add(self, rhs: GpuTensorOfSetterFn<F, B, Fn>) -> Self::Output {
+        rhs + self
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend> core::ops::Mul for &'a GpuTensorOf<F, B> {
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+    fn mul(self, rhs: Self) -> Self::Output {
+        GpuTensorOfSetterFn::new(|target: &mut B::Tensor| {
+            B::mul(target, &self.tensor, &rhs.tensor)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend, Fn: TypedSetterFn<B> + 'a> core::ops::Mul<&'a GpuTensorOf<F, B>>
+    for GpuTensorOfSetterFn<F, B, Fn>
+{
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+
+    fn mul(self, rhs: &'a GpuTensorOf<F, B>) -> Self::Output {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            self.0(target)?;
+
+            B::mul_in_place(target, &rhs.tensor)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend, Fn: TypedSetterFn<B> + 'a>
+    core::ops::Mul<GpuTensorOfSetterFn<F, B, Fn>> for &'a GpuTensorOf<F, B>
+{
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+
+    fn mul(self, rhs: GpuTensorOfSetterFn<F, B, Fn>) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend> core::ops::Sub for &'a GpuTensorOf<F, B> {
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+    fn sub(self, rhs: Self) -> Self::Output {
+        GpuTensorOfSetterFn::new(|target: &mut B::Tensor| {
+            B::sub(target, &self.tensor, &rhs.tensor)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend, Fn: TypedSetterFn<B> + 'a> core::ops::Sub<&'a GpuTensorOf<F, B>>
+    for GpuTensorOfSetterFn<F, B, Fn>
+{
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+
+    fn sub(self, rhs: &'a GpuTensorOf<F, B>) -> Self::Output {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            self.0(target)?;
+
+            B::decrement(target, &rhs.tensor)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend, Fn: TypedSetterFn<B> + 'a>
+    core::ops::Sub<GpuTensorOfSetterFn<F, B, Fn>> for &'a GpuTensorOf<F, B>
+{
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+
+    fn sub(self, rhs: GpuTensorOfSetterFn<F, B, Fn>) -> Self::Output {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            rhs.0(target)?;
+
+            B::rsub_in_place(target, &self.tensor)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend> core::ops::Div for &'a GpuTensorOf<F, B> {
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+    fn div(self, rhs: Self) -> Self::Output {
+        GpuTensorOfSetterFn::new(|target: &mut B::Tensor| {
+            B::div(target, &self.tensor, &rhs.tensor)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend, Fn: TypedSetterFn<B> + 'a> core::ops::Div<&'a GpuTensorOf<F, B>>
+    for GpuTensorOfSetterFn<F, B, Fn>
+{
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+
+    fn div(self, rhs: &'a GpuTensorOf<F, B>) -> Self::Output {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            self.0(target)?;
+
+            B::div_in_place(target, &rhs.tensor)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend, Fn: TypedSetterFn<B> + 'a>
+    core::ops::Div<GpuTensorOfSetterFn<F, B, Fn>> for &'a GpuTensorOf<F, B>
+{
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+
+    fn div(self, rhs: GpuTensorOfSetterFn<F, B, Fn>) -> Self::Output {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            rhs.0(target)?;
+
+            B::rdiv_in_place(target, &self.tensor)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend> core::ops::Add<F> for &'a GpuTensorOf<F, B> {
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+
+    fn add(self, rhs: F) -> Self::Output {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            B::add_scalar(target, &self.tensor, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<F: Dtyped, B: Backend, Fn: TypedSetterFn<B>> core::ops::Add<F>
+    for GpuTensorOfSetterFn<F, B, Fn>
+{
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B>>;
+
+    fn add(self, rhs: F) -> Self::Output {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            self.0(target)?;
+
+            B::shift(target, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped, B: Backend> core::ops::Mul<F> for &'a GpuTensorOf<F, B> {
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B> + 'a>;
+
+    fn mul(self, rhs: F) -> Self::Output {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            B::mul_scalar(target, &self.tensor, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<F: Dtyped, B: Backend, Fn: TypedSetterFn<B>> core::ops::Mul<F>
+    for GpuTensorOfSetterFn<F, B, Fn>
+{
+    type Output = GpuTensorOfSetterFn<F, B, impl TypedSetterFn<B>>;
+
+    fn mul(self, rhs: F) -> Self::Output {
+        GpuTensorOfSetterFn::new(move |target: &mut B::Tensor| {
+            self.0(target)?;
+
+            B::scale(target, rhs)?;
+            Ok(())
+        })
+    }
+}
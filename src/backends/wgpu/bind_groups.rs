@@ -1,746 +1,222 @@
-use once_cell::sync::Lazy;
 use std::num::NonZeroU64;
-use wgpu::{
-    BindGroup, BindGroupLayout, Buffer, BufferAddress, ComputePipeline, PipelineLayout,
-    ShaderModule,
+use wgpu::{BindGroup, BindGroupLayout, Buffer, BufferAddress, Device};
+
+use super::{
+    dtype::Dtype,
+    tensor_info::{UniformBroadcast, UniformTensorInfo},
 };
 
-use super::globals::DEVICE_QUEUE;
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// Tensor info, two read only operands and a read write output.
+    Abc,
+    /// Tensor info, a read write operand and a read only one.
+    Ab,
+    /// Tensor info, two read only operands and a read write [`Dtype::Bool`] mask.
+    AbcMask,
+    /// Like [`Layout::AbcMask`] with operands of the given dtypes, followed by their strides.
+    AbcMaskMixed(Dtype, Dtype),
+    /// Tensor info, a read only [`Dtype::Bool`] mask, two read only operands and a read write
+    /// output.
+    MaskAbc,
+    /// Like [`Layout::MaskAbc`] with operands of the given dtypes, followed by the strides of the
+    /// operands and the mask.
+    MaskAbcMixed(Dtype, Dtype),
+    /// Tensor info, a read only operand, and read write quantized words and scales.
+    Quantize,
+    /// Tensor info, read only quantized words and scales, and a read write output.
+    Dequantize,
+    /// Tensor info, a read only operand, read only quantized words and scales, and a read write
+    /// output.
+    AQuantizedC,
+    /// Tensor info, a read write operand, and read only quantized words and scales.
+    AQuantized,
+    /// Tensor info, a read only operand of the given dtype and a read write output.
+    Cast(Dtype),
+    /// Tensor info, a read only operand and a read write output.
+    Unary,
+    /// Tensor info and a read write operand.
+    A,
+    /// Tensor info, a read only operand, a uniform scalar and a read write output.
+    AScalarC,
+    /// Tensor info, a read write operand and a uniform scalar.
+    AScalar,
+    /// Tensor info, a read only operand, a uniform scalar and a read write [`Dtype::Bool`] mask.
+    AScalarMask,
+    /// Tensor info, a read only complex operand and a read write output of its [`Dtype::real`].
+    ComplexPart,
+    /// Tensor info, two read only operands of the given dtypes, a read write output and the
+    /// strides of the operands.
+    AbcMixed(Dtype, Dtype),
+    /// Tensor info, a read write operand, a read only one of the given dtype and the strides of
+    /// the operands.
+    AbMixed(Dtype),
+}
 
-static ABC_F32_BIND_GROUP_LAYOUT: Lazy<BindGroupLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        min_binding_size: Some(unsafe {
-                            NonZeroU64::new(core::mem::size_of::<
-                                super::tensor_info::UniformTensorInfo,
-                            >() as BufferAddress)
-                            .unwrap_unchecked()
-                        }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(4).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(4).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(4).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
+impl Layout {
+    pub fn create(self, device: &Device, dtype: Dtype) -> BindGroupLayout {
+        let storage_of = |binding, read_only, dtype: Dtype| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                min_binding_size: NonZeroU64::new(word_size(dtype)),
+                has_dynamic_offset: false,
+            },
+            count: None,
+        };
+        let storage = |binding, read_only| storage_of(binding, read_only, dtype);
+
+        let scalar = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                min_binding_size: NonZeroU64::new(word_size(dtype)),
+                has_dynamic_offset: false,
+            },
+            count: None,
+        };
+
+        let strides = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                min_binding_size: NonZeroU64::new(
+                    core::mem::size_of::<UniformBroadcast>() as BufferAddress
+                ),
+                has_dynamic_offset: false,
+            },
+            count: None,
+        };
+
+        let info = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                min_binding_size: Some(unsafe {
+                    NonZeroU64::new(core::mem::size_of::<UniformTensorInfo>() as BufferAddress)
+                        .unwrap_unchecked()
+                }),
+                has_dynamic_offset: false,
+            },
+            count: None,
+        };
+
+        let entries = match self {
+            Layout::Abc => vec![info, storage(1, true), storage(2, true), storage(3, false)],
+            Layout::Ab => vec![info, storage(1, false), storage(2, true)],
+            Layout::AbcMask => vec![
+                info,
+                storage(1, true),
+                storage(2, true),
+                storage_of(3, false, Dtype::Bool),
             ],
-        })
-});
-
-static ABC_F64_BIND_GROUP_LAYOUT: Lazy<BindGroupLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        min_binding_size: Some(unsafe {
-                            NonZeroU64::new(core::mem::size_of::<
-                                super::tensor_info::UniformTensorInfo,
-                            >() as BufferAddress)
-                            .unwrap_unchecked()
-                        }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(8).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(8).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(8).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
+            Layout::AbcMaskMixed(a, b) => vec![
+                info,
+                storage_of(1, true, a),
+                storage_of(2, true, b),
+                storage_of(3, false, Dtype::Bool),
+                strides(4),
             ],
-        })
-});
-
-static ABC_F16_BIND_GROUP_LAYOUT: Lazy<BindGroupLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        min_binding_size: Some(unsafe {
-                            NonZeroU64::new(core::mem::size_of::<
-                                super::tensor_info::UniformTensorInfo,
-                            >() as BufferAddress)
-                            .unwrap_unchecked()
-                        }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(2).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(2).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(2).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
+            Layout::MaskAbc => vec![
+                info,
+                storage_of(1, true, Dtype::Bool),
+                storage(2, true),
+                storage(3, true),
+                storage(4, false),
             ],
-        })
-});
-
-static ABC_F32_PIPELINE_LAYOUT: Lazy<PipelineLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[&ABC_F32_BIND_GROUP_LAYOUT],
-            push_constant_ranges: &[],
-        })
-});
-
-static ABC_F64_PIPELINE_LAYOUT: Lazy<PipelineLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[&ABC_F64_BIND_GROUP_LAYOUT],
-            push_constant_ranges: &[],
-        })
-});
-
-static ABC_F16_PIPELINE_LAYOUT: Lazy<PipelineLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[&ABC_F16_BIND_GROUP_LAYOUT],
-            push_constant_ranges: &[],
-        })
-});
-
-static AB_F32_BIND_GROUP_LAYOUT: Lazy<BindGroupLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        min_binding_size: Some(unsafe {
-                            NonZeroU64::new(core::mem::size_of::<
-                                super::tensor_info::UniformTensorInfo,
-                            >() as BufferAddress)
-                            .unwrap_unchecked()
-                        }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(4).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(4).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
+            Layout::MaskAbcMixed(a, b) => vec![
+                info,
+                storage_of(1, true, Dtype::Bool),
+                storage_of(2, true, a),
+                storage_of(3, true, b),
+                storage(4, false),
+                strides(5),
             ],
-        })
-});
-
-static AB_F32_PIPELINE_LAYOUT: Lazy<PipelineLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[&AB_F32_BIND_GROUP_LAYOUT],
-            push_constant_ranges: &[],
-        })
-});
-
-static AB_F64_BIND_GROUP_LAYOUT: Lazy<BindGroupLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        min_binding_size: Some(unsafe {
-                            NonZeroU64::new(core::mem::size_of::<
-                                super::tensor_info::UniformTensorInfo,
-                            >() as BufferAddress)
-                            .unwrap_unchecked()
-                        }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(8).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(8).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
+            Layout::Quantize => vec![
+                info,
+                storage(1, true),
+                storage_of(2, false, Dtype::U32),
+                storage_of(3, false, Dtype::U32),
             ],
-        })
-});
-
-static AB_F64_PIPELINE_LAYOUT: Lazy<PipelineLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[&AB_F64_BIND_GROUP_LAYOUT],
-            push_constant_ranges: &[],
-        })
-});
-
-static AB_F16_BIND_GROUP_LAYOUT: Lazy<BindGroupLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        min_binding_size: Some(unsafe {
-                            NonZeroU64::new(core::mem::size_of::<
-                                super::tensor_info::UniformTensorInfo,
-                            >() as BufferAddress)
-                            .unwrap_unchecked()
-                        }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(2).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        min_binding_size: Some(unsafe { NonZeroU64::new(2).unwrap_unchecked() }),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
-                },
+            Layout::Dequantize => vec![
+                info,
+                storage_of(1, true, Dtype::U32),
+                storage_of(2, true, Dtype::U32),
+                storage(3, false),
             ],
-        })
-});
-
-static AB_F16_PIPELINE_LAYOUT: Lazy<PipelineLayout> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[&AB_F16_BIND_GROUP_LAYOUT],
-            push_constant_ranges: &[],
-        })
-});
-
-static ADD_F32_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/add_f32.wgsl"))
-});
-
-pub static ADD_F32_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&ABC_F32_PIPELINE_LAYOUT),
-            module: &ADD_F32_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-static ADD_F64_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/add_f64.wgsl"))
-});
-
-pub static ADD_F64_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&ABC_F64_PIPELINE_LAYOUT),
-            module: &ADD_F64_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-static ADD_F16_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/add_f16.wgsl"))
-});
-
-pub static ADD_F16_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&ABC_F16_PIPELINE_LAYOUT),
-            module: &ADD_F16_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-static MUL_F32_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/mul_f32.wgsl"))
-});
-
-pub static MUL_F32_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&ABC_F32_PIPELINE_LAYOUT),
-            module: &MUL_F32_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-static MUL_F16_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/mul_f16.wgsl"))
-});
-
-pub static MUL_F16_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&ABC_F16_PIPELINE_LAYOUT),
-            module: &MUL_F16_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-static MUL_F64_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/add_f64.wgsl"))
-});
-
-pub static MUL_F64_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&ABC_F64_PIPELINE_LAYOUT),
-            module: &MUL_F64_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-static INCREMENT_F32_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/increment_f32.wgsl"))
-});
-
-pub static INCREMENT_F32_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&AB_F32_PIPELINE_LAYOUT),
-            module: &INCREMENT_F32_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-static INCREMENT_F64_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/increment_f64.wgsl"))
-});
-
-pub static INCREMENT_F64_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&AB_F64_PIPELINE_LAYOUT),
-            module: &INCREMENT_F64_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-static INCREMENT_F16_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/increment_f16.wgsl"))
-});
-
-pub static INCREMENT_F16_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&AB_F16_PIPELINE_LAYOUT),
-            module: &INCREMENT_F16_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-static MUL_IN_PLACE_F32_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/mul_in_place_f32.wgsl"))
-});
-
-pub static MUL_IN_PLACE_F32_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&AB_F32_PIPELINE_LAYOUT),
-            module: &MUL_IN_PLACE_F32_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-static MUL_IN_PLACE_F16_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/mul_in_place_f16.wgsl"))
-});
-
-pub static MUL_IN_PLACE_F16_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&AB_F16_PIPELINE_LAYOUT),
-            module: &MUL_IN_PLACE_F16_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-static MUL_IN_PLACE_F64_MODULE: Lazy<ShaderModule> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_shader_module(wgpu::include_wgsl!("../wgpu_shaders/mul_in_place_f64.wgsl"))
-});
-
-pub static MUL_IN_PLACE_F64_PIPELINE: Lazy<ComputePipeline> = Lazy::new(|| {
-    DEVICE_QUEUE
-        .0
-        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&AB_F64_PIPELINE_LAYOUT),
-            module: &MUL_IN_PLACE_F64_MODULE,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-});
-
-pub fn abc_f32_bind_group(info: &Buffer, a: &Buffer, b: &Buffer, c: &Buffer) -> BindGroup {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &ABC_F32_BIND_GROUP_LAYOUT,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: info.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: a.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: b.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: c.as_entire_binding(),
-                },
+            Layout::AQuantizedC => vec![
+                info,
+                storage(1, true),
+                storage_of(2, true, Dtype::U32),
+                storage_of(3, true, Dtype::U32),
+                storage(4, false),
             ],
-        })
-}
-
-pub fn abc_f64_bind_group(info: &Buffer, a: &Buffer, b: &Buffer, c: &Buffer) -> BindGroup {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &ABC_F64_BIND_GROUP_LAYOUT,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: info.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: a.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: b.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: c.as_entire_binding(),
-                },
+            Layout::AQuantized => vec![
+                info,
+                storage(1, false),
+                storage_of(2, true, Dtype::U32),
+                storage_of(3, true, Dtype::U32),
             ],
-        })
-}
-
-pub fn abc_f16_bind_group(info: &Buffer, a: &Buffer, b: &Buffer, c: &Buffer) -> BindGroup {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &ABC_F16_BIND_GROUP_LAYOUT,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: info.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: a.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: b.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: c.as_entire_binding(),
-                },
+            Layout::Cast(from) => vec![info, storage_of(1, true, from), storage(2, false)],
+            Layout::Unary => vec![info, storage(1, true), storage(2, false)],
+            Layout::A => vec![info, storage(1, false)],
+            Layout::AScalarC => vec![info, storage(1, true), scalar(2), storage(3, false)],
+            Layout::AScalar => vec![info, storage(1, false), scalar(2)],
+            Layout::AScalarMask => vec![
+                info,
+                storage(1, true),
+                scalar(2),
+                storage_of(3, false, Dtype::Bool),
             ],
-        })
-}
+            Layout::ComplexPart => vec![info, storage(1, true), storage_of(2, false, dtype.real())],
+            Layout::AbcMixed(a, b) => vec![
+                info,
+                storage_of(1, true, a),
+                storage_of(2, true, b),
+                storage(3, false),
+                strides(4),
+            ],
+            Layout::AbMixed(b) => vec![info, storage(1, false), storage_of(2, true, b), strides(3)],
+        };
 
-pub fn ab_f32_bind_group(info: &Buffer, a: &Buffer, b: &Buffer) -> BindGroup {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group(&wgpu::BindGroupDescriptor {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: None,
-            layout: &AB_F32_BIND_GROUP_LAYOUT,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: info.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: a.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: b.as_entire_binding(),
-                },
-            ],
+            entries: &entries,
         })
+    }
 }
 
-pub fn ab_f64_bind_group(info: &Buffer, a: &Buffer, b: &Buffer) -> BindGroup {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &AB_F64_BIND_GROUP_LAYOUT,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: info.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: a.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: b.as_entire_binding(),
-                },
-            ],
-        })
+/// Size of the storage words kernels index, bf16 values and f16 values on devices without shader
+/// f16 are packed in pairs.
+fn word_size(dtype: Dtype) -> BufferAddress {
+    match dtype {
+        Dtype::F16 | Dtype::BF16 => 4,
+        _ => dtype.size() as BufferAddress,
+    }
 }
 
-pub fn ab_f16_bind_group(info: &Buffer, a: &Buffer, b: &Buffer) -> BindGroup {
-    DEVICE_QUEUE
-        .0
-        .create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &AB_F16_BIND_GROUP_LAYOUT,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: info.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: a.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: b.as_entire_binding(),
-                },
-            ],
-        })
+pub fn bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    info: &Buffer,
+    buffers: &[&Buffer],
+) -> BindGroup {
+    let entries = core::iter::once(info)
+        .chain(buffers.iter().copied())
+        .enumerate()
+        .map(|(binding, buffer)| wgpu::BindGroupEntry {
+            binding: binding as u32,
+            resource: buffer.as_entire_binding(),
+        })
+        .collect::<Vec<_>>();
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: None,
+        layout,
+        entries: &entries,
+    })
 }
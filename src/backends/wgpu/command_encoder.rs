@@ -1,38 +1,34 @@
-use once_cell::sync::Lazy;
 use parking_lot::{Mutex, MutexGuard};
-use wgpu::{CommandBuffer, CommandEncoder};
+use wgpu::{CommandBuffer, CommandEncoder, Device};
 
-use super::globals::DEVICE_QUEUE;
-
-static COMMAND_ENCODER: Lazy<GlobalCommandEncoder> = Lazy::new(GlobalCommandEncoder::new);
-
-pub struct GlobalCommandEncoder {
+pub struct SharedCommandEncoder {
     encoder: Mutex<Option<CommandEncoder>>,
 }
 
-impl GlobalCommandEncoder {
-    fn new() -> Self {
+impl SharedCommandEncoder {
+    pub fn new() -> Self {
         Self {
             encoder: Mutex::new(None),
         }
     }
 
-    pub fn lock<'a>() -> GlobalCommandEncoderGuard<'a> {
-        GlobalCommandEncoderGuard {
-            guard: COMMAND_ENCODER.encoder.lock(),
+    pub fn lock<'a>(&'a self, device: &'a Device) -> SharedCommandEncoderGuard<'a> {
+        SharedCommandEncoderGuard {
+            device,
+            guard: self.encoder.lock(),
         }
     }
 }
 
-pub struct GlobalCommandEncoderGuard<'a> {
+pub struct SharedCommandEncoderGuard<'a> {
+    device: &'a Device,
     guard: MutexGuard<'a, Option<CommandEncoder>>,
 }
 
-impl<'a> GlobalCommandEncoderGuard<'a> {
+impl<'a> SharedCommandEncoderGuard<'a> {
     pub fn get(&mut self) -> &mut CommandEncoder {
         self.guard.get_or_insert_with(|| {
-            DEVICE_QUEUE
-                .0
+            self.device
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None })
         })
     }
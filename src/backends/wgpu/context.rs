@@ -0,0 +1,317 @@
+use parking_lot::{Mutex, ReentrantMutex};
+use pollster::FutureExt;
+use std::{collections::HashMap, sync::Arc};
+use wgpu::{
+    Adapter, AdapterInfo, BindGroup, BindGroupLayout, Buffer, ComputePipeline, Device, Queue,
+    SubmissionIndex,
+};
+
+use super::{
+    bind_groups::bind_group,
+    command_encoder::{SharedCommandEncoder, SharedCommandEncoderGuard},
+    context_builder::GpuContextBuilder,
+    download_vec::DownloadGpuTensor,
+    dtype::Dtype,
+    error::GpuMatError,
+    globals::DEFAULT_CONTEXT,
+    pipelines::{Kernel, PipelineCache},
+};
+
+/// A wgpu device together with everything gpu-mat keeps per device: the queue, the pending
+/// command encoder, compiled pipelines and saved intermediates.
+///
+/// Cloning is cheap and yields a handle to the same device. Tensors keep their context alive,
+/// the device is released once the last context and tensor referencing it are dropped.
+#[derive(Clone)]
+pub struct GpuContext {
+    inner: Arc<ContextInner>,
+}
+
+struct ContextInner {
+    adapter_info: Option<AdapterInfo>,
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    encoder: SharedCommandEncoder,
+    pipelines: PipelineCache,
+    intermediates: Mutex<HashMap<&'static str, DownloadGpuTensor>>,
+    scope: ReentrantMutex<()>,
+    lost: Arc<Mutex<Option<String>>>,
+    uncaptured: Arc<Mutex<Option<GpuMatError>>>,
+}
+
+impl GpuContext {
+    /// Creates a new device, isolated from every other context, on the adapter selected by
+    /// [`GpuContextBuilder::from_env`].
+    ///
+    /// Panics if no suitable device is available, see [`GpuContext::try_new`].
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_new() -> Result<Self, GpuMatError> {
+        GpuContextBuilder::from_env().build()
+    }
+
+    pub fn builder() -> GpuContextBuilder {
+        GpuContextBuilder::new()
+    }
+
+    /// Requests a device with the shader f16 and f64 support of `adapter`, except `emulated`.
+    pub(super) fn from_adapter(
+        adapter: &Adapter,
+        emulated: wgpu::Features,
+    ) -> Result<Self, GpuMatError> {
+        let features = adapter
+            .features()
+            .intersection(wgpu::Features::SHADER_F64 | wgpu::Features::SHADER_F16)
+            .difference(emulated);
+        if !features.contains(wgpu::Features::SHADER_F64) {
+            log::info!("f64 values are emulated with df64 arithmetic on this device");
+        }
+        if !features.contains(wgpu::Features::SHADER_F16) {
+            log::info!("f16 values are emulated with packed pairs on this device");
+        }
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: "GpuMat".into(),
+                required_features: features,
+                required_limits: wgpu::Limits::downlevel_defaults(),
+                memory_hints: wgpu::MemoryHints::Performance,
+                trace: wgpu::Trace::Off,
+            })
+            .block_on()
+            .map_err(|err| GpuMatError::RequestDevice(err.to_string()))?;
+
+        let context = Self::with_device(Some(adapter.get_info()), device.into(), queue.into());
+
+        let lost = context.inner.lost.clone();
+        context
+            .inner
+            .device
+            .set_device_lost_callback(move |_reason, message| {
+                log::error!("GpuMat: device lost: {message}");
+                *lost.lock() = Some(message);
+            });
+
+        let uncaptured = context.inner.uncaptured.clone();
+        context
+            .inner
+            .device
+            .on_uncaptured_error(Box::new(move |err| {
+                log::error!("GpuMat: {err}");
+                uncaptured.lock().get_or_insert(err.into());
+            }));
+
+        Ok(context)
+    }
+
+    /// Attaches to a device and queue owned by the host application, so tensors live next to its
+    /// own resources.
+    ///
+    /// The host keeps its device lost and uncaptured error handlers. Errors raised by gpu-mat calls
+    /// are still reported through their results, but [`GpuContext::status`] only sees device loss
+    /// once a later call fails.
+    pub fn from_device(device: Arc<Device>, queue: Arc<Queue>) -> Self {
+        Self::with_device(None, device, queue)
+    }
+
+    fn with_device(
+        adapter_info: Option<AdapterInfo>,
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(ContextInner {
+                adapter_info,
+                device,
+                queue,
+                encoder: SharedCommandEncoder::new(),
+                pipelines: PipelineCache::new(),
+                intermediates: Mutex::new(HashMap::new()),
+                scope: ReentrantMutex::new(()),
+                lost: Arc::new(Mutex::new(None)),
+                uncaptured: Arc::new(Mutex::new(None)),
+            }),
+        }
+    }
+
+    /// The context used by every constructor without an `_in` suffix, created on first use.
+    ///
+    /// Panics if the default context could not be created, see [`GpuContext::try_global`].
+    pub fn global() -> &'static Self {
+        Self::try_global().unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Creates the default context if needed and reports why it is unavailable, if it is.
+    pub fn try_global() -> Result<&'static Self, GpuMatError> {
+        DEFAULT_CONTEXT.as_ref().map_err(Clone::clone)
+    }
+
+    /// The adapter the device was requested from, unknown for [`GpuContext::from_device`].
+    pub fn adapter_info(&self) -> Option<&AdapterInfo> {
+        self.inner.adapter_info.as_ref()
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.inner.device
+    }
+
+    pub fn queue(&self) -> &Queue {
+        &self.inner.queue
+    }
+
+    /// Whether f64 tensors are stored and computed as df64, because the device lacks shader f64
+    /// or [`GpuContextBuilder::emulate_f64`] ignored it.
+    ///
+    /// Each value is then a pair of f32 `(hi, lo)` summing to it, also in [`GpuTensor::raw`]
+    /// buffers, and C128 parts are stored the same way. Uploads round to about 48 significant
+    /// bits, and add and mul have a relative error below `2^-44`, compared to `2^-53` natively.
+    /// The range stays that of f32: magnitudes above about `1e34` lose the extra precision or
+    /// overflow, and below about `1e-38` flush to zero.
+    ///
+    /// [`GpuTensor::raw`]: super::GpuTensor::raw
+    pub fn emulates_f64(&self) -> bool {
+        !self
+            .inner
+            .device
+            .features()
+            .contains(wgpu::Features::SHADER_F64)
+    }
+
+    /// Whether f16 tensors are packed in pairs of a u32 and computed as f32, because the device
+    /// lacks shader f16 or [`GpuContextBuilder::emulate_f16`] ignored it.
+    pub fn emulates_f16(&self) -> bool {
+        !self
+            .inner
+            .device
+            .features()
+            .contains(wgpu::Features::SHADER_F16)
+    }
+
+    /// Submits every operation recorded so far, making their results visible to other users of
+    /// the device.
+    pub fn flush(&self) -> Result<SubmissionIndex, GpuMatError> {
+        self.scoped(|| {
+            let command_buffer = self.encoder().finish();
+            self.queue().submit([command_buffer])
+        })
+    }
+
+    /// Reports a lost device, or the first wgpu error raised outside of a gpu-mat call since the
+    /// last time it was reported.
+    pub fn status(&self) -> Result<(), GpuMatError> {
+        if let Some(message) = self.inner.lost.lock().clone() {
+            return Err(GpuMatError::DeviceLost(message));
+        }
+        match self.inner.uncaptured.lock().take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs `f` inside out of memory and validation error scopes, so failing wgpu calls turn into
+    /// an error instead of reaching the uncaptured error handler.
+    pub(super) fn scoped<T>(&self, f: impl FnOnce() -> T) -> Result<T, GpuMatError> {
+        let _scope = self.inner.scope.lock();
+        if let Some(message) = self.inner.lost.lock().clone() {
+            return Err(GpuMatError::DeviceLost(message));
+        }
+
+        self.inner
+            .device
+            .push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        self.inner
+            .device
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        let value = f();
+        let validation = self.inner.device.pop_error_scope().block_on();
+        let out_of_memory = self.inner.device.pop_error_scope().block_on();
+
+        match out_of_memory.or(validation) {
+            Some(err) => Err(err.into()),
+            None => Ok(value),
+        }
+    }
+
+    pub(super) fn encoder(&self) -> SharedCommandEncoderGuard<'_> {
+        self.inner.encoder.lock(&self.inner.device)
+    }
+
+    pub(super) fn pipeline(
+        &self,
+        kernel: Kernel,
+        dtype: Dtype,
+    ) -> Result<(ComputePipeline, BindGroupLayout), GpuMatError> {
+        self.scoped(|| self.inner.pipelines.get(&self.inner.device, kernel, dtype))
+            .inspect_err(|_| self.inner.pipelines.evict(kernel, dtype))
+    }
+
+    /// Records `kernel` over `threads` invocations, bound to `info` followed by `buffers`.
+    pub(super) fn dispatch(
+        &self,
+        kernel: Kernel,
+        dtype: Dtype,
+        info: &Buffer,
+        buffers: &[&Buffer],
+        threads: u64,
+    ) -> Result<(), GpuMatError> {
+        let (pipeline, layout) = self.pipeline(kernel, dtype)?;
+        self.scoped(|| {
+            let bind_group = self.bind_group(&layout, info, buffers);
+
+            let mut encoder = self.encoder();
+            let mut compute_pass = encoder
+                .get()
+                .begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: None,
+                    timestamp_writes: None,
+                });
+
+            compute_pass.set_pipeline(&pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+
+            let workgroup_count = threads.div_ceil(64);
+            compute_pass.dispatch_workgroups(workgroup_count as u32, 1, 1);
+        })
+    }
+
+    pub(super) fn bind_group(
+        &self,
+        layout: &BindGroupLayout,
+        info: &Buffer,
+        buffers: &[&Buffer],
+    ) -> BindGroup {
+        bind_group(&self.inner.device, layout, info, buffers)
+    }
+
+    pub(super) fn intermediates(
+        &self,
+    ) -> parking_lot::MutexGuard<'_, HashMap<&'static str, DownloadGpuTensor>> {
+        self.inner.intermediates.lock()
+    }
+}
+
+impl Default for GpuContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for GpuContext {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for GpuContext {}
+
+impl From<wgpu::Error> for GpuMatError {
+    fn from(err: wgpu::Error) -> Self {
+        match err {
+            wgpu::Error::OutOfMemory { source } => GpuMatError::OutOfMemory(source.to_string()),
+            wgpu::Error::Validation { description, .. }
+            | wgpu::Error::Internal { description, .. } => GpuMatError::Validation(description),
+        }
+    }
+}
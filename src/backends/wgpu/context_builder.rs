@@ -0,0 +1,157 @@
+use pollster::FutureExt;
+use wgpu::{AdapterInfo, Backends, Features, Instance, PowerPreference};
+
+use super::{context::GpuContext, error::GpuMatError};
+
+/// Chooses the adapter a [`GpuContext`] runs on.
+///
+/// [`GpuContextBuilder::from_env`] starts from the defaults and applies these overrides:
+///
+/// - `GPUMAT_BACKEND`: comma separated wgpu backends, e.g. `vulkan`, `gl` or `vulkan,metal`.
+/// - `GPUMAT_POWER_PREFERENCE`: `high`, `low` or `none`.
+/// - `GPUMAT_FORCE_FALLBACK`: `1` or `true` to only accept a software adapter.
+/// - `GPUMAT_ADAPTER`: only accept adapters whose name contains this string.
+/// - `GPUMAT_EMULATE_F16`: `1` or `true` to emulate f16 even if the device supports it.
+/// - `GPUMAT_EMULATE_F64`: `1` or `true` to emulate f64 with df64 even if the device supports it.
+#[derive(Clone, Debug)]
+pub struct GpuContextBuilder {
+    backends: Backends,
+    power_preference: PowerPreference,
+    force_fallback_adapter: bool,
+    adapter_name: Option<String>,
+    emulated: Features,
+}
+
+impl GpuContextBuilder {
+    pub fn new() -> Self {
+        Self {
+            backends: Backends::all(),
+            power_preference: PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            adapter_name: None,
+            emulated: Features::empty(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let mut builder = Self::new();
+
+        if let Ok(backends) = std::env::var("GPUMAT_BACKEND") {
+            builder.backends = Backends::from_comma_list(&backends);
+        }
+        if let Ok(power_preference) = std::env::var("GPUMAT_POWER_PREFERENCE") {
+            match power_preference.to_lowercase().as_str() {
+                "high" => builder.power_preference = PowerPreference::HighPerformance,
+                "low" => builder.power_preference = PowerPreference::LowPower,
+                "none" => builder.power_preference = PowerPreference::None,
+                other => log::warn!("GpuMat: unknown GPUMAT_POWER_PREFERENCE '{other}'"),
+            }
+        }
+        if let Ok(force_fallback) = std::env::var("GPUMAT_FORCE_FALLBACK") {
+            builder.force_fallback_adapter = matches!(force_fallback.as_str(), "1" | "true");
+        }
+        if let Ok(adapter_name) = std::env::var("GPUMAT_ADAPTER") {
+            builder.adapter_name = Some(adapter_name);
+        }
+        if let Ok(emulate_f16) = std::env::var("GPUMAT_EMULATE_F16") {
+            builder = builder.emulate_f16(matches!(emulate_f16.as_str(), "1" | "true"));
+        }
+        if let Ok(emulate_f64) = std::env::var("GPUMAT_EMULATE_F64") {
+            builder = builder.emulate_f64(matches!(emulate_f64.as_str(), "1" | "true"));
+        }
+
+        builder
+    }
+
+    pub fn backends(mut self, backends: Backends) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    pub fn power_preference(mut self, power_preference: PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    pub fn force_fallback_adapter(mut self, force_fallback_adapter: bool) -> Self {
+        self.force_fallback_adapter = force_fallback_adapter;
+        self
+    }
+
+    pub fn adapter_name(mut self, adapter_name: impl Into<String>) -> Self {
+        self.adapter_name = Some(adapter_name.into());
+        self
+    }
+
+    /// Ignores shader f16 support, so f16 values are packed in pairs like on devices without it.
+    pub fn emulate_f16(mut self, emulate_f16: bool) -> Self {
+        self.emulated.set(Features::SHADER_F16, emulate_f16);
+        self
+    }
+
+    /// Ignores shader f64 support, so f64 and c128 values are computed as df64, see
+    /// [`GpuContext::emulates_f64`].
+    pub fn emulate_f64(mut self, emulate_f64: bool) -> Self {
+        self.emulated.set(Features::SHADER_F64, emulate_f64);
+        self
+    }
+
+    /// Lists every adapter available on the selected backends.
+    pub fn adapters(&self) -> Vec<AdapterInfo> {
+        self.instance()
+            .enumerate_adapters(self.backends)
+            .iter()
+            .map(|adapter| adapter.get_info())
+            .collect()
+    }
+
+    pub fn build(self) -> Result<GpuContext, GpuMatError> {
+        let instance = self.instance();
+
+        let adapter = match &self.adapter_name {
+            Some(name) => instance
+                .enumerate_adapters(self.backends)
+                .into_iter()
+                .find(|adapter| {
+                    let info = adapter.get_info();
+                    info.name.to_lowercase().contains(&name.to_lowercase())
+                        && (!self.force_fallback_adapter
+                            || info.device_type == wgpu::DeviceType::Cpu)
+                })
+                .ok_or_else(|| GpuMatError::NoAdapter(format!("no adapter named {name}")))?,
+            None => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: self.power_preference,
+                    force_fallback_adapter: self.force_fallback_adapter,
+                    compatible_surface: None,
+                })
+                .block_on()
+                .map_err(|err| GpuMatError::NoAdapter(err.to_string()))?,
+        };
+
+        if !adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+        {
+            return Err(GpuMatError::NoComputeShaders {
+                adapter: adapter.get_info().name,
+            });
+        }
+
+        GpuContext::from_adapter(&adapter, self.emulated)
+    }
+
+    fn instance(&self) -> Instance {
+        Instance::new(&wgpu::InstanceDescriptor {
+            backends: self.backends,
+            ..Default::default()
+        })
+    }
+}
+
+impl Default for GpuContextBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
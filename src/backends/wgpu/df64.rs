@@ -0,0 +1,26 @@
+//! Host side of the df64 representation used when a device lacks shader f64, see
+//! [`GpuContext::emulates_f64`](super::GpuContext::emulates_f64).
+
+/// Splits each value into the nearest f32 and the f32 nearest to the remainder.
+pub fn encode(values: &[f64]) -> Vec<[f32; 2]> {
+    values
+        .iter()
+        .map(|&value| {
+            let hi = value as f32;
+            let lo = if hi.is_finite() {
+                (value - hi as f64) as f32
+            } else {
+                0.
+            };
+            [hi, lo]
+        })
+        .collect()
+}
+
+/// Sums the `(hi, lo)` pairs of a mapped buffer back into f64 values.
+pub fn decode(data: &[u8]) -> Vec<f64> {
+    bytemuck::pod_collect_to_vec::<u8, [f32; 2]>(data)
+        .into_iter()
+        .map(|[hi, lo]| hi as f64 + lo as f64)
+        .collect()
+}
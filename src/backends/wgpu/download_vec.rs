@@ -1,6 +1,11 @@
 use wgpu::{Buffer, BufferAddress};
 
-use super::{dtype::Dtype, globals::DEVICE_QUEUE};
+use super::{
+    context::GpuContext,
+    df64,
+    dtype::{Dtype, DtypeVec},
+    error::GpuMatError,
+};
 
 pub struct DownloadGpuTensor {
     shape: Vec<u32>,
@@ -9,18 +14,25 @@ pub struct DownloadGpuTensor {
 }
 
 impl DownloadGpuTensor {
-    pub fn new(size: BufferAddress, shape: Vec<u32>, dtype: Dtype) -> Self {
-        let buffer = DEVICE_QUEUE.0.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-            mapped_at_creation: false,
-        });
-        Self {
+    pub fn new(
+        context: &GpuContext,
+        size: BufferAddress,
+        shape: Vec<u32>,
+        dtype: Dtype,
+    ) -> Result<Self, GpuMatError> {
+        let buffer = context.scoped(|| {
+            context.device().create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            })
+        })?;
+        Ok(Self {
             buffer,
             shape,
             dtype,
-        }
+        })
     }
 
     pub fn buffer(&self) -> &Buffer {
@@ -34,4 +46,15 @@ impl DownloadGpuTensor {
     pub fn dtype(&self) -> &Dtype {
         &self.dtype
     }
+
+    /// Reads the mapped buffer, decoding df64 values when `context` emulates f64.
+    pub fn to_vec(&self, context: &GpuContext) -> DtypeVec {
+        let data = self.buffer.slice(..).get_mapped_range();
+        match self.dtype {
+            dtype @ (Dtype::F64 | Dtype::C128) if context.emulates_f64() => {
+                dtype.to_vec(bytemuck::cast_slice(&df64::decode(&data)), &self.shape)
+            }
+            dtype => dtype.to_vec(&data, &self.shape),
+        }
+    }
 }
@@ -1,100 +1,143 @@
-use once_cell::sync::Lazy;
 use parking_lot::Mutex;
-use std::{
-    collections::HashMap,
-    sync::atomic::AtomicBool,
-    sync::{Arc, atomic::Ordering},
-    task::Poll,
-};
-use wgpu::SubmissionIndex;
+use std::{sync::Arc, task::Poll};
+use wgpu::{BufferAsyncError, SubmissionIndex};
 
-use super::{download_vec::DownloadGpuTensor, dtype::DtypeVec, globals::DEVICE_QUEUE};
-
-pub static INTERMEDIATES_MAP: Lazy<Mutex<HashMap<&'static str, DownloadGpuTensor>>> =
-    Lazy::new(|| Mutex::new(HashMap::new()));
+use super::{Computed, context::GpuContext, download_vec::DownloadGpuTensor, error::GpuMatError};
 
 pub struct ComputeHandle {
+    context: GpuContext,
+    state: Option<Result<Pending, GpuMatError>>,
+}
+
+struct Pending {
     output: DownloadGpuTensor,
     submission: SubmissionIndex,
-    ready: Arc<AtomicBool>,
+    mapped: Arc<Mutex<Option<Result<(), BufferAsyncError>>>>,
 }
 
 impl ComputeHandle {
-    pub fn new(output: DownloadGpuTensor, submission: SubmissionIndex) -> Self {
-        INTERMEDIATES_MAP
-            .lock()
-            .iter()
-            .map(|(_k, vec)| vec.buffer().slice(..))
+    pub fn new(
+        context: GpuContext,
+        output: DownloadGpuTensor,
+        submission: SubmissionIndex,
+    ) -> Self {
+        context
+            .intermediates()
+            .values()
+            .map(|vec| vec.buffer().slice(..))
             .for_each(|buffer_slice| buffer_slice.map_async(wgpu::MapMode::Read, |_| {}));
 
-        let ready = Arc::new(AtomicBool::new(false));
-        let ready_clone = ready.clone();
+        let mapped = Arc::new(Mutex::new(None));
+        let mapped_clone = mapped.clone();
         output
             .buffer()
             .slice(..)
-            .map_async(wgpu::MapMode::Read, move |_result| {
-                ready_clone.store(true, Ordering::Relaxed);
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *mapped_clone.lock() = Some(result);
             });
 
         Self {
-            output,
-            submission,
-            ready,
+            context,
+            state: Some(Ok(Pending {
+                output,
+                submission,
+                mapped,
+            })),
+        }
+    }
+
+    pub fn failed(context: GpuContext, err: GpuMatError) -> Self {
+        Self {
+            context,
+            state: Some(Err(err)),
         }
     }
 
-    pub fn join(self) -> (DtypeVec, HashMap<&'static str, DtypeVec>) {
-        DEVICE_QUEUE
-            .0
-            .poll(wgpu::PollType::WaitForSubmissionIndex(self.submission))
-            .unwrap();
-
-        let intermediates = INTERMEDIATES_MAP
-            .lock()
-            .drain()
-            .map(|(k, vec)| {
-                let data = vec.buffer().slice(..).get_mapped_range();
-                let vec = vec.dtype().to_vec(&data, vec.shape());
-                (k, vec)
-            })
+    pub fn join(self) -> Computed {
+        self.try_join()
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_join(mut self) -> Result<Computed, GpuMatError> {
+        let pending = self.take_state()?;
+        self.context
+            .device()
+            .poll(wgpu::PollType::WaitForSubmissionIndex(
+                pending.submission.clone(),
+            ))
+            .map_err(|err| GpuMatError::Download(err.to_string()))?;
+
+        self.finish(pending)
+    }
+
+    /// Turns the handle into a future that resolves to an error instead of panicking.
+    pub fn fallible(self) -> FallibleComputeHandle {
+        FallibleComputeHandle(self)
+    }
+
+    fn take_state(&mut self) -> Result<Pending, GpuMatError> {
+        self.state
+            .take()
+            .expect("GpuMat: ComputeHandle polled after completion")
+    }
+
+    fn finish(&self, pending: Pending) -> Result<Computed, GpuMatError> {
+        let intermediates = self.context.intermediates().drain().collect::<Vec<_>>();
+
+        self.context.status()?;
+        match pending.mapped.lock().take() {
+            Some(Ok(())) => {}
+            Some(Err(err)) => return Err(GpuMatError::Download(err.to_string())),
+            None => return Err(GpuMatError::Download("output was never mapped".into())),
+        }
+
+        let intermediates = intermediates
+            .into_iter()
+            .map(|(k, vec)| (k, vec.to_vec(&self.context)))
             .collect();
-        let output_data = self.output.buffer().slice(..).get_mapped_range();
-        (
-            self.output
-                .dtype()
-                .to_vec(&output_data, self.output.shape()),
-            intermediates,
-        )
+        Ok((pending.output.to_vec(&self.context), intermediates))
+    }
+
+    fn poll_result(
+        &mut self,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<Result<Computed, GpuMatError>> {
+        let ready = match &self.state {
+            Some(Ok(pending)) => pending.mapped.lock().is_some(),
+            _ => true,
+        };
+        if !ready {
+            cx.waker().wake_by_ref();
+            if let Err(err) = self.context.device().poll(wgpu::PollType::Poll) {
+                return Poll::Ready(Err(GpuMatError::Download(err.to_string())));
+            }
+            return Poll::Pending;
+        }
+
+        Poll::Ready(self.take_state().and_then(|pending| self.finish(pending)))
     }
 }
 
 impl Future for ComputeHandle {
-    type Output = (DtypeVec, HashMap<&'static str, DtypeVec>);
+    type Output = Computed;
     fn poll(
         self: core::pin::Pin<&mut Self>,
         cx: &mut core::task::Context<'_>,
     ) -> Poll<Self::Output> {
-        if !self.ready.load(Ordering::Relaxed) {
-            cx.waker().wake_by_ref();
-            DEVICE_QUEUE.0.poll(wgpu::PollType::Poll).unwrap();
-            return Poll::Pending;
-        }
+        self.get_mut()
+            .poll_result(cx)
+            .map(|result| result.unwrap_or_else(|err| panic!("GpuMat: {err}")))
+    }
+}
 
-        let intermediates = INTERMEDIATES_MAP
-            .lock()
-            .drain()
-            .map(|(k, vec)| {
-                let data = vec.buffer().slice(..).get_mapped_range();
-                let vec = vec.dtype().to_vec(&data, vec.shape());
-                (k, vec)
-            })
-            .collect();
-        let output_data = self.output.buffer().slice(..).get_mapped_range();
-        Poll::Ready((
-            self.output
-                .dtype()
-                .to_vec(&output_data, self.output.shape()),
-            intermediates,
-        ))
+pub struct FallibleComputeHandle(ComputeHandle);
+
+impl Future for FallibleComputeHandle {
+    type Output = Result<Computed, GpuMatError>;
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        self.get_mut().0.poll_result(cx)
     }
 }
@@ -1,20 +1,349 @@
 mod bind_groups;
 mod command_encoder;
+mod context;
+mod context_builder;
+mod df64;
 mod download_vec;
-mod dtype;
 mod globals;
 mod handle;
+mod pipelines;
+mod quantized;
 mod tensor;
 mod tensor_info;
 mod vec;
 
-pub use tensor::GpuTensor;
+use super::{
+    Backend, Computed, dtype,
+    dtype::{Dtype, DtypeVec, Dtyped},
+    error::{self, GpuMatError},
+    ops::{self, Activation, CompareOp, UnaryOp},
+    quantization,
+};
+
+pub use context::GpuContext;
+pub use context_builder::GpuContextBuilder;
+pub use handle::{ComputeHandle, FallibleComputeHandle};
+pub use quantization::{QuantBits, QuantFormat};
+pub use quantized::QuantizedTensor;
+pub use tensor::{GpuTensor, RawBuffer};
+
+pub struct Wgpu;
+
+impl Backend for Wgpu {
+    type Tensor = GpuTensor;
+    type Handle = ComputeHandle;
+    type Quantized = QuantizedTensor;
+
+    fn new<F: Dtyped>(shape: Vec<u32>, values: &[F]) -> Result<GpuTensor, GpuMatError> {
+        GpuTensor::try_new(shape, values)
+    }
+
+    fn with_capacity(capacity: u64) -> Result<GpuTensor, GpuMatError> {
+        GpuTensor::try_with_capacity(capacity)
+    }
+
+    fn upload(values: &DtypeVec) -> Result<GpuTensor, GpuMatError> {
+        GpuTensor::try_upload(values)
+    }
+
+    fn download(tensor: &GpuTensor) -> Result<DtypeVec, GpuMatError> {
+        tensor.try_download()
+    }
+
+    fn capacity(tensor: &GpuTensor) -> u64 {
+        tensor.capacity()
+    }
+
+    fn dtype(tensor: &GpuTensor) -> Dtype {
+        tensor.dtype()
+    }
+
+    fn add<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_add(lhs, rhs)
+    }
+
+    fn increment<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_increment(by)
+    }
+
+    fn mul<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_mul(lhs, rhs)
+    }
+
+    fn mul_in_place<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_mul_in_place(by)
+    }
+
+    fn sub<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_sub(lhs, rhs)
+    }
+
+    fn decrement<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_decrement(by)
+    }
+
+    fn rsub_in_place<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_rsub_in_place(lhs)
+    }
+
+    fn div<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_div(lhs, rhs)
+    }
+
+    fn div_in_place<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_div_in_place(by)
+    }
+
+    fn rdiv_in_place<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_rdiv_in_place(lhs)
+    }
+
+    fn add_scalar<'a, F: Dtyped>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        scalar: F,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_add_scalar(lhs, scalar)
+    }
+
+    fn mul_scalar<'a, F: Dtyped>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        scalar: F,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_mul_scalar(lhs, scalar)
+    }
+
+    fn shift<F: Dtyped>(tensor: &mut GpuTensor, scalar: F) -> Result<&mut GpuTensor, GpuMatError> {
+        tensor.try_shift(scalar)
+    }
+
+    fn scale<F: Dtyped>(tensor: &mut GpuTensor, scalar: F) -> Result<&mut GpuTensor, GpuMatError> {
+        tensor.try_scale(scalar)
+    }
+
+    fn minimum<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_minimum(lhs, rhs)
+    }
+
+    fn maximum<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_maximum(lhs, rhs)
+    }
+
+    fn minimum_in_place<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_minimum_in_place(by)
+    }
+
+    fn maximum_in_place<'a>(
+        target: &'a mut GpuTensor,
+        by: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_maximum_in_place(by)
+    }
+
+    fn clamp<'a>(
+        target: &'a mut GpuTensor,
+        src: &GpuTensor,
+        lo: &GpuTensor,
+        hi: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_clamp(src, lo, hi)
+    }
+
+    fn clamp_in_place<'a>(
+        target: &'a mut GpuTensor,
+        lo: &GpuTensor,
+        hi: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_clamp_in_place(lo, hi)
+    }
+
+    fn clamp_scalar<'a, F: Dtyped>(
+        target: &'a mut GpuTensor,
+        src: &GpuTensor,
+        lo: F,
+        hi: F,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_clamp_scalar(src, lo, hi)
+    }
+
+    fn clamp_scalar_in_place<F: Dtyped>(
+        tensor: &mut GpuTensor,
+        lo: F,
+        hi: F,
+    ) -> Result<&mut GpuTensor, GpuMatError> {
+        tensor.try_clamp_scalar_in_place(lo, hi)
+    }
+
+    fn compare<'a>(
+        target: &'a mut GpuTensor,
+        op: CompareOp,
+        lhs: &GpuTensor,
+        rhs: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_compare(op, lhs, rhs)
+    }
+
+    fn compare_scalar<'a, F: Dtyped>(
+        target: &'a mut GpuTensor,
+        op: CompareOp,
+        lhs: &GpuTensor,
+        scalar: F,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_compare_scalar(op, lhs, scalar)
+    }
+
+    fn select<'a>(
+        target: &'a mut GpuTensor,
+        mask: &GpuTensor,
+        a: &GpuTensor,
+        b: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_select(mask, a, b)
+    }
+
+    fn unary<'a>(
+        target: &'a mut GpuTensor,
+        op: UnaryOp,
+        src: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_unary(op, src)
+    }
+
+    fn unary_in_place(tensor: &mut GpuTensor, op: UnaryOp) -> Result<&mut GpuTensor, GpuMatError> {
+        tensor.try_unary_in_place(op)
+    }
+
+    fn activation<'a>(
+        target: &'a mut GpuTensor,
+        activation: Activation,
+        src: &GpuTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_activation(activation, src)
+    }
+
+    fn activation_in_place(
+        tensor: &mut GpuTensor,
+        activation: Activation,
+    ) -> Result<&mut GpuTensor, GpuMatError> {
+        tensor.try_activation_in_place(activation)
+    }
+
+    fn cast<'a>(
+        target: &'a mut GpuTensor,
+        src: &GpuTensor,
+        to: Dtype,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_cast(src, to)
+    }
+
+    fn quantize(tensor: &GpuTensor, format: QuantFormat) -> Result<QuantizedTensor, GpuMatError> {
+        QuantizedTensor::try_quantize(tensor, format)
+    }
+
+    fn dequantize<'a>(
+        target: &'a mut GpuTensor,
+        quantized: &QuantizedTensor,
+        dtype: Dtype,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_dequantize(quantized, dtype)
+    }
+
+    fn add_quantized<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &QuantizedTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_add_quantized(lhs, rhs)
+    }
+
+    fn increment_quantized<'a>(
+        target: &'a mut GpuTensor,
+        by: &QuantizedTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_increment_quantized(by)
+    }
+
+    fn mul_quantized<'a>(
+        target: &'a mut GpuTensor,
+        lhs: &GpuTensor,
+        rhs: &QuantizedTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_mul_quantized(lhs, rhs)
+    }
+
+    fn mul_in_place_quantized<'a>(
+        target: &'a mut GpuTensor,
+        by: &QuantizedTensor,
+    ) -> Result<&'a mut GpuTensor, GpuMatError> {
+        target.try_mul_in_place_quantized(by)
+    }
+
+    fn save_intermediate(tensor: &GpuTensor, name: &'static str) -> Result<(), GpuMatError> {
+        tensor.try_save_intermediate(name)?;
+        Ok(())
+    }
+
+    fn compute(tensor: &GpuTensor) -> ComputeHandle {
+        tensor.compute()
+    }
+
+    fn join(handle: ComputeHandle) -> Result<Computed, GpuMatError> {
+        handle.try_join()
+    }
+}
 
 #[cfg(test)]
 mod tests {
     //env_logger::init_from_env(env_logger::Env::default().default_filter_or("warn"));
 
     use super::*;
+    use crate::backends::test_util::array;
 
     #[test]
     fn add_f32() {
@@ -49,9 +378,9 @@ mod tests {
 
         type F = f32;
 
-        let a = &tensor::GpuTensor::new::<F>(vec![100, 100, 100], &[1.; 1000_000]);
-        let b = &tensor::GpuTensor::new::<F>(vec![100, 100, 100], &[2.; 1000_000]);
-        let c = &tensor::GpuTensor::new::<F>(vec![100, 100, 100], &[0.5; 1000_000]);
+        let a = &tensor::GpuTensor::new::<F>(vec![100, 100, 100], &[1.; 1_000_000]);
+        let b = &tensor::GpuTensor::new::<F>(vec![100, 100, 100], &[2.; 1_000_000]);
+        let c = &tensor::GpuTensor::new::<F>(vec![100, 100, 100], &[0.5; 1_000_000]);
         let d = &mut tensor::GpuTensor::with_capacity(a.capacity());
 
         assert!(
@@ -96,4 +425,1154 @@ mod tests {
                 ))
         );
     }
+
+    #[test]
+    fn isolated_contexts() {
+        use pollster::FutureExt;
+
+        type F = f32;
+
+        let first = &GpuContext::new();
+        let second = &GpuContext::new();
+        assert!(first != second);
+
+        let a = &tensor::GpuTensor::new_in(first, vec![2], &[1. as F; 2]);
+        let b = &tensor::GpuTensor::new_in(second, vec![2], &[2. as F; 2]);
+        let c = &mut tensor::GpuTensor::with_capacity_in(second, b.capacity());
+
+        a.save_intermediate("a");
+        let (output, intermediates) = c.set((b + b).save_intermediate("c")).compute().block_on();
+        assert!(
+            output
+                == super::dtype::DtypeVec::F32(ndarray::Array::from_elem(ndarray::IxDyn(&[2]), 4.))
+        );
+        assert!(intermediates.len() == 1 && intermediates.contains_key("c"));
+
+        let (_, intermediates) = a.compute().join();
+        assert!(intermediates.len() == 1 && intermediates.contains_key("a"));
+    }
+
+    #[test]
+    fn context_builder() {
+        let builder = GpuContext::builder()
+            .backends(::wgpu::Backends::all())
+            .power_preference(::wgpu::PowerPreference::LowPower);
+        let adapters = builder.adapters();
+        assert!(!adapters.is_empty());
+
+        let context = builder
+            .clone()
+            .adapter_name(&adapters[0].name)
+            .build()
+            .unwrap();
+        assert!(context.adapter_info().unwrap().name == adapters[0].name);
+
+        assert!(matches!(
+            builder.adapter_name("not an adapter").build(),
+            Err(error::GpuMatError::NoAdapter(_))
+        ));
+    }
+
+    #[test]
+    fn host_device() {
+        use pollster::FutureExt;
+        use std::sync::Arc;
+
+        let instance = ::wgpu::Instance::new(&::wgpu::InstanceDescriptor::from_env_or_default());
+        let adapter = instance
+            .request_adapter(&::wgpu::RequestAdapterOptions::default())
+            .block_on()
+            .unwrap();
+        let (device, queue) = adapter
+            .request_device(&::wgpu::DeviceDescriptor {
+                required_limits: ::wgpu::Limits::downlevel_defaults(),
+                ..Default::default()
+            })
+            .block_on()
+            .unwrap();
+
+        let context = &GpuContext::from_device(Arc::new(device), Arc::new(queue));
+        assert!(context.adapter_info().is_none());
+
+        let a = &tensor::GpuTensor::new_in::<f32>(context, vec![3], &[1., 2., 3.]);
+        let b = &mut tensor::GpuTensor::with_capacity_in(context, a.capacity());
+        assert!(b.set(a * a).compute().join().0 == DtypeVec::F32(array(&[3], &[1., 4., 9.])));
+    }
+
+    #[test]
+    fn external_buffer() {
+        let context = &GpuContext::new();
+        let buffer = context.device().create_buffer(&::wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: ::wgpu::BufferUsages::STORAGE
+                | ::wgpu::BufferUsages::COPY_SRC
+                | ::wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        context
+            .queue()
+            .write_buffer(&buffer, 0, bytemuck::cast_slice(&[1f32, 2., 3., 4.]));
+
+        let a =
+            &mut tensor::GpuTensor::from_buffer_in(context, buffer.clone(), Dtype::F32, vec![3]);
+        let b = &tensor::GpuTensor::new_in::<f32>(context, vec![3], &[0.5; 3]);
+        a.increment(b);
+
+        let raw = a.raw();
+        assert!(*raw.buffer == buffer);
+        assert!((raw.offset, raw.size, raw.dtype, raw.shape) == (0, 12, Dtype::F32, &[3][..]));
+        context.flush().unwrap();
+
+        let c = tensor::GpuTensor::from_buffer_in(context, buffer, Dtype::F32, vec![4]);
+        assert!(c.download() == DtypeVec::F32(array(&[4], &[1.5, 2.5, 3.5, 4.])));
+
+        let uniform = context.device().create_buffer(&::wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: ::wgpu::BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+        assert!(matches!(
+            tensor::GpuTensor::try_from_buffer_in(context, uniform, Dtype::F32, vec![4]),
+            Err(error::GpuMatError::InvalidBuffer(_))
+        ));
+    }
+
+    #[test]
+    fn integers_wrap() {
+        let a = &tensor::GpuTensor::new::<i32>(vec![3], &[i32::MAX, -7, 3]);
+        let b = &tensor::GpuTensor::new::<i32>(vec![3], &[1, 2, -4]);
+        let c = &mut tensor::GpuTensor::with_capacity(a.capacity());
+        assert!(c.set(a + b).download() == DtypeVec::I32(array(&[3], &[i32::MIN, -5, -1])));
+        assert!(c.mul_in_place(b).download() == DtypeVec::I32(array(&[3], &[i32::MIN, -10, 4])));
+
+        let a = &tensor::GpuTensor::new::<u32>(vec![2], &[u32::MAX, 5]);
+        let b = &tensor::GpuTensor::new::<u32>(vec![2], &[2, 3]);
+        let c = &mut tensor::GpuTensor::with_capacity(a.capacity());
+        assert!(c.set(a * b).compute().join().0 == DtypeVec::U32(array(&[2], &[u32::MAX - 1, 15])));
+        assert!(c.increment(b).download() == DtypeVec::U32(array(&[2], &[0, 18])));
+    }
+
+    /// The default context and one emulating f16, which are the same on devices without shader
+    /// f16, to run f16 and bf16 tests on both paths.
+    fn f16_contexts() -> [GpuContext; 2] {
+        let emulated = GpuContextBuilder::from_env()
+            .emulate_f16(true)
+            .build()
+            .unwrap();
+        assert!(emulated.emulates_f16());
+        [GpuContext::global().clone(), emulated]
+    }
+
+    #[test]
+    fn f16_values() {
+        use half::f16;
+
+        let values = |values: &[f32]| values.iter().map(|&v| f16::from_f32(v)).collect::<Vec<_>>();
+        let a_values = values(&[1.5, -2., 0.125]);
+        let b_values = values(&[0.25, 4., 3.]);
+
+        for context in &f16_contexts() {
+            let a = &tensor::GpuTensor::new_in::<f16>(context, vec![3], &a_values);
+            let b = &tensor::GpuTensor::new_in::<f16>(context, vec![3], &b_values);
+            let c = &mut tensor::GpuTensor::with_capacity_in(context, 6);
+            assert!(c.capacity() == 8);
+
+            let expected = |op: fn(f16, f16) -> f16| {
+                DtypeVec::F16(ndarray::Array::from_shape_fn(ndarray::IxDyn(&[3]), |idx| {
+                    op(a_values[idx[0]], b_values[idx[0]])
+                }))
+            };
+            assert!(c.set(a + b).download() == expected(|a, b| a + b));
+            assert!(c.set(a * b).compute().join().0 == expected(|a, b| a * b));
+            assert!(c.mul_in_place(b).download() == expected(|a, b| a * b * b));
+            assert!(c.set(a - b).download() == expected(|a, b| a - b));
+            assert!(c.set(b / a).download() == expected(|a, b| b / a));
+
+            let mask = &mut tensor::GpuTensor::with_capacity_in(context, 12);
+            assert!(mask.le(a, b).download() == DtypeVec::Bool(array(&[3], &[false, true, true])));
+
+            let wide = &mut tensor::GpuTensor::with_capacity_in(context, 12);
+            assert!(
+                wide.cast(a, Dtype::F32).download()
+                    == DtypeVec::F32(array(&[3], &[1.5, -2., 0.125]))
+            );
+        }
+    }
+
+    #[test]
+    fn f64_values() {
+        let a_values = [1. + 1e-12, -3.25e-7, 12345.678901234];
+        let b_values = [1. - 1e-12, 2.5e3, 1. / 3.];
+
+        let a = &tensor::GpuTensor::new::<f64>(vec![3], &a_values);
+        let b = &tensor::GpuTensor::new::<f64>(vec![3], &b_values);
+        let c = &mut tensor::GpuTensor::with_capacity(24);
+
+        let check = |actual: DtypeVec, op: fn(f64, f64) -> f64| {
+            let DtypeVec::F64(actual) = actual else {
+                panic!("expected f64 values, got {actual:?}");
+            };
+            for ((actual, a), b) in actual.iter().zip(a_values).zip(b_values) {
+                let expected = op(a, b);
+                assert!(
+                    (actual - expected).abs() <= expected.abs() * 1e-13,
+                    "{actual} != {expected}"
+                );
+            }
+        };
+        check(a.download(), |a, _| a);
+        check(c.set(a + b).download(), |a, b| a + b);
+        check(c.set(a * b).download(), |a, b| a * b);
+        check(c.set(a + b).increment(b).download(), |a, b| a + b + b);
+        check(c.set(a * b).mul_in_place(b).download(), |a, b| a * b * b);
+
+        let mask = &mut tensor::GpuTensor::with_capacity(12);
+        assert!(mask.lt(b, a).download() == DtypeVec::Bool(array(&[3], &[true, false, true])));
+    }
+
+    #[test]
+    fn bf16_packed() {
+        use half::bf16;
+
+        let values = |values: &[f32]| {
+            values
+                .iter()
+                .map(|&v| bf16::from_f32(v))
+                .collect::<Vec<_>>()
+        };
+        let a_values = values(&[1.5, -2., 3.140625]);
+        let b_values = values(&[0.25, 1e-3, 3.]);
+
+        for context in &f16_contexts() {
+            let a = &tensor::GpuTensor::new_in::<bf16>(context, vec![3], &a_values);
+            let b = &tensor::GpuTensor::new_in::<bf16>(context, vec![3], &b_values);
+            let c = &mut tensor::GpuTensor::with_capacity_in(context, a.capacity());
+
+            let expected = |op: fn(bf16, bf16) -> bf16| {
+                DtypeVec::BF16(ndarray::Array::from_shape_fn(ndarray::IxDyn(&[3]), |idx| {
+                    op(a_values[idx[0]], b_values[idx[0]])
+                }))
+            };
+            assert!(c.set(a + b).download() == expected(|a, b| a + b));
+            assert!(c.set(a * b).compute().join().0 == expected(|a, b| a * b));
+            assert!(c.increment(b).download() == expected(|a, b| a * b + b));
+
+            let mask = &mut tensor::GpuTensor::with_capacity_in(context, 12);
+            assert!(mask.gt(a, b).download() == DtypeVec::Bool(array(&[3], &[true, false, true])));
+
+            // bf16 meets f16 at f32, which takes both the f16 and the bf16 path of the device.
+            let halves = &tensor::GpuTensor::new_in(context, vec![3], &[half::f16::ONE; 3]);
+            let sum = &mut tensor::GpuTensor::with_capacity_in(context, 12);
+            assert!(
+                sum.set(a + halves).download()
+                    == DtypeVec::F32(ndarray::Array::from_shape_fn(ndarray::IxDyn(&[3]), |idx| {
+                        a_values[idx[0]].to_f32() + 1.
+                    }))
+            );
+        }
+    }
+
+    #[test]
+    fn quantized() {
+        let values = (0..100).map(|i| (i as f32 - 40.) / 8.).collect::<Vec<_>>();
+        let a = &tensor::GpuTensor::new::<f32>(vec![4, 25], &values);
+        let ones = &tensor::GpuTensor::new::<f32>(vec![4, 25], &[1.; 100]);
+        let b = &mut tensor::GpuTensor::with_capacity(a.capacity());
+
+        let close = |result: DtypeVec, tolerance: f32, expected: &dyn Fn(f32) -> f32| {
+            let DtypeVec::F32(result) = result else {
+                panic!("expected f32 values, got {result:?}");
+            };
+            assert!(result.shape() == [4, 25]);
+            result
+                .iter()
+                .zip(&values)
+                .all(|(&r, &v)| (r - expected(v)).abs() <= tolerance)
+        };
+
+        for (format, tolerance) in [
+            (QuantFormat::INT8, 7.4 / 127.),
+            (QuantFormat::INT4.with_block_size(16), 7.4 / 7.),
+            (QuantFormat::INT4.with_scale(Dtype::F16), 7.4 / 7.),
+        ] {
+            let q = &QuantizedTensor::quantize(a, format);
+            assert!(q.size() < a.capacity() / 2);
+
+            assert!(close(
+                b.dequantize(q, Dtype::F32).download(),
+                tolerance,
+                &|v| v
+            ));
+            assert!(close(b.set(ones + q).download(), tolerance, &|v| v + 1.));
+            assert!(close(b.set(a * q).download(), 8. * tolerance, &|v| v * v));
+            assert!(close(
+                b.set(a * ones + q).compute().join().0,
+                tolerance,
+                &|v| 2. * v
+            ));
+        }
+
+        let q = &QuantizedTensor::quantize(a, QuantFormat::INT8);
+        assert!(
+            b.dequantize(q, Dtype::BF16).dtype() == Dtype::BF16
+                && b.try_dequantize(q, Dtype::I32).err()
+                    == Some(error::GpuMatError::UnsupportedDtype(Dtype::I32))
+        );
+        assert!(matches!(
+            QuantizedTensor::try_quantize(a, QuantFormat::INT8.with_block_size(12)),
+            Err(error::GpuMatError::InvalidQuantization(_))
+        ));
+
+        let row = &tensor::GpuTensor::new::<f32>(vec![25], &[1.; 25]);
+        assert!(matches!(
+            b.try_add_quantized(row, q),
+            Err(error::GpuMatError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn compare_masks() {
+        let mask = |values: Vec<bool>| DtypeVec::Bool(array(&[4], &values));
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![4], &[1., 2., 3., f32::NAN]);
+        let b = &tensor::GpuTensor::new::<f32>(vec![4], &[2., 2., 2., 2.]);
+        let c = &mut tensor::GpuTensor::with_capacity(a.capacity());
+        assert!(c.lt(a, b).download() == mask(vec![true, false, false, false]));
+        assert!(c.eq(a, b).download() == mask(vec![false, true, false, false]));
+        assert!(c.ne(a, b).download() == mask(vec![true, false, true, true]));
+        assert!(c.ge(a, b).compute().join().0 == mask(vec![false, true, true, false]));
+        assert!(c.le_scalar(a, 2.).download() == mask(vec![true, true, false, false]));
+        assert!(c.ne_scalar(a, 3.).download() == mask(vec![true, true, false, true]));
+        let halves = [1., 2.5, 3., -1.].map(half::f16::from_f32);
+        let halves = &tensor::GpuTensor::new::<half::f16>(vec![4], &halves);
+        assert!(c.gt(halves, a).download() == mask(vec![false, true, false, false]));
+        assert!(
+            c.try_compare_scalar(CompareOp::Lt, a, 1i32).err()
+                == Some(error::GpuMatError::DtypeMismatch {
+                    lhs: Dtype::F32,
+                    rhs: Dtype::I32
+                })
+        );
+
+        let a = &tensor::GpuTensor::new::<i32>(vec![4], &[-3, 0, 5, 7]);
+        let b = &tensor::GpuTensor::new::<i32>(vec![4], &[0, 0, 0, 8]);
+        assert!(c.gt(a, b).download() == mask(vec![false, false, true, false]));
+        assert!(c.gt_scalar(a, 0i32).download() == mask(vec![false, false, true, true]));
+        assert!(c.le(a, b).download() == mask(vec![true, true, false, true]));
+
+        let d = &tensor::GpuTensor::upload(&mask(vec![true, true, false, false]));
+        let e = &mut tensor::GpuTensor::with_capacity(d.capacity());
+        assert!(e.eq(c, d).download() == mask(vec![true, true, true, false]));
+        assert!(c.try_add(d, d).err() == Some(error::GpuMatError::UnsupportedDtype(Dtype::Bool)));
+    }
+
+    #[test]
+    fn complex_values() {
+        use num_complex::Complex;
+
+        let a_values = [
+            Complex::new(1.5, -2.),
+            Complex::new(0., 1.),
+            Complex::new(-3., 4.),
+        ];
+        let b_values = [
+            Complex::new(0.5, 0.25),
+            Complex::new(0., 1.),
+            Complex::new(2., 0.),
+        ];
+        let a = &tensor::GpuTensor::new::<Complex<f32>>(vec![3], &a_values);
+        let b = &tensor::GpuTensor::new::<Complex<f32>>(vec![3], &b_values);
+        let c = &mut tensor::GpuTensor::with_capacity(24);
+
+        let complex = |actual: DtypeVec, op: fn(Complex<f32>, Complex<f32>) -> Complex<f32>| {
+            let DtypeVec::C64(actual) = actual else {
+                panic!("expected c64 values, got {actual:?}");
+            };
+            for ((actual, a), b) in actual.iter().zip(a_values).zip(b_values) {
+                assert!(
+                    (actual - op(a, b)).norm() <= 1e-6,
+                    "{actual} != {}",
+                    op(a, b)
+                );
+            }
+        };
+        complex(c.set(a + b).download(), |a, b| a + b);
+        complex(c.set(a * b + b).download(), |a, b| a * b + b);
+        complex(c.conj(a).download(), |a, _| a.conj());
+
+        let real = |actual: DtypeVec, op: fn(Complex<f32>) -> f32| {
+            let DtypeVec::F32(actual) = actual else {
+                panic!("expected f32 values, got {actual:?}");
+            };
+            for (actual, a) in actual.iter().zip(a_values) {
+                assert!((actual - op(a)).abs() <= 1e-5, "{actual} != {}", op(a));
+            }
+        };
+        real(c.abs(a).download(), Complex::norm);
+        real(c.arg(a).download(), Complex::arg);
+
+        let mask = &mut tensor::GpuTensor::with_capacity(12);
+        assert!(
+            mask.try_compare(CompareOp::Lt, a, b).err()
+                == Some(error::GpuMatError::UnsupportedDtype(dtype::Dtype::C64))
+        );
+        assert!(
+            c.abs(&tensor::GpuTensor::new::<f32>(vec![1], &[-1.]))
+                .download()
+                == DtypeVec::F32(ndarray::Array::from_elem(ndarray::IxDyn(&[1]), 1.))
+        );
+    }
+
+    #[test]
+    fn complex_f64_values() {
+        use num_complex::Complex;
+
+        let a_values = [Complex::new(1. + 1e-12, -2.), Complex::new(1. / 3., 3e-9)];
+        let b_values = [Complex::new(1. - 1e-12, 0.25), Complex::new(3., 1e4)];
+        let a = &tensor::GpuTensor::new::<Complex<f64>>(vec![2], &a_values);
+        let b = &tensor::GpuTensor::new::<Complex<f64>>(vec![2], &b_values);
+        let c = &mut tensor::GpuTensor::with_capacity(32);
+
+        let check = |actual: DtypeVec, op: fn(Complex<f64>, Complex<f64>) -> Complex<f64>| {
+            let DtypeVec::C128(actual) = actual else {
+                panic!("expected c128 values, got {actual:?}");
+            };
+            for ((actual, a), b) in actual.iter().zip(a_values).zip(b_values) {
+                let expected = op(a, b);
+                assert!(
+                    (actual - expected).norm() <= expected.norm() * 1e-13,
+                    "{actual} != {expected}"
+                );
+            }
+        };
+        check(a.download(), |a, _| a);
+        check(c.set(a + b).download(), |a, b| a + b);
+        check(c.set(a * b).download(), |a, b| a * b);
+        check(c.conj(b).download(), |_, b| b.conj());
+
+        let DtypeVec::F64(abs) = c.abs(b).download() else {
+            panic!("expected f64 magnitudes");
+        };
+        for (actual, b) in abs.iter().zip(b_values) {
+            assert!((actual - b.norm()).abs() <= b.norm() * 1e-13);
+        }
+    }
+
+    #[test]
+    fn promotion() {
+        use dtype::Dtype;
+        use error::GpuMatError;
+        use half::{bf16, f16};
+        use num_complex::Complex;
+
+        let halves = [1.5, -2., 0.25].map(f16::from_f32);
+        let brains = [3., 0.5, -1.].map(bf16::from_f32);
+        let singles = [0.1, 1e-4, 3.];
+        let doubles = [1. + 1e-12, 1. / 3., -7.];
+        let a = &tensor::GpuTensor::new::<f16>(vec![3], &halves);
+        let b = &tensor::GpuTensor::new::<bf16>(vec![3], &brains);
+        let c = &tensor::GpuTensor::new::<f32>(vec![3], &singles);
+        let d = &tensor::GpuTensor::new::<f64>(vec![3], &doubles);
+        let e = &tensor::GpuTensor::new::<Complex<f32>>(vec![3], &[Complex::new(0., 1.); 3]);
+        let out = &mut tensor::GpuTensor::with_capacity(48);
+
+        let f32s = |values: DtypeVec| match values {
+            DtypeVec::F32(values) => values.into_raw_vec_and_offset().0,
+            values => panic!("expected f32 values, got {values:?}"),
+        };
+        let expected = (0..3)
+            .map(|i| halves[i].to_f32() + singles[i])
+            .collect::<Vec<_>>();
+        assert!(f32s(out.set(a + c).download()) == expected);
+        let expected = (0..3)
+            .map(|i| halves[i].to_f32() * brains[i].to_f32())
+            .collect::<Vec<_>>();
+        assert!(f32s(out.set(a * b).download()) == expected);
+        let expected = (0..3)
+            .map(|i| (singles[i] * halves[i].to_f32()) + halves[i].to_f32())
+            .collect::<Vec<_>>();
+        assert!(f32s(out.set(c * a + a).download()) == expected);
+
+        let DtypeVec::F64(values) = out.set(c + d).download() else {
+            panic!("expected f64 values");
+        };
+        for ((actual, c), d) in values.iter().zip(singles).zip(doubles) {
+            let expected = c as f64 + d;
+            assert!((actual - expected).abs() <= expected.abs() * 1e-13);
+        }
+
+        let DtypeVec::C128(values) = out.set(e * d).download() else {
+            panic!("expected c128 values");
+        };
+        for (actual, d) in values.iter().zip(doubles) {
+            assert!((actual - Complex::new(0., d)).norm() <= d.abs() * 1e-13);
+        }
+        assert!(out.set(e + c).dtype() == Dtype::C64);
+
+        let halves_out = &mut tensor::GpuTensor::with_capacity(6);
+        halves_out.set(a + a);
+        assert!(
+            halves_out.try_increment(c).err()
+                == Some(GpuMatError::DtypeMismatch {
+                    lhs: Dtype::F16,
+                    rhs: Dtype::F32
+                })
+        );
+    }
+
+    #[test]
+    fn casts() {
+        use dtype::Dtype;
+        use error::GpuMatError;
+        use half::{bf16, f16};
+
+        let values = [1. + 2f64.powi(-30), -0.1, 3e5, 1e-3, 0.];
+        let a = &tensor::GpuTensor::new::<f64>(vec![5], &values);
+        let b = &mut tensor::GpuTensor::with_capacity(40);
+        let c = &mut tensor::GpuTensor::with_capacity(40);
+
+        let f32s = values.map(|v| v as f32);
+        b.set(a.cast_to(Dtype::F32));
+        assert!(b.download() == DtypeVec::F32(array(&[5], &f32s)));
+
+        let f16s = f32s.map(f16::from_f32);
+        c.cast(b, Dtype::F16);
+        assert!(c.download() == DtypeVec::F16(array(&[5], &f16s)));
+        b.cast(c, Dtype::F64);
+        assert!(b.download() == DtypeVec::F64(array(&[5], &f16s.map(f16::to_f64))));
+
+        let bf16s = f32s.map(bf16::from_f32);
+        c.cast(a, Dtype::BF16);
+        assert!(c.download() == DtypeVec::BF16(array(&[5], &bf16s)));
+        b.cast(c, Dtype::F32);
+        assert!(b.download() == DtypeVec::F32(array(&[5], &bf16s.map(bf16::to_f32))));
+
+        assert!(c.cast(a, Dtype::F64).download() == a.download());
+
+        let small = &mut tensor::GpuTensor::with_capacity(8);
+        assert!(
+            small.try_cast(a, Dtype::F32).err()
+                == Some(GpuMatError::InsufficientCapacity {
+                    required: 5,
+                    capacity: 2
+                })
+        );
+        assert!(c.try_cast(a, Dtype::I32).err() == Some(GpuMatError::UnsupportedDtype(Dtype::I32)));
+    }
+
+    #[test]
+    fn sub_div() {
+        use half::f16;
+        use num_complex::Complex;
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![4], &[6., 1., -3., 8.]);
+        let b = &tensor::GpuTensor::new::<f32>(vec![4], &[2., 4., 0.5, -2.]);
+        let c = &tensor::GpuTensor::new::<f32>(vec![4], &[1.; 4]);
+        let out = &mut tensor::GpuTensor::with_capacity(a.capacity());
+        assert!(out.set(a - b).download() == DtypeVec::F32(array(&[4], &[4., -3., -3.5, 10.])));
+        assert!(out.set(a / b).download() == DtypeVec::F32(array(&[4], &[3., 0.25, -6., -4.])));
+        assert!(
+            out.set(a * b - c).download() == DtypeVec::F32(array(&[4], &[11., 3., -2.5, -17.]))
+        );
+        assert!(
+            out.set(c - a * b).download() == DtypeVec::F32(array(&[4], &[-11., -3., 2.5, 17.]))
+        );
+        assert!(
+            out.set((a + c) / b).download() == DtypeVec::F32(array(&[4], &[3.5, 0.5, -4., -4.5]))
+        );
+        assert!(
+            out.set(c / (b - c)).download()
+                == DtypeVec::F32(array(&[4], &[1., 1. / 3., -2., -1. / 3.]))
+        );
+
+        // Division by zero gives the dividend, as WGSL specifies for integers.
+        let a = &tensor::GpuTensor::new::<i32>(vec![4], &[7, -7, 5, i32::MIN]);
+        let b = &tensor::GpuTensor::new::<i32>(vec![4], &[2, 2, 0, -1]);
+        let out = &mut tensor::GpuTensor::with_capacity(a.capacity());
+        assert!(out.set(a / b).download() == DtypeVec::I32(array(&[4], &[3, -3, 5, i32::MIN])));
+        assert!(out.set(a - b).download() == DtypeVec::I32(array(&[4], &[5, -9, 5, i32::MIN + 1])));
+        assert!(out.rdiv_in_place(b).download() == DtypeVec::I32(array(&[4], &[0, 0, 0, 0])));
+
+        let halves = [1.5, -2., 0.125].map(f16::from_f32);
+        let singles = [0.5, 4., -1.];
+        let a = &tensor::GpuTensor::new::<f16>(vec![3], &halves);
+        let b = &tensor::GpuTensor::new::<f32>(vec![3], &singles);
+        let ones = &tensor::GpuTensor::new::<f16>(vec![3], &[f16::ONE; 3]);
+        let out = &mut tensor::GpuTensor::with_capacity(12);
+        let expected = (0..3)
+            .map(|i| 1. - halves[i].to_f32() / singles[i])
+            .collect::<Vec<f32>>();
+        assert!(out.set(ones - a / b).download() == DtypeVec::F32(array(&[3], &expected)));
+        let expected = halves.map(|h| (h - f16::ONE) / h);
+        let out = &mut tensor::GpuTensor::with_capacity(6);
+        assert!(out.set((a - ones) / a).download() == DtypeVec::F16(array(&[3], &expected)));
+
+        let doubles = [1., -2., 1e-3];
+        let a = &tensor::GpuTensor::new::<f64>(vec![3], &doubles);
+        let b = &tensor::GpuTensor::new::<f64>(vec![3], &[3.; 3]);
+        let out = &mut tensor::GpuTensor::with_capacity(24);
+        let DtypeVec::F64(values) = out.set(a / b - a).download() else {
+            panic!("expected f64 values");
+        };
+        for (actual, a) in values.iter().zip(doubles) {
+            let expected = a / 3. - a;
+            assert!((actual - expected).abs() <= expected.abs() * 1e-13);
+        }
+
+        let lhs = [Complex::new(1., 2.), Complex::new(-3., 0.5)];
+        let rhs = [Complex::new(0.5, -1.), Complex::new(2., 3.)];
+        let a = &tensor::GpuTensor::new::<Complex<f32>>(vec![2], &lhs);
+        let b = &tensor::GpuTensor::new::<Complex<f32>>(vec![2], &rhs);
+        let out = &mut tensor::GpuTensor::with_capacity(16);
+        let DtypeVec::C64(values) = out.set(a - a / b).download() else {
+            panic!("expected c64 values");
+        };
+        for ((actual, a), b) in values.iter().zip(lhs).zip(rhs) {
+            let expected = a - a / b;
+            assert!((actual - expected).norm() <= expected.norm() * 1e-6);
+        }
+
+        let lhs = lhs.map(|z| Complex::new(z.re as f64, z.im as f64));
+        let rhs = rhs.map(|z| Complex::new(z.re as f64, z.im as f64));
+        let a = &tensor::GpuTensor::new::<Complex<f64>>(vec![2], &lhs);
+        let b = &tensor::GpuTensor::new::<Complex<f64>>(vec![2], &rhs);
+        let out = &mut tensor::GpuTensor::with_capacity(32);
+        let DtypeVec::C128(values) = out.set(b / a).download() else {
+            panic!("expected c128 values");
+        };
+        for ((actual, a), b) in values.iter().zip(lhs).zip(rhs) {
+            let expected = b / a;
+            assert!((actual - expected).norm() <= expected.norm() * 1e-13);
+        }
+    }
+
+    #[test]
+    fn unary_math() {
+        use error::GpuMatError;
+        use half::{bf16, f16};
+        use num_complex::Complex;
+        use ops::UnaryOp;
+
+        let values = [0.3f32, -0.7, 2.5, 1e-8, -0.5];
+        let a = &tensor::GpuTensor::new::<f32>(vec![5], &values);
+        let out = &mut tensor::GpuTensor::with_capacity(a.capacity());
+        let cases = [
+            (UnaryOp::Exp, f32::exp as fn(f32) -> f32),
+            (UnaryOp::Log1p, f32::ln_1p),
+            (UnaryOp::Sin, f32::sin),
+            (UnaryOp::Cos, f32::cos),
+            (UnaryOp::Tan, f32::tan),
+            (UnaryOp::Tanh, f32::tanh),
+            (UnaryOp::Abs, f32::abs),
+            (UnaryOp::Round, f32::round_ties_even),
+        ];
+        for (op, f) in cases {
+            let DtypeVec::F32(actual) = out.unary(op, a).download() else {
+                panic!("expected f32 values");
+            };
+            for (actual, value) in actual.iter().zip(values) {
+                let expected = f(value);
+                // WGSL only bounds the absolute error of most of these near zero.
+                assert!(
+                    (actual - expected).abs() <= expected.abs().max(1.) * 1e-5,
+                    "{op:?}({value})"
+                );
+            }
+        }
+        let tiny = &tensor::GpuTensor::new::<f32>(vec![1], &[1e-8]);
+        assert!(out.log1p(tiny).download() == DtypeVec::F32(array(&[1], &[1e-8f32.ln_1p()])));
+        let DtypeVec::F32(actual) = out.abs(a).sqrt_in_place().log_in_place().download() else {
+            panic!("expected f32 values");
+        };
+        for (actual, value) in actual.iter().zip(values) {
+            let expected = value.abs().sqrt().ln();
+            assert!((actual - expected).abs() <= expected.abs() * 1e-5);
+        }
+        let DtypeVec::F32(actual) = out.abs(a).rsqrt_in_place().download() else {
+            panic!("expected f32 values");
+        };
+        for (actual, value) in actual.iter().zip(values) {
+            let expected = 1. / value.abs().sqrt();
+            assert!((actual - expected).abs() <= expected * 1e-5);
+        }
+        assert!(out.sign(a).download() == DtypeVec::F32(array(&[5], &[1., -1., 1., 1., -1.])));
+        assert!(out.floor(a).download() == DtypeVec::F32(array(&[5], &[0., -1., 2., 0., -1.])));
+        assert!(
+            out.ceil_in_place().neg_in_place().download()
+                == DtypeVec::F32(array(&[5], &[0., 1., -2., 0., 1.]))
+        );
+
+        let halves = [0.5, -2.25, 3.5].map(f16::from_f32);
+        let a = &mut tensor::GpuTensor::new::<f16>(vec![3], &halves);
+        let expected = halves.map(|h| f16::from_f32(h.to_f32().exp()));
+        assert!(a.exp_in_place().download() == DtypeVec::F16(array(&[3], &expected)));
+        let brains = [0.5, -2.25, 3.5].map(bf16::from_f32);
+        let a = &tensor::GpuTensor::new::<bf16>(vec![3], &brains);
+        let out = &mut tensor::GpuTensor::with_capacity(6);
+        let expected = brains.map(|b| bf16::from_f32(b.to_f32().tanh()));
+        assert!(out.tanh(a).download() == DtypeVec::BF16(array(&[3], &expected)));
+
+        let a = &tensor::GpuTensor::new::<i32>(vec![4], &[-3, 0, 7, i32::MIN]);
+        let out = &mut tensor::GpuTensor::with_capacity(16);
+        assert!(out.abs(a).download() == DtypeVec::I32(array(&[4], &[3, 0, 7, i32::MIN])));
+        assert!(out.neg(a).download() == DtypeVec::I32(array(&[4], &[3, 0, -7, i32::MIN])));
+        assert!(out.sign(a).download() == DtypeVec::I32(array(&[4], &[-1, 0, 1, -1])));
+
+        let doubles = [2.5, 3.5, -2.5, 1. + 1e-12, -(1. + 1e-12), 1e10 + 0.5];
+        let a = &tensor::GpuTensor::new::<f64>(vec![6], &doubles);
+        let out = &mut tensor::GpuTensor::with_capacity(48);
+        let f64s = |values: DtypeVec| match values {
+            DtypeVec::F64(values) => values.into_raw_vec_and_offset().0,
+            values => panic!("expected f64 values, got {values:?}"),
+        };
+        assert!(f64s(out.round(a).download()) == doubles.map(f64::round_ties_even));
+        assert!(f64s(out.floor(a).download()) == doubles.map(f64::floor));
+        assert!(f64s(out.ceil(a).download()) == doubles.map(f64::ceil));
+        assert!(f64s(out.neg(a).abs_in_place().download()) == doubles.map(f64::abs));
+        assert!(f64s(out.sign(a).download()) == doubles.map(f64::signum));
+        for (actual, value) in f64s(out.abs(a).sqrt_in_place().download())
+            .iter()
+            .zip(doubles)
+        {
+            let expected = value.abs().sqrt();
+            assert!((actual - expected).abs() <= expected * 1e-13);
+        }
+        // WGSL has no f64 exp, it is computed in f32.
+        let small = [-1.5, 0.25, 3.];
+        let a = &tensor::GpuTensor::new::<f64>(vec![3], &small);
+        for (actual, value) in f64s(out.exp(a).download()).iter().zip(small) {
+            assert!((actual - value.exp()).abs() <= value.exp() * 1e-6);
+        }
+
+        let z = [Complex::new(1f32, -2.), Complex::new(-0.5, 0.25)];
+        let a = &tensor::GpuTensor::new::<Complex<f32>>(vec![2], &z);
+        let out = &mut tensor::GpuTensor::with_capacity(16);
+        assert!(out.neg(a).download() == DtypeVec::C64(array(&[2], &z.map(|z| -z))));
+        assert!(
+            out.try_unary_in_place(UnaryOp::Abs).err()
+                == Some(GpuMatError::UnsupportedDtype(Dtype::C64))
+        );
+
+        let a = &tensor::GpuTensor::new::<u32>(vec![2], &[1, 2]);
+        assert!(
+            out.try_unary(UnaryOp::Neg, a).err() == Some(GpuMatError::UnsupportedDtype(Dtype::U32))
+        );
+    }
+
+    #[test]
+    fn broadcasting() {
+        use error::GpuMatError;
+        use half::f16;
+
+        let x = &tensor::GpuTensor::new::<f32>(vec![2, 3], &[1., 2., 3., 4., 5., 6.]);
+        let bias = &tensor::GpuTensor::new::<f32>(vec![3], &[10., 20., 30.]);
+        let column = &tensor::GpuTensor::new::<f32>(vec![2, 1], &[1., -1.]);
+        let out = &mut tensor::GpuTensor::with_capacity(32);
+        assert!(
+            out.set(x + bias).download()
+                == DtypeVec::F32(array(&[2, 3], &[11., 22., 33., 14., 25., 36.]))
+        );
+        assert!(
+            out.set(column * bias).download()
+                == DtypeVec::F32(array(&[2, 3], &[10., 20., 30., -10., -20., -30.]))
+        );
+        assert!(
+            out.set(x * column + bias).download()
+                == DtypeVec::F32(array(&[2, 3], &[11., 22., 33., 6., 15., 24.]))
+        );
+        assert!(
+            out.set(bias - x / column).download()
+                == DtypeVec::F32(array(&[2, 3], &[9., 18., 27., 14., 25., 36.]))
+        );
+
+        let scalar = &tensor::GpuTensor::new::<f32>(vec![], &[2.]);
+        assert!(out.set(scalar * bias).download() == DtypeVec::F32(array(&[3], &[20., 40., 60.])));
+        let mask = &mut tensor::GpuTensor::with_capacity(24);
+        let expected = [false, true, true, true, true, true];
+        assert!(mask.gt(x, column).download() == DtypeVec::Bool(array(&[2, 3], &expected)));
+        let expected = [false, false, true, true, true, true];
+        assert!(mask.lt(scalar, x).download() == DtypeVec::Bool(array(&[2, 3], &expected)));
+
+        let halves = &tensor::GpuTensor::new::<f16>(vec![3, 1], &[1., 2., 3.].map(f16::from_f32));
+        let row = &tensor::GpuTensor::new::<f16>(vec![2], &[0.5, -1.].map(f16::from_f32));
+        let expected = [1.5, 0., 2.5, 1., 3.5, 2.].map(f16::from_f32);
+        assert!(out.set(halves + row).download() == DtypeVec::F16(array(&[3, 2], &expected)));
+        assert!(
+            out.try_set(bias + row).err()
+                == Some(GpuMatError::ShapeMismatch {
+                    lhs: vec![3],
+                    rhs: vec![2]
+                })
+        );
+
+        let doubles = &tensor::GpuTensor::new::<f64>(vec![2, 1], &[1., -1.]);
+        let tiny = &tensor::GpuTensor::new::<f64>(vec![2], &[1e-12, 3e-12]);
+        assert!(
+            out.set(doubles + tiny).download()
+                == DtypeVec::F64(array(
+                    &[2, 2],
+                    &[1. + 1e-12, 1. + 3e-12, -1. + 1e-12, -1. + 3e-12]
+                ))
+        );
+
+        let small = &mut tensor::GpuTensor::with_capacity(12);
+        assert!(
+            small.try_add(column, bias).err()
+                == Some(GpuMatError::InsufficientCapacity {
+                    required: 6,
+                    capacity: 3
+                })
+        );
+        let out = &mut tensor::GpuTensor::new::<f32>(vec![3], &[0.; 3]);
+        assert!(
+            out.try_increment(x).err()
+                == Some(GpuMatError::ShapeMismatch {
+                    lhs: vec![3],
+                    rhs: vec![2, 3]
+                })
+        );
+    }
+
+    #[test]
+    fn scalar_ops() {
+        use dtype::Dtype;
+        use error::GpuMatError;
+        use half::{bf16, f16};
+        use num_complex::Complex;
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![3], &[1., -2., 4.]);
+        let out = &mut tensor::GpuTensor::with_capacity(12);
+        assert!(out.set(a * 0.5f32).download() == DtypeVec::F32(array(&[3], &[0.5, -1., 2.])));
+        assert!(out.set(a + 1f32).download() == DtypeVec::F32(array(&[3], &[2., -1., 5.])));
+        // Every dispatch keeps its own scalar until the batch is submitted.
+        assert!(
+            out.set((a * 0.5f32 + 1f32) * 4f32 + a).download()
+                == DtypeVec::F32(array(&[3], &[7., -2., 16.]))
+        );
+        assert!(
+            out.set(a * a * 0.75f32 + 0.75f32).download()
+                == DtypeVec::F32(array(&[3], &[1.5, 3.75, 12.75]))
+        );
+        // Float literals convert to the dtype of the tensor, integers must match it.
+        assert!(out.set(a * 0.5 + 1.).download() == DtypeVec::F32(array(&[3], &[1.5, 0., 3.])));
+        assert!(
+            out.try_mul_scalar(a, 2i32).err()
+                == Some(GpuMatError::DtypeMismatch {
+                    lhs: Dtype::F32,
+                    rhs: Dtype::I32
+                })
+        );
+
+        let halves = &mut tensor::GpuTensor::new::<f16>(vec![3], &[f16::ONE; 3]);
+        let expected = [f16::from_f32(-1.5); 3];
+        assert!(
+            halves
+                .scale(f16::from_f32(-0.5))
+                .shift(f16::NEG_ONE)
+                .download()
+                == DtypeVec::F16(array(&[3], &expected))
+        );
+        let brains = &tensor::GpuTensor::new::<bf16>(vec![3], &[1., 0., 2.5].map(bf16::from_f32));
+        let out = &mut tensor::GpuTensor::with_capacity(6);
+        let expected = [2.5, 0.5, 5.5].map(bf16::from_f32);
+        assert!(
+            out.set(brains * bf16::from_f32(2.) + bf16::from_f32(0.5))
+                .download()
+                == DtypeVec::BF16(array(&[3], &expected))
+        );
+
+        let a = &mut tensor::GpuTensor::new::<i32>(vec![2], &[3, i32::MAX]);
+        assert!(a.shift(1i32).scale(-2i32).download() == DtypeVec::I32(array(&[2], &[-8, 0])));
+
+        let a = &tensor::GpuTensor::new::<f64>(vec![2], &[1., -0.5]);
+        let out = &mut tensor::GpuTensor::with_capacity(16);
+        assert!(
+            out.add_scalar(a, 1e-12).download()
+                == DtypeVec::F64(array(&[2], &[1. + 1e-12, -0.5 + 1e-12]))
+        );
+
+        let z = [Complex::new(1f32, 2.), Complex::new(-1., 0.)];
+        let a = &tensor::GpuTensor::new::<Complex<f32>>(vec![2], &z);
+        let out = &mut tensor::GpuTensor::with_capacity(16);
+        let i = Complex::new(0f32, 1.);
+        assert!(out.set(a * i + i).download() == DtypeVec::C64(array(&[2], &z.map(|z| z * i + i))));
+        assert!(out.set(a * 2.).download() == DtypeVec::C64(array(&[2], &z.map(|z| z * 2.))));
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![2], &[0., 1.]);
+        let mask = &mut tensor::GpuTensor::with_capacity(8);
+        mask.compare(ops::CompareOp::Lt, a, a);
+        assert!(mask.try_shift(1u32).err() == Some(GpuMatError::UnsupportedDtype(Dtype::Bool)));
+    }
+
+    #[test]
+    fn activations() {
+        use dtype::Dtype;
+        use error::GpuMatError;
+        use half::{bf16, f16};
+        use ops::Activation;
+
+        fn sigmoid(x: f64) -> f64 {
+            1. / (1. + (-x).exp())
+        }
+        fn gelu(x: f64) -> f64 {
+            let t = (2. / std::f64::consts::PI).sqrt() * (x + 0.044715 * x.powi(3));
+            0.5 * x * (1. + t.tanh())
+        }
+        let values = [-30f32, -2.5, -1e-3, 0., 0.75, 3., 40.];
+        let a = &tensor::GpuTensor::new::<f32>(vec![7], &values);
+        let out = &mut tensor::GpuTensor::with_capacity(a.capacity());
+        let cases = [
+            (Activation::Gelu, gelu as fn(f64) -> f64),
+            (Activation::Silu, |x| x * sigmoid(x)),
+            (Activation::Sigmoid, sigmoid),
+            (Activation::Softplus, |x| {
+                x.max(0.) + (-x.abs()).exp().ln_1p()
+            }),
+        ];
+        for (activation, f) in cases {
+            let DtypeVec::F32(actual) = out.activation(activation, a).download() else {
+                panic!("expected f32 values");
+            };
+            for (actual, value) in actual.iter().zip(values) {
+                let expected = f(value.into()) as f32;
+                assert!(
+                    (actual - expected).abs() <= expected.abs().max(1.) * 1e-5,
+                    "{activation:?}({value}) = {actual}, expected {expected}"
+                );
+            }
+        }
+        assert!(
+            out.relu(a).download()
+                == DtypeVec::F32(array(&[7], &values.map(|x| if x < 0. { 0. } else { x })))
+        );
+        assert!(
+            out.leaky_relu(a, 0.1).download()
+                == DtypeVec::F32(array(
+                    &[7],
+                    &values.map(|x| if x < 0. { x * 0.1 } else { x })
+                ))
+        );
+
+        let w = &tensor::GpuTensor::new::<f32>(vec![4], &[1., -2., 0.5, 3.]);
+        let b = &tensor::GpuTensor::new::<f32>(vec![4], &[-1., 1., 0.25, -10.]);
+        let x = &tensor::GpuTensor::new::<f32>(vec![4], &[2., 1., -1., 3.]);
+        let d = &mut tensor::GpuTensor::with_capacity(16);
+        assert!(
+            d.set((x * w + b).relu()).compute().join().0
+                == DtypeVec::F32(array(&[4], &[1., 0., 0., 0.]))
+        );
+        let DtypeVec::F32(actual) = d.set((x * w).leaky_relu(0.5).sigmoid()).compute().join().0
+        else {
+            panic!("expected f32 values");
+        };
+        for (actual, value) in actual.iter().zip([2., -1., -0.25, 9.]) {
+            assert!((actual - sigmoid(value) as f32).abs() <= 1e-6);
+        }
+        // Slopes are uniforms of their own dispatch, so one batch may use several.
+        assert!(
+            d.set((x * w).leaky_relu(0.5).leaky_relu(0.25))
+                .compute()
+                .join()
+                .0
+                == DtypeVec::F32(array(&[4], &[2., -0.25, -0.0625, 9.]))
+        );
+
+        let halves = [-1.5, 0., 2.25].map(f16::from_f32);
+        let a = &mut tensor::GpuTensor::new::<f16>(vec![3], &halves);
+        let expected = halves.map(|h| f16::from_f32(h.to_f32().max(0.)));
+        assert!(a.relu_in_place().download() == DtypeVec::F16(array(&[3], &expected)));
+        let brains = [-1.5, 0., 2.25].map(bf16::from_f32);
+        let a = &tensor::GpuTensor::new::<bf16>(vec![3], &brains);
+        let out = &mut tensor::GpuTensor::with_capacity(6);
+        let expected = brains.map(|b| {
+            bf16::from_f32(if b.to_f32() < 0. {
+                b.to_f32() * 0.25
+            } else {
+                b.to_f32()
+            })
+        });
+        assert!(out.leaky_relu(a, 0.25).download() == DtypeVec::BF16(array(&[3], &expected)));
+
+        let doubles = [-(1. + 1e-12), 1. + 1e-12, 2.5];
+        let a = &mut tensor::GpuTensor::new::<f64>(vec![3], &doubles);
+        assert!(a.relu_in_place().download() == DtypeVec::F64(array(&[3], &[0., 1. + 1e-12, 2.5])));
+        let DtypeVec::F64(actual) = a.sigmoid_in_place().download() else {
+            panic!("expected f64 values");
+        };
+        for (actual, value) in actual.iter().zip([0., 1. + 1e-12, 2.5]) {
+            assert!((actual - sigmoid(value)).abs() <= 1e-6);
+        }
+
+        let a = &tensor::GpuTensor::new::<i32>(vec![2], &[-1, 1]);
+        assert!(
+            out.try_activation(Activation::Relu, a).err()
+                == Some(GpuMatError::UnsupportedDtype(Dtype::I32))
+        );
+    }
+
+    #[test]
+    fn invalid_operands() {
+        use error::GpuMatError;
+
+        assert!(matches!(
+            tensor::GpuTensor::try_new::<f32>(vec![2, 2], &[1.; 3]),
+            Err(GpuMatError::LengthMismatch { .. })
+        ));
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![2, 2], &[1.; 4]);
+        let b = &tensor::GpuTensor::new::<i32>(vec![2, 2], &[1; 4]);
+        let c = &tensor::GpuTensor::new::<f32>(vec![4], &[1.; 4]);
+        let d = &mut tensor::GpuTensor::with_capacity(8);
+
+        assert!(
+            d.try_add(a, b).err()
+                == Some(GpuMatError::DtypeMismatch {
+                    lhs: dtype::Dtype::F32,
+                    rhs: dtype::Dtype::I32
+                })
+        );
+        assert!(
+            d.try_mul(a, c).err()
+                == Some(GpuMatError::ShapeMismatch {
+                    lhs: vec![2, 2],
+                    rhs: vec![4]
+                })
+        );
+        assert!(
+            d.try_set(a + a).err()
+                == Some(GpuMatError::InsufficientCapacity {
+                    required: 4,
+                    capacity: 2
+                })
+        );
+    }
+
+    #[test]
+    fn min_max_clamp_select() {
+        use dtype::Dtype;
+        use error::GpuMatError;
+        use half::{bf16, f16};
+
+        // NaN compares unequal to itself, so results are checked with it as `None`.
+        fn f32s(tensor: &tensor::GpuTensor) -> Vec<Option<f32>> {
+            let DtypeVec::F32(values) = tensor.download() else {
+                panic!("expected f32 values");
+            };
+            values.iter().map(|&x| (!x.is_nan()).then_some(x)).collect()
+        }
+
+        let a = &tensor::GpuTensor::new::<f32>(vec![2, 3], &[1., -2., 4., f32::NAN, 0.5, -0.5]);
+        let b = &tensor::GpuTensor::new::<f32>(vec![3], &[0., f32::NAN, 1.]);
+        let out = &mut tensor::GpuTensor::with_capacity(24);
+        let expected = [Some(0.), None, Some(1.), None, None, Some(-0.5)];
+        assert!(f32s(out.minimum(a, b)) == expected);
+        assert!(f32s(out.minimum(b, a)) == expected);
+        let expected = [Some(1.), None, Some(4.), None, None, Some(1.)];
+        assert!(f32s(out.maximum(a, b)) == expected);
+        assert!(f32s(out.maximum(b, a)) == expected);
+
+        let expected = [Some(1.), Some(-1.), Some(1.), None, Some(0.5), Some(-0.5)];
+        assert!(f32s(out.clamp_scalar(a, -1f32, 1f32)) == expected);
+        let lo = &tensor::GpuTensor::new::<f32>(vec![3], &[-1., -1., 0.]);
+        let hi = &tensor::GpuTensor::new::<f32>(vec![2, 1], &[0.5, 2.]);
+        let expected = [Some(0.5), Some(-1.), Some(0.5), None, Some(0.5), Some(0.)];
+        assert!(f32s(out.clamp(a, lo, hi)) == expected);
+        let expected = [Some(0.5), Some(-1.), Some(0.5), None, Some(1.), Some(0.)];
+        assert!(f32s(out.set((a * 2f32).clamp(lo, hi))) == expected);
+        // Bounds the wrong way around give `hi`, like NumPy.
+        let expected = [Some(-1.), Some(-1.), Some(-1.), None, Some(-1.), Some(-1.)];
+        assert!(f32s(out.clamp_scalar(a, 1f32, -1f32)) == expected);
+
+        let halves = &tensor::GpuTensor::new::<f16>(vec![3], &[1., -3., 0.25].map(f16::from_f32));
+        let floor = &tensor::GpuTensor::new::<f32>(vec![1], &[0.5]);
+        assert!(
+            out.maximum(halves, floor).download() == DtypeVec::F32(array(&[3], &[1., 0.5, 0.5]))
+        );
+        let halves =
+            &mut tensor::GpuTensor::new::<f16>(vec![3], &[1., -3., 0.25].map(f16::from_f32));
+        let ceiling = &tensor::GpuTensor::new::<f16>(vec![1], &[f16::from_f32(0.5)]);
+        assert!(
+            halves.minimum_in_place(ceiling).download()
+                == DtypeVec::F16(array(&[3], &[0.5, -3., 0.25].map(f16::from_f32)))
+        );
+        let brains =
+            &mut tensor::GpuTensor::new::<bf16>(vec![3], &[-1., 0.5, 3.].map(bf16::from_f32));
+        assert!(
+            brains
+                .clamp_scalar_in_place(bf16::ZERO, bf16::ONE)
+                .download()
+                == DtypeVec::BF16(array(&[3], &[0., 0.5, 1.].map(bf16::from_f32)))
+        );
+        // The low words of df64 values decide these.
+        let a = &tensor::GpuTensor::new::<f64>(vec![2], &[1. + 1e-12, 1. - 1e-12]);
+        let b = &tensor::GpuTensor::new::<f64>(vec![2], &[1.; 2]);
+        let out = &mut tensor::GpuTensor::with_capacity(16);
+        assert!(out.minimum(a, b).download() == DtypeVec::F64(array(&[2], &[1., 1. - 1e-12])));
+        assert!(out.maximum(a, b).download() == DtypeVec::F64(array(&[2], &[1. + 1e-12, 1.])));
+
+        let x = &tensor::GpuTensor::new::<f32>(vec![4], &[1., -2., f32::NAN, 3.]);
+        let zeros = &tensor::GpuTensor::new::<f32>(vec![4], &[0.; 4]);
+        let valid = &mut tensor::GpuTensor::with_capacity(16);
+        valid.eq(x, x);
+        let out = &mut tensor::GpuTensor::with_capacity(16);
+        assert!(
+            out.select(valid, x, zeros).download()
+                == DtypeVec::F32(array(&[4], &[1., -2., 0., 3.]))
+        );
+        let mask = &tensor::GpuTensor::upload(&DtypeVec::Bool(array(&[3], &[true, false, true])));
+        let ones = &tensor::GpuTensor::new::<f16>(vec![3], &[f16::ONE; 3]);
+        let twos = &tensor::GpuTensor::new::<f16>(vec![3], &[f16::from_f32(2.); 3]);
+        assert!(
+            out.select(mask, ones, twos).download()
+                == DtypeVec::F16(array(&[3], &[1., 2., 1.].map(f16::from_f32)))
+        );
+
+        let ints = &tensor::GpuTensor::new::<i32>(vec![3], &[1, 2, 3]);
+        assert!(
+            out.try_minimum(ints, ints).err() == Some(GpuMatError::UnsupportedDtype(Dtype::I32))
+        );
+        assert!(
+            out.try_clamp_scalar(x, 0i32, 1i32).err()
+                == Some(GpuMatError::DtypeMismatch {
+                    lhs: Dtype::F32,
+                    rhs: Dtype::I32
+                })
+        );
+        assert!(
+            out.try_select(x, x, zeros).err()
+                == Some(GpuMatError::DtypeMismatch {
+                    lhs: Dtype::Bool,
+                    rhs: Dtype::F32
+                })
+        );
+        assert!(
+            out.try_select(mask, x, zeros).err()
+                == Some(GpuMatError::ShapeMismatch {
+                    lhs: vec![3],
+                    rhs: vec![4]
+                })
+        );
+    }
+
+    #[test]
+    fn allocation_errors() {
+        use error::GpuMatError;
+        use pollster::FutureExt;
+
+        let context = &GpuContext::new();
+        assert!(matches!(
+            tensor::GpuTensor::try_with_capacity_in(context, 1 << 50),
+            Err(GpuMatError::OutOfMemory(_) | GpuMatError::Validation(_))
+        ));
+        assert!(context.status().is_ok());
+
+        let a = &tensor::GpuTensor::new_in::<f32>(context, vec![2], &[1., 2.]);
+        let b = &mut tensor::GpuTensor::with_capacity_in(context, a.capacity());
+        assert!(
+            b.set(a + a).compute().fallible().block_on().unwrap().0
+                == DtypeVec::F32(array(&[2], &[2., 4.]))
+        );
+    }
 }
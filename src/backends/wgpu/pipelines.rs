@@ -0,0 +1,608 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use wgpu::{BindGroupLayout, ComputePipeline, Device, Features, ShaderModuleDescriptor};
+
+use super::{
+    bind_groups::Layout,
+    dtype::Dtype,
+    ops::{Activation, CompareOp, UnaryOp},
+    quantization::QuantFormat,
+    quantized,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kernel {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Increment,
+    Decrement,
+    MulInPlace,
+    DivInPlace,
+    /// `a = b - a`, the in place form of `&b - setter`.
+    RsubInPlace,
+    /// `a = b / a`, the in place form of `&b / setter`.
+    RdivInPlace,
+    AddScalar,
+    MulScalar,
+    IncrementScalar,
+    MulInPlaceScalar,
+    Minimum,
+    Maximum,
+    MinimumInPlace,
+    MaximumInPlace,
+    MaximumScalar,
+    MinimumInPlaceScalar,
+    MaximumInPlaceScalar,
+    /// Picks elements of `a` where a [`Dtype::Bool`] mask is set and of `b` elsewhere.
+    Select,
+    Compare(CompareOp),
+    /// Compares a tensor with a uniform scalar of its dtype.
+    CompareScalar(CompareOp),
+    Quantize(QuantFormat),
+    Dequantize(QuantFormat),
+    AddQuantized(QuantFormat),
+    MulQuantized(QuantFormat),
+    IncrementQuantized(QuantFormat),
+    MulInPlaceQuantized(QuantFormat),
+    Copy,
+    Unary(UnaryOp),
+    UnaryInPlace(UnaryOp),
+    Activation(FixedActivation),
+    ActivationInPlace(FixedActivation),
+    /// `Activation::LeakyRelu`, with the slope as a scalar operand of the dtype of `a`.
+    LeakyRelu,
+    LeakyReluInPlace,
+    Conj,
+    Abs,
+    Arg,
+    /// Converts from the given dtype to the one the pipeline is compiled for.
+    Cast(Dtype),
+    /// Operands of the given dtypes, converted on load to the one the pipeline is compiled for and
+    /// broadcast to the shape of the output.
+    AddMixed(Dtype, Dtype),
+    SubMixed(Dtype, Dtype),
+    MulMixed(Dtype, Dtype),
+    DivMixed(Dtype, Dtype),
+    IncrementMixed(Dtype),
+    DecrementMixed(Dtype),
+    MulInPlaceMixed(Dtype),
+    DivInPlaceMixed(Dtype),
+    RsubInPlaceMixed(Dtype),
+    RdivInPlaceMixed(Dtype),
+    MinimumMixed(Dtype, Dtype),
+    MaximumMixed(Dtype, Dtype),
+    MinimumInPlaceMixed(Dtype),
+    MaximumInPlaceMixed(Dtype),
+    SelectMixed(Dtype, Dtype),
+    CompareMixed(CompareOp, Dtype, Dtype),
+}
+
+/// An [`Activation`] without parameters, which a kernel computes on its own.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FixedActivation {
+    Relu,
+    Gelu,
+    Silu,
+    Sigmoid,
+    Softplus,
+}
+
+impl From<Activation> for FixedActivation {
+    fn from(activation: Activation) -> Self {
+        match activation {
+            Activation::Relu => FixedActivation::Relu,
+            Activation::LeakyRelu(_) => unreachable!("LeakyRelu runs on Kernel::LeakyRelu"),
+            Activation::Gelu => FixedActivation::Gelu,
+            Activation::Silu => FixedActivation::Silu,
+            Activation::Sigmoid => FixedActivation::Sigmoid,
+            Activation::Softplus => FixedActivation::Softplus,
+        }
+    }
+}
+
+impl Kernel {
+    fn layout(self) -> Layout {
+        match self {
+            Kernel::Add
+            | Kernel::Sub
+            | Kernel::Mul
+            | Kernel::Div
+            | Kernel::Minimum
+            | Kernel::Maximum => Layout::Abc,
+            Kernel::Increment
+            | Kernel::Decrement
+            | Kernel::MulInPlace
+            | Kernel::DivInPlace
+            | Kernel::RsubInPlace
+            | Kernel::RdivInPlace
+            | Kernel::MinimumInPlace
+            | Kernel::MaximumInPlace
+            | Kernel::Copy => Layout::Ab,
+            Kernel::AddScalar | Kernel::MulScalar | Kernel::MaximumScalar | Kernel::LeakyRelu => {
+                Layout::AScalarC
+            }
+            Kernel::IncrementScalar
+            | Kernel::MulInPlaceScalar
+            | Kernel::MinimumInPlaceScalar
+            | Kernel::MaximumInPlaceScalar
+            | Kernel::LeakyReluInPlace => Layout::AScalar,
+            Kernel::Select => Layout::MaskAbc,
+            Kernel::SelectMixed(a, b) => Layout::MaskAbcMixed(a, b),
+            Kernel::Compare(_) => Layout::AbcMask,
+            Kernel::CompareScalar(_) => Layout::AScalarMask,
+            Kernel::CompareMixed(_, a, b) => Layout::AbcMaskMixed(a, b),
+            Kernel::Quantize(_) => Layout::Quantize,
+            Kernel::Dequantize(_) => Layout::Dequantize,
+            Kernel::AddQuantized(_) | Kernel::MulQuantized(_) => Layout::AQuantizedC,
+            Kernel::IncrementQuantized(_) | Kernel::MulInPlaceQuantized(_) => Layout::AQuantized,
+            Kernel::Cast(from) => Layout::Cast(from),
+            Kernel::Unary(_) | Kernel::Activation(_) | Kernel::Conj => Layout::Unary,
+            Kernel::UnaryInPlace(_) | Kernel::ActivationInPlace(_) => Layout::A,
+            Kernel::Abs | Kernel::Arg => Layout::ComplexPart,
+            Kernel::AddMixed(a, b)
+            | Kernel::SubMixed(a, b)
+            | Kernel::MulMixed(a, b)
+            | Kernel::DivMixed(a, b)
+            | Kernel::MinimumMixed(a, b)
+            | Kernel::MaximumMixed(a, b) => Layout::AbcMixed(a, b),
+            Kernel::IncrementMixed(b)
+            | Kernel::DecrementMixed(b)
+            | Kernel::MulInPlaceMixed(b)
+            | Kernel::DivInPlaceMixed(b)
+            | Kernel::RsubInPlaceMixed(b)
+            | Kernel::RdivInPlaceMixed(b)
+            | Kernel::MinimumInPlaceMixed(b)
+            | Kernel::MaximumInPlaceMixed(b) => Layout::AbMixed(b),
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Kernel::Add
+            | Kernel::Sub
+            | Kernel::Mul
+            | Kernel::Div
+            | Kernel::Minimum
+            | Kernel::Maximum => include_str!("../wgpu_shaders/binary.wgsl"),
+            Kernel::Increment
+            | Kernel::Decrement
+            | Kernel::MulInPlace
+            | Kernel::DivInPlace
+            | Kernel::RsubInPlace
+            | Kernel::RdivInPlace
+            | Kernel::MinimumInPlace
+            | Kernel::MaximumInPlace => include_str!("../wgpu_shaders/in_place.wgsl"),
+            Kernel::AddScalar | Kernel::MulScalar | Kernel::MaximumScalar | Kernel::LeakyRelu => {
+                include_str!("../wgpu_shaders/binary_scalar.wgsl")
+            }
+            Kernel::IncrementScalar
+            | Kernel::MulInPlaceScalar
+            | Kernel::MinimumInPlaceScalar
+            | Kernel::MaximumInPlaceScalar
+            | Kernel::LeakyReluInPlace => include_str!("../wgpu_shaders/in_place_scalar.wgsl"),
+            Kernel::Select => include_str!("../wgpu_shaders/select.wgsl"),
+            Kernel::SelectMixed(..) => include_str!("../wgpu_shaders/select_mixed.wgsl"),
+            Kernel::Compare(_) => include_str!("../wgpu_shaders/compare.wgsl"),
+            Kernel::CompareScalar(_) => include_str!("../wgpu_shaders/compare_scalar.wgsl"),
+            Kernel::CompareMixed(..) => include_str!("../wgpu_shaders/compare_mixed.wgsl"),
+            Kernel::Quantize(_) => include_str!("../wgpu_shaders/quantize.wgsl"),
+            Kernel::Dequantize(_) => include_str!("../wgpu_shaders/dequantize.wgsl"),
+            Kernel::AddQuantized(_) | Kernel::MulQuantized(_) => {
+                include_str!("../wgpu_shaders/binary_quantized.wgsl")
+            }
+            Kernel::IncrementQuantized(_) | Kernel::MulInPlaceQuantized(_) => {
+                include_str!("../wgpu_shaders/in_place_quantized.wgsl")
+            }
+            Kernel::Copy => include_str!("../wgpu_shaders/copy.wgsl"),
+            Kernel::Cast(_) => include_str!("../wgpu_shaders/cast.wgsl"),
+            Kernel::Unary(_) | Kernel::Activation(_) | Kernel::Conj => {
+                include_str!("../wgpu_shaders/unary.wgsl")
+            }
+            Kernel::UnaryInPlace(_) | Kernel::ActivationInPlace(_) => {
+                include_str!("../wgpu_shaders/unary_in_place.wgsl")
+            }
+            Kernel::Abs | Kernel::Arg => include_str!("../wgpu_shaders/complex_part.wgsl"),
+            Kernel::AddMixed(..)
+            | Kernel::SubMixed(..)
+            | Kernel::MulMixed(..)
+            | Kernel::DivMixed(..)
+            | Kernel::MinimumMixed(..)
+            | Kernel::MaximumMixed(..) => include_str!("../wgpu_shaders/binary_mixed.wgsl"),
+            Kernel::IncrementMixed(_)
+            | Kernel::DecrementMixed(_)
+            | Kernel::MulInPlaceMixed(_)
+            | Kernel::DivInPlaceMixed(_)
+            | Kernel::RsubInPlaceMixed(_)
+            | Kernel::RdivInPlaceMixed(_)
+            | Kernel::MinimumInPlaceMixed(_)
+            | Kernel::MaximumInPlaceMixed(_) => include_str!("../wgpu_shaders/in_place_mixed.wgsl"),
+        }
+    }
+
+    /// Definitions a kernel expects next to `T`, such as the comparison it applies.
+    fn definitions(self, dtype: Dtype, features: Features) -> String {
+        const BROADCAST: &str = include_str!("../wgpu_shaders/broadcast.wgsl");
+        let quantization = match self {
+            Kernel::Quantize(format)
+            | Kernel::Dequantize(format)
+            | Kernel::AddQuantized(format)
+            | Kernel::MulQuantized(format)
+            | Kernel::IncrementQuantized(format)
+            | Kernel::MulInPlaceQuantized(format) => quantized::prelude(format),
+            _ => String::new(),
+        };
+
+        const LOG1P: &str = include_str!("../wgpu_shaders/log1p.wgsl");
+        let unary;
+        let op = match self {
+            Kernel::Unary(op) | Kernel::UnaryInPlace(op) => {
+                let functions = if op == UnaryOp::Log1p { LOG1P } else { "" };
+                unary = format!(
+                    "{functions}fn op(a: E) -> E {{ {} }}\n",
+                    unary_body(op, dtype, features)
+                );
+                &unary
+            }
+            Kernel::Activation(activation) | Kernel::ActivationInPlace(activation) => {
+                unary = format!(
+                    "{LOG1P}{}fn op(a: E) -> E {{ {} }}\n",
+                    include_str!("../wgpu_shaders/activations.wgsl"),
+                    activation_body(activation)
+                );
+                &unary
+            }
+            Kernel::Compare(op) | Kernel::CompareScalar(op) | Kernel::CompareMixed(op, ..) => {
+                match op {
+                    CompareOp::Eq => "fn cmp(a: E, b: E) -> bool { return e_eq(a, b); }\n",
+                    CompareOp::Ne => "fn cmp(a: E, b: E) -> bool { return !e_eq(a, b); }\n",
+                    CompareOp::Lt => "fn cmp(a: E, b: E) -> bool { return e_lt(a, b); }\n",
+                    CompareOp::Le => {
+                        "fn cmp(a: E, b: E) -> bool { return e_lt(a, b) || e_eq(a, b); }\n"
+                    }
+                    CompareOp::Gt => "fn cmp(a: E, b: E) -> bool { return e_lt(b, a); }\n",
+                    CompareOp::Ge => {
+                        "fn cmp(a: E, b: E) -> bool { return e_lt(b, a) || e_eq(a, b); }\n"
+                    }
+                }
+            }
+            Kernel::Add
+            | Kernel::Increment
+            | Kernel::AddScalar
+            | Kernel::IncrementScalar
+            | Kernel::AddQuantized(_)
+            | Kernel::IncrementQuantized(_)
+            | Kernel::AddMixed(..)
+            | Kernel::IncrementMixed(_) => "fn e_op(a: E, b: E) -> E { return e_add(a, b); }\n",
+            Kernel::Mul
+            | Kernel::MulInPlace
+            | Kernel::MulScalar
+            | Kernel::MulInPlaceScalar
+            | Kernel::MulQuantized(_)
+            | Kernel::MulInPlaceQuantized(_)
+            | Kernel::MulMixed(..)
+            | Kernel::MulInPlaceMixed(_) => "fn e_op(a: E, b: E) -> E { return e_mul(a, b); }\n",
+            Kernel::Sub | Kernel::Decrement | Kernel::SubMixed(..) | Kernel::DecrementMixed(_) => {
+                "fn e_op(a: E, b: E) -> E { return e_sub(a, b); }\n"
+            }
+            Kernel::Div
+            | Kernel::DivInPlace
+            | Kernel::DivMixed(..)
+            | Kernel::DivInPlaceMixed(_) => "fn e_op(a: E, b: E) -> E { return e_div(a, b); }\n",
+            Kernel::RsubInPlace | Kernel::RsubInPlaceMixed(_) => {
+                "fn e_op(a: E, b: E) -> E { return e_sub(b, a); }\n"
+            }
+            Kernel::RdivInPlace | Kernel::RdivInPlaceMixed(_) => {
+                "fn e_op(a: E, b: E) -> E { return e_div(b, a); }\n"
+            }
+            // `a` is NaN when it differs from itself, which either operand propagates.
+            Kernel::Minimum
+            | Kernel::MinimumInPlace
+            | Kernel::MinimumInPlaceScalar
+            | Kernel::MinimumMixed(..)
+            | Kernel::MinimumInPlaceMixed(_) => {
+                "fn e_op(a: E, b: E) -> E { return select(b, a, e_lt(a, b) || !e_eq(a, a)); }\n"
+            }
+            Kernel::Maximum
+            | Kernel::MaximumInPlace
+            | Kernel::MaximumScalar
+            | Kernel::MaximumInPlaceScalar
+            | Kernel::MaximumMixed(..)
+            | Kernel::MaximumInPlaceMixed(_) => {
+                "fn e_op(a: E, b: E) -> E { return select(b, a, e_lt(b, a) || !e_eq(a, a)); }\n"
+            }
+            Kernel::LeakyRelu | Kernel::LeakyReluInPlace => {
+                "fn e_op(a: E, b: E) -> E { return select(a, e_mul(a, b), e_lt(a, from_f32(0.0))); }\n"
+            }
+            Kernel::Conj => "fn op(a: E) -> E { return e_conj(a); }\n",
+            Kernel::Abs => "fn part(a: E) -> R { return e_abs(a); }\n",
+            Kernel::Arg => "fn part(a: E) -> R { return e_arg(a); }\n",
+            _ => "",
+        };
+
+        let operands = match self {
+            Kernel::Cast(Dtype::F64) if matches!(dtype, Dtype::F16 | Dtype::BF16) => {
+                f64_load_to_odd(features)
+            }
+            Kernel::Cast(from) => {
+                let (word, value) = f32_load("src", from, features);
+                format!("alias SRC = {word};\nfn load(i: u32) -> f32 {{ return {value}; }}\n")
+            }
+            Kernel::AddMixed(a, b)
+            | Kernel::SubMixed(a, b)
+            | Kernel::MulMixed(a, b)
+            | Kernel::DivMixed(a, b)
+            | Kernel::MinimumMixed(a, b)
+            | Kernel::MaximumMixed(a, b)
+            | Kernel::SelectMixed(a, b)
+            | Kernel::CompareMixed(_, a, b) => {
+                BROADCAST.to_owned()
+                    + &operand("a", a, dtype, features)
+                    + &operand("b", b, dtype, features)
+            }
+            Kernel::IncrementMixed(b)
+            | Kernel::DecrementMixed(b)
+            | Kernel::MulInPlaceMixed(b)
+            | Kernel::DivInPlaceMixed(b)
+            | Kernel::RsubInPlaceMixed(b)
+            | Kernel::RdivInPlaceMixed(b)
+            | Kernel::MinimumInPlaceMixed(b)
+            | Kernel::MaximumInPlaceMixed(b) => {
+                BROADCAST.to_owned() + &operand("b", b, dtype, features)
+            }
+            _ => String::new(),
+        };
+
+        quantization + op + &operands
+    }
+
+    /// Kernels work on storage words `S` holding `LANES` elements `E`, which the dtype prelude
+    /// defines together with `unpack`, `pack` and the element operations.
+    ///
+    /// f16 and f64 use native types when the device supports them. Otherwise f16 values are
+    /// packed in pairs and f64 values are emulated as df64, see [`GpuContext::emulates_f64`].
+    /// Complex elements are vectors of their parts, operated on through the `r_*` functions of
+    /// the real prelude.
+    ///
+    /// [`GpuContext::emulates_f64`]: super::GpuContext::emulates_f64
+    fn shader(self, dtype: Dtype, features: Features) -> ShaderModuleDescriptor<'static> {
+        const PLAIN: &str = include_str!("../wgpu_shaders/dtypes/plain.wgsl");
+        const SCALAR: &str = include_str!("../wgpu_shaders/dtypes/scalar.wgsl");
+        const FLOAT_DIV: &str = "fn e_div(a: E, b: E) -> E { return a / b; }\n";
+        // WGSL specifies that integers divided by zero give `a`, which GLSL leaves undefined.
+        const INT_DIV: &str =
+            "fn e_div(a: E, b: E) -> E { return a / select(b, E(1), b == E(0)); }\n";
+        const DF64: &str = include_str!("../wgpu_shaders/dtypes/df64.wgsl");
+        const REAL: &str = include_str!("../wgpu_shaders/dtypes/real_native.wgsl");
+        const COMPLEX: &str = include_str!("../wgpu_shaders/dtypes/complex.wgsl");
+        let prelude = match dtype {
+            Dtype::F16 if !features.contains(Features::SHADER_F16) => format!(
+                "{}{SCALAR}{FLOAT_DIV}",
+                include_str!("../wgpu_shaders/dtypes/f16_packed.wgsl")
+            ),
+            Dtype::F16 => format!("enable f16;\nalias T = f16;\n{PLAIN}{SCALAR}{FLOAT_DIV}"),
+            Dtype::F64 if !features.contains(Features::SHADER_F64) => format!(
+                "alias T = vec2<f32>;\n{PLAIN}{DF64}{}",
+                include_str!("../wgpu_shaders/dtypes/df64_elements.wgsl")
+            ),
+            Dtype::F32 => format!("alias T = f32;\n{PLAIN}{SCALAR}{FLOAT_DIV}"),
+            Dtype::F64 => format!("alias T = f64;\n{PLAIN}{SCALAR}{FLOAT_DIV}"),
+            Dtype::I32 => format!("alias T = i32;\n{PLAIN}{SCALAR}{INT_DIV}"),
+            Dtype::U32 | Dtype::Bool => format!("alias T = u32;\n{PLAIN}{SCALAR}{INT_DIV}"),
+            Dtype::BF16 => format!(
+                "{}{SCALAR}{FLOAT_DIV}",
+                include_str!("../wgpu_shaders/dtypes/bf16.wgsl")
+            ),
+            Dtype::C64 => format!("alias T = vec2<f32>;\n{PLAIN}alias R = f32;\n{REAL}{COMPLEX}"),
+            Dtype::C128 if !features.contains(Features::SHADER_F64) => format!(
+                "alias T = vec4<f32>;\n{PLAIN}{DF64}{}{COMPLEX}",
+                include_str!("../wgpu_shaders/dtypes/real_df64.wgsl")
+            ),
+            Dtype::C128 => format!("alias T = vec2<f64>;\n{PLAIN}alias R = f64;\n{REAL}{COMPLEX}"),
+        };
+
+        ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(
+                format!(
+                    "{prelude}{}{}",
+                    self.definitions(dtype, features),
+                    self.source()
+                )
+                .into(),
+            ),
+        }
+    }
+}
+
+/// The body of `op` on an element `a`, using WGSL builtins when the elements are scalars.
+fn unary_body(op: UnaryOp, dtype: Dtype, features: Features) -> &'static str {
+    if op == UnaryOp::Neg {
+        return "return -a;";
+    }
+    // WGSL has no f64 transcendental functions, native or emulated f64 goes through f32.
+    if dtype == Dtype::F64 {
+        match op {
+            UnaryOp::Exp => return "return from_f32(exp(to_f32(a)));",
+            UnaryOp::Log => return "return from_f32(log(to_f32(a)));",
+            UnaryOp::Log1p => return "return from_f32(log1p(to_f32(a)));",
+            UnaryOp::Sin => return "return from_f32(sin(to_f32(a)));",
+            UnaryOp::Cos => return "return from_f32(cos(to_f32(a)));",
+            UnaryOp::Tan => return "return from_f32(tan(to_f32(a)));",
+            UnaryOp::Tanh => return "return from_f32(tanh(to_f32(a)));",
+            _ => {}
+        }
+    }
+    if dtype == Dtype::F64 && !features.contains(Features::SHADER_F64) {
+        return match op {
+            UnaryOp::Sqrt => "return df64_sqrt(a);",
+            UnaryOp::Rsqrt => "return df64_div(vec2<f32>(1.0, 0.0), df64_sqrt(a));",
+            UnaryOp::Abs => "return select(a, -a, a.x < 0.0);",
+            UnaryOp::Floor => "return df64_floor(a);",
+            UnaryOp::Ceil => "return -df64_floor(-a);",
+            UnaryOp::Round => "return df64_round(a);",
+            UnaryOp::Sign => "return vec2<f32>(sign(a.x), 0.0);",
+            _ => unreachable!("{op:?} goes through f32 for f64"),
+        };
+    }
+    match op {
+        UnaryOp::Exp => "return exp(a);",
+        UnaryOp::Log => "return log(a);",
+        UnaryOp::Log1p => "return from_f32(log1p(to_f32(a)));",
+        UnaryOp::Sqrt => "return sqrt(a);",
+        UnaryOp::Rsqrt => "return inverseSqrt(a);",
+        UnaryOp::Abs => "return abs(a);",
+        UnaryOp::Sin => "return sin(a);",
+        UnaryOp::Cos => "return cos(a);",
+        UnaryOp::Tan => "return tan(a);",
+        UnaryOp::Tanh => "return tanh(a);",
+        UnaryOp::Floor => "return floor(a);",
+        UnaryOp::Ceil => "return ceil(a);",
+        UnaryOp::Round => "return round(a);",
+        UnaryOp::Sign => "return sign(a);",
+        UnaryOp::Neg => unreachable!(),
+    }
+}
+
+/// The body of `op` applying `activation` to an element `a`. Only `Relu` stays in the element
+/// type, the others go through the f32 functions of activations.wgsl.
+fn activation_body(activation: FixedActivation) -> &'static str {
+    match activation {
+        FixedActivation::Relu => "return select(a, from_f32(0.0), e_lt(a, from_f32(0.0)));",
+        FixedActivation::Gelu => "return from_f32(gelu(to_f32(a)));",
+        FixedActivation::Silu => "return from_f32(to_f32(a) * sigmoid(to_f32(a)));",
+        FixedActivation::Sigmoid => "return from_f32(sigmoid(to_f32(a)));",
+        FixedActivation::Softplus => "return from_f32(softplus(to_f32(a)));",
+    }
+}
+
+/// The storage word of `dtype` and an expression reading its element `i` from the array `name`
+/// as f32, which holds every f16 and bf16 exactly. f16 words are read as packed pairs even with
+/// shader f16, whose `enable` must precede the prelude.
+fn f32_load(name: &str, dtype: Dtype, features: Features) -> (&'static str, String) {
+    match dtype {
+        Dtype::F16 => ("u32", format!("unpack2x16float({name}[i / 2u])[i % 2u]")),
+        Dtype::BF16 => (
+            "u32",
+            format!("bitcast<f32>(({name}[i / 2u] >> (i % 2u * 16u)) << 16u)"),
+        ),
+        Dtype::F64 if !features.contains(Features::SHADER_F64) => {
+            ("vec2<f32>", format!("{name}[i].x + {name}[i].y"))
+        }
+        Dtype::F64 => ("f64", format!("f32({name}[i])")),
+        _ => ("f32", format!("{name}[i]")),
+    }
+}
+
+/// Declares `load` reading element `i` of the f64 array `src` as f32 rounded to odd: an inexact
+/// value becomes the neighbour whose last bit is set. Rounding that again to f16 or bf16, which
+/// keep far fewer bits, gives the f64 rounded once, where rounding to nearest twice may not.
+fn f64_load_to_odd(features: Features) -> String {
+    let load = if features.contains(Features::SHADER_F64) {
+        "alias SRC = f64;\n\
+         fn load(i: u32) -> f32 {\n\
+             let r = f32(src[i]);\n\
+             return to_odd(r, f64(r) != src[i], abs(src[i]) > abs(f64(r)));\n\
+         }\n"
+    } else {
+        // df64 values are `hi + lo` with `hi` the nearest f32, and `lo` is zero when `hi` is.
+        "alias SRC = vec2<f32>;\n\
+         fn load(i: u32) -> f32 {\n\
+             let v = src[i];\n\
+             return to_odd(v.x, v.y != 0.0, (v.y > 0.0) == (v.x > 0.0));\n\
+         }\n"
+    };
+    format!(
+        "fn to_odd(r: f32, inexact: bool, beyond: bool) -> f32 {{\n\
+             let bits = bitcast<u32>(r);\n\
+             if (!inexact || (bits & 1u) == 1u) {{ return r; }}\n\
+             return bitcast<f32>(select(bits - 1u, bits + 1u, beyond));\n\
+         }}\n{load}"
+    )
+}
+
+/// Declares the word type of the operand `name` and `load_{name}`, reading its element `i` as an
+/// element of `to`. Conversions are exact as `to` is the promoted dtype, see [`Dtype::promote`].
+fn operand(name: &str, from: Dtype, to: Dtype, features: Features) -> String {
+    let word = name.to_uppercase();
+    let (ty, value) = match (from, to) {
+        _ if from == to => (
+            "S".to_owned(),
+            format!("var x = unpack({name}[i / LANES]); return x[i % LANES];"),
+        ),
+        // f64 words are the real parts of c128 elements, native or df64.
+        (Dtype::F64, Dtype::C128) => (
+            "R".to_owned(),
+            format!("return complex({name}[i], r_from_f32(0.0));"),
+        ),
+        (Dtype::C64, Dtype::C128) => (
+            "vec2<f32>".to_owned(),
+            format!("return complex(r_from_f32({name}[i].x), r_from_f32({name}[i].y));"),
+        ),
+        _ => {
+            let (ty, value) = f32_load(name, from, features);
+            (ty.to_owned(), format!("return from_f32({value});"))
+        }
+    };
+    format!("alias {word} = {ty};\nfn load_{name}(i: u32) -> E {{ {value} }}\n")
+}
+
+pub struct PipelineCache {
+    layouts: Mutex<HashMap<(Layout, Dtype), BindGroupLayout>>,
+    pipelines: Mutex<HashMap<(Kernel, Dtype), ComputePipeline>>,
+}
+
+impl PipelineCache {
+    pub fn new() -> Self {
+        Self {
+            layouts: Mutex::new(HashMap::new()),
+            pipelines: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(
+        &self,
+        device: &Device,
+        kernel: Kernel,
+        dtype: Dtype,
+    ) -> (ComputePipeline, BindGroupLayout) {
+        let layout = self
+            .layouts
+            .lock()
+            .entry((kernel.layout(), dtype))
+            .or_insert_with(|| kernel.layout().create(device, dtype))
+            .clone();
+
+        let pipeline = self
+            .pipelines
+            .lock()
+            .entry((kernel, dtype))
+            .or_insert_with(|| {
+                let module = device.create_shader_module(kernel.shader(dtype, device.features()));
+                let pipeline_layout =
+                    device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                        label: None,
+                        bind_group_layouts: &[&layout],
+                        push_constant_ranges: &[],
+                    });
+
+                device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: None,
+                    layout: Some(&pipeline_layout),
+                    module: &module,
+                    entry_point: None,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    cache: None,
+                })
+            })
+            .clone();
+
+        (pipeline, layout)
+    }
+
+    pub fn evict(&self, kernel: Kernel, dtype: Dtype) {
+        self.layouts.lock().remove(&(kernel.layout(), dtype));
+        self.pipelines.lock().remove(&(kernel, dtype));
+    }
+}
@@ -0,0 +1,129 @@
+use wgpu::{Buffer, BufferAddress};
+
+use super::{
+    context::GpuContext,
+    dtype::Dtype,
+    error::GpuMatError,
+    pipelines::Kernel,
+    quantization::QuantFormat,
+    tensor::GpuTensor,
+    tensor_info::{TensorInfo, UniformTensorInfo},
+};
+
+fn scales_per_word(format: QuantFormat) -> u64 {
+    match format.scale {
+        Dtype::F16 => 2,
+        _ => 1,
+    }
+}
+
+/// Number of blocks allocated for `length` elements, rounded up to whole words of scales.
+fn blocks(format: QuantFormat, length: u64) -> u64 {
+    length
+        .div_ceil(format.block_size as u64)
+        .next_multiple_of(scales_per_word(format))
+        .max(scales_per_word(format))
+}
+
+/// Constants and scale accessors `quant.wgsl` is written against.
+pub(super) fn prelude(format: QuantFormat) -> String {
+    let scales = match format.scale {
+        Dtype::F16 => include_str!("../wgpu_shaders/dtypes/scale_f16.wgsl"),
+        _ => include_str!("../wgpu_shaders/dtypes/scale_f32.wgsl"),
+    };
+    format!(
+        "const BLOCK_SIZE: u32 = {}u;\nconst BITS: u32 = {}u;\nconst PER_WORD: u32 = {}u;\n\
+         const MASK: u32 = {}u;\nconst QMAX: f32 = {}.0;\n{scales}{}",
+        format.block_size,
+        format.bits.bits(),
+        32 / format.bits.bits(),
+        (1u32 << format.bits.bits()) - 1,
+        format.bits.max(),
+        include_str!("../wgpu_shaders/dtypes/quant.wgsl"),
+    )
+}
+
+/// A read only tensor stored as blocks of small integers, see [`QuantFormat`].
+///
+/// Use it as the right hand side of [`GpuTensor::add_quantized`], [`GpuTensor::mul_quantized`]
+/// and their operator forms, or expand it with [`GpuTensor::dequantize`]. Those are the only ops
+/// taking a quantized operand, and they need the float tensor on the other side to have exactly
+/// its shape: quantized operands are not broadcast, a mismatch fails with
+/// [`GpuMatError::ShapeMismatch`]. Dequantize first to use any other op.
+pub struct QuantizedTensor {
+    context: GpuContext,
+    shape: Vec<u32>,
+    format: QuantFormat,
+    data: Buffer,
+    scales: Buffer,
+}
+
+impl QuantizedTensor {
+    pub fn quantize(tensor: &GpuTensor, format: QuantFormat) -> Self {
+        Self::try_quantize(tensor, format).unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_quantize(tensor: &GpuTensor, format: QuantFormat) -> Result<Self, GpuMatError> {
+        format.check()?;
+        GpuMatError::check_float(tensor.dtype())?;
+
+        let context = tensor.context();
+        let length = tensor.shape().iter().product::<u32>() as u64;
+        let blocks = blocks(format, length);
+        let storage = |size| {
+            context.scoped(|| {
+                context.device().create_buffer(&wgpu::BufferDescriptor {
+                    label: None,
+                    size,
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                })
+            })
+        };
+        let data = storage(blocks * format.block_size as u64 * format.bits.bits() as u64 / 8)?;
+        let scales = storage(blocks / scales_per_word(format) * 4)?;
+
+        let info = TensorInfo::new(context)?;
+        info.set(context, &UniformTensorInfo::new(tensor.shape()));
+        context.dispatch(
+            Kernel::Quantize(format),
+            tensor.dtype(),
+            info.buffer(),
+            &[tensor.buffer(), &data, &scales],
+            blocks / scales_per_word(format),
+        )?;
+
+        Ok(Self {
+            context: context.clone(),
+            shape: tensor.shape().to_vec(),
+            format,
+            data,
+            scales,
+        })
+    }
+
+    pub fn context(&self) -> &GpuContext {
+        &self.context
+    }
+
+    pub fn shape(&self) -> &[u32] {
+        &self.shape
+    }
+
+    pub fn format(&self) -> QuantFormat {
+        self.format
+    }
+
+    /// Bytes of device memory held by the quantized values and their scales.
+    pub fn size(&self) -> BufferAddress {
+        self.data.size() + self.scales.size()
+    }
+
+    pub(super) fn data(&self) -> &Buffer {
+        &self.data
+    }
+
+    pub(super) fn scales(&self) -> &Buffer {
+        &self.scales
+    }
+}
@@ -1,24 +1,42 @@
-use wgpu::{Buffer, BufferAddress};
+use wgpu::{Buffer, BufferAddress, SubmissionIndex};
 
 use super::{
-    bind_groups::{
-        ADD_F16_PIPELINE, ADD_F32_PIPELINE, ADD_F64_PIPELINE, INCREMENT_F16_PIPELINE,
-        INCREMENT_F32_PIPELINE, INCREMENT_F64_PIPELINE, MUL_F16_PIPELINE, MUL_F32_PIPELINE,
-        MUL_F64_PIPELINE, MUL_IN_PLACE_F16_PIPELINE, MUL_IN_PLACE_F32_PIPELINE,
-        MUL_IN_PLACE_F64_PIPELINE, ab_f16_bind_group, ab_f32_bind_group, ab_f64_bind_group,
-        abc_f16_bind_group, abc_f32_bind_group, abc_f64_bind_group,
-    },
-    command_encoder::GlobalCommandEncoder,
+    context::GpuContext,
     download_vec::DownloadGpuTensor,
     dtype::Dtype,
+    dtype::DtypeVec,
     dtype::Dtyped,
-    globals::DEVICE_QUEUE,
-    handle::{ComputeHandle, INTERMEDIATES_MAP},
-    tensor_info::{TensorInfo, UniformTensorInfo},
-    vec::GpuVec,
+    error::GpuMatError,
+    handle::ComputeHandle,
+    ops::{Activation, CompareOp, UnaryOp, activation_methods, unary_methods},
+    pipelines::Kernel,
+    quantization::QuantFormat,
+    quantized::QuantizedTensor,
+    tensor_info::{TensorInfo, UniformBroadcast, UniformTensorInfo},
+    vec::{self, GpuVec},
 };
 
+/// The storage behind a [`GpuTensor`], see [`GpuTensor::raw`].
+pub struct RawBuffer<'a> {
+    pub buffer: &'a Buffer,
+    /// Offset of the first element, in bytes.
+    pub offset: BufferAddress,
+    /// Length of the elements in `shape`, in bytes. The buffer itself may be larger.
+    pub size: BufferAddress,
+    pub dtype: Dtype,
+    pub shape: &'a [u32],
+}
+
+/// A binary kernel with the dtype it is compiled for, and the strides of its operands for the
+/// mixed kernels converting and broadcasting them.
+struct Binary {
+    kernel: Kernel,
+    dtype: Dtype,
+    strides: Option<Buffer>,
+}
+
 pub struct GpuTensor {
+    context: GpuContext,
     shape: Vec<u32>,
     info: TensorInfo,
     buffer: GpuVec,
@@ -26,23 +44,193 @@ pub struct GpuTensor {
 
 impl GpuTensor {
     pub fn new<F: Dtyped>(shape: Vec<u32>, values: &[F]) -> Self {
-        assert!(shape.iter().product::<u32>() == values.len() as u32);
+        Self::try_new(shape, values).unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_new<F: Dtyped>(shape: Vec<u32>, values: &[F]) -> Result<Self, GpuMatError> {
+        Self::try_new_in(GpuContext::try_global()?, shape, values)
+    }
 
-        Self {
+    pub fn new_in<F: Dtyped>(context: &GpuContext, shape: Vec<u32>, values: &[F]) -> Self {
+        Self::try_new_in(context, shape, values).unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_new_in<F: Dtyped>(
+        context: &GpuContext,
+        shape: Vec<u32>,
+        values: &[F],
+    ) -> Result<Self, GpuMatError> {
+        GpuMatError::check_len(&shape, values.len())?;
+
+        Ok(Self {
+            context: context.clone(),
             shape,
-            info: TensorInfo::new(),
-            buffer: GpuVec::new_init(values),
-        }
+            info: TensorInfo::new(context)?,
+            buffer: GpuVec::new_init(context, values)?,
+        })
     }
 
     pub fn with_capacity(capacity: BufferAddress) -> Self {
-        Self {
+        Self::try_with_capacity(capacity).unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_with_capacity(capacity: BufferAddress) -> Result<Self, GpuMatError> {
+        Self::try_with_capacity_in(GpuContext::try_global()?, capacity)
+    }
+
+    pub fn with_capacity_in(context: &GpuContext, capacity: BufferAddress) -> Self {
+        Self::try_with_capacity_in(context, capacity).unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_with_capacity_in(
+        context: &GpuContext,
+        capacity: BufferAddress,
+    ) -> Result<Self, GpuMatError> {
+        Ok(Self {
+            context: context.clone(),
             shape: Vec::new(),
-            info: TensorInfo::new(),
-            buffer: GpuVec::new_uninit::<f32>(capacity),
+            info: TensorInfo::new(context)?,
+            buffer: GpuVec::new_uninit::<f32>(context, capacity)?,
+        })
+    }
+
+    pub fn upload(values: &DtypeVec) -> Self {
+        Self::try_upload(values).unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_upload(values: &DtypeVec) -> Result<Self, GpuMatError> {
+        Self::try_upload_in(GpuContext::try_global()?, values)
+    }
+
+    pub fn upload_in(context: &GpuContext, values: &DtypeVec) -> Self {
+        Self::try_upload_in(context, values).unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_upload_in(context: &GpuContext, values: &DtypeVec) -> Result<Self, GpuMatError> {
+        let shape = values.shape();
+        match values {
+            DtypeVec::F16(array) => Self::try_new_in(
+                context,
+                shape,
+                array.as_standard_layout().as_slice().unwrap(),
+            ),
+            DtypeVec::BF16(array) => Self::try_new_in(
+                context,
+                shape,
+                array.as_standard_layout().as_slice().unwrap(),
+            ),
+            DtypeVec::F32(array) => Self::try_new_in(
+                context,
+                shape,
+                array.as_standard_layout().as_slice().unwrap(),
+            ),
+            DtypeVec::F64(array) => Self::try_new_in(
+                context,
+                shape,
+                array.as_standard_layout().as_slice().unwrap(),
+            ),
+            DtypeVec::I32(array) => Self::try_new_in(
+                context,
+                shape,
+                array.as_standard_layout().as_slice().unwrap(),
+            ),
+            DtypeVec::U32(array) => Self::try_new_in(
+                context,
+                shape,
+                array.as_standard_layout().as_slice().unwrap(),
+            ),
+            DtypeVec::Bool(array) => {
+                let values = array.iter().map(|&value| value as u32).collect::<Vec<_>>();
+                let mut tensor = Self::try_new_in(context, shape, &values)?;
+                tensor.buffer.set_dtype(Dtype::Bool);
+                Ok(tensor)
+            }
+            DtypeVec::C64(array) => Self::try_new_in(
+                context,
+                shape,
+                array.as_standard_layout().as_slice().unwrap(),
+            ),
+            DtypeVec::C128(array) => Self::try_new_in(
+                context,
+                shape,
+                array.as_standard_layout().as_slice().unwrap(),
+            ),
         }
     }
 
+    /// Wraps a buffer created on the device of `context`, read as `dtype` values from offset 0.
+    ///
+    /// The buffer needs `STORAGE | COPY_SRC` usage, a size that is a multiple of 4 bytes and room
+    /// for every element of `shape`. Operations write to it in place, like any other tensor.
+    /// F64 values are `(hi, lo)` f32 pairs when the context [emulates f64].
+    ///
+    /// Callers must make sure the buffer comes from [`GpuContext::device`]: wgpu does not expose
+    /// the device of a buffer, so one from another device is not rejected here, and operations
+    /// on the tensor are not guaranteed to report it either.
+    ///
+    /// [emulates f64]: GpuContext::emulates_f64
+    pub fn from_buffer_in(
+        context: &GpuContext,
+        buffer: Buffer,
+        dtype: Dtype,
+        shape: Vec<u32>,
+    ) -> Self {
+        Self::try_from_buffer_in(context, buffer, dtype, shape)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_from_buffer_in(
+        context: &GpuContext,
+        buffer: Buffer,
+        dtype: Dtype,
+        shape: Vec<u32>,
+    ) -> Result<Self, GpuMatError> {
+        let usage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC;
+        if !buffer.usage().contains(usage) {
+            return Err(GpuMatError::InvalidBuffer(format!(
+                "usage {:?} lacks {:?}",
+                buffer.usage(),
+                usage - buffer.usage()
+            )));
+        }
+        if !buffer.size().is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(GpuMatError::InvalidBuffer(format!(
+                "size {} is not a multiple of {}",
+                buffer.size(),
+                wgpu::COPY_BUFFER_ALIGNMENT
+            )));
+        }
+
+        let buffer = GpuVec::from_buffer(buffer, dtype);
+        GpuMatError::check_capacity(&shape, buffer.capacity_elements())?;
+
+        Ok(Self {
+            context: context.clone(),
+            shape,
+            info: TensorInfo::new(context)?,
+            buffer,
+        })
+    }
+
+    /// The buffer backing the tensor, to bind it into passes outside of gpu-mat.
+    ///
+    /// Operations are recorded lazily, call [`GpuContext::flush`] before the host reads the
+    /// buffer.
+    pub fn raw(&self) -> RawBuffer<'_> {
+        RawBuffer {
+            buffer: self.buffer(),
+            offset: 0,
+            size: self.shape.iter().product::<u32>() as BufferAddress
+                * self.dtype().size() as BufferAddress,
+            dtype: self.dtype(),
+            shape: &self.shape,
+        }
+    }
+
+    pub fn context(&self) -> &GpuContext {
+        &self.context
+    }
+
     pub fn capacity(&self) -> BufferAddress {
         self.buffer.capacity()
     }
@@ -51,7 +239,11 @@ impl GpuTensor {
         self.buffer.capacity_elements()
     }
 
-    fn buffer(&self) -> &Buffer {
+    pub fn shape(&self) -> &[u32] {
+        &self.shape
+    }
+
+    pub(super) fn buffer(&self) -> &Buffer {
         self.buffer.buffer()
     }
 
@@ -60,40 +252,86 @@ impl GpuTensor {
     }
 
     pub fn compute(&self) -> ComputeHandle {
-        let output_download_vec =
-            DownloadGpuTensor::new(self.capacity(), self.shape.clone(), self.dtype());
-
-        let mut encoder = GlobalCommandEncoder::lock();
-        encoder.get().copy_buffer_to_buffer(
-            &self.buffer(),
-            0,
-            &output_download_vec.buffer(),
-            0,
-            self.capacity(),
-        );
+        match self.submit_download() {
+            Ok((output, idx)) => ComputeHandle::new(self.context.clone(), output, idx),
+            Err(err) => ComputeHandle::failed(self.context.clone(), err),
+        }
+    }
+
+    pub fn download(&self) -> DtypeVec {
+        self.try_download()
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_download(&self) -> Result<DtypeVec, GpuMatError> {
+        let (download_vec, idx) = self.submit_download()?;
+
+        download_vec
+            .buffer()
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, |_| {});
+        self.context
+            .device()
+            .poll(wgpu::PollType::WaitForSubmissionIndex(idx))
+            .map_err(|err| GpuMatError::Download(err.to_string()))?;
+        self.context.status()?;
+
+        Ok(download_vec.to_vec(&self.context))
+    }
+
+    /// Copies the tensor into a mappable buffer and submits all pending work.
+    fn submit_download(&self) -> Result<(DownloadGpuTensor, SubmissionIndex), GpuMatError> {
+        self.context.scoped(|| {
+            let download_vec = DownloadGpuTensor::new(
+                &self.context,
+                self.capacity(),
+                self.shape.clone(),
+                self.dtype(),
+            )?;
+
+            let mut encoder = self.context.encoder();
+            encoder.get().copy_buffer_to_buffer(
+                self.buffer(),
+                0,
+                download_vec.buffer(),
+                0,
+                self.capacity(),
+            );
 
-        let command_buffer = encoder.finish();
-        let idx = DEVICE_QUEUE.1.submit([command_buffer]);
-        ComputeHandle::new(output_download_vec, idx)
+            let command_buffer = encoder.finish();
+            let idx = self.context.queue().submit([command_buffer]);
+            Ok((download_vec, idx))
+        })?
     }
 
     pub fn save_intermediate(&self, name: &'static str) -> &Self {
-        let intermediate_download_vec =
-            DownloadGpuTensor::new(self.capacity(), self.shape.clone(), self.dtype());
-
-        GlobalCommandEncoder::lock().get().copy_buffer_to_buffer(
-            &self.buffer(),
-            0,
-            &intermediate_download_vec.buffer(),
-            0,
+        self.try_save_intermediate(name)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_save_intermediate(&self, name: &'static str) -> Result<&Self, GpuMatError> {
+        let intermediate_download_vec = DownloadGpuTensor::new(
+            &self.context,
             self.capacity(),
-        );
+            self.shape.clone(),
+            self.dtype(),
+        )?;
 
-        INTERMEDIATES_MAP
-            .lock()
+        self.context.scoped(|| {
+            self.context.encoder().get().copy_buffer_to_buffer(
+                self.buffer(),
+                0,
+                intermediate_download_vec.buffer(),
+                0,
+                self.capacity(),
+            )
+        })?;
+
+        self.context
+            .intermediates()
             .insert(name, intermediate_download_vec);
 
-        self
+        Ok(self)
     }
 
     pub fn save_intermediate_mut(&mut self, name: &'static str) -> &mut Self {
@@ -101,274 +339,1145 @@ impl GpuTensor {
         self
     }
 
-    pub fn set(&mut self, seter: GpuTensorSetterFn<impl FnOnce(&mut GpuTensor)>) -> &mut Self {
-        seter.0(self);
-        self
+    pub fn set(&mut self, seter: GpuTensorSetterFn<impl SetterFn>) -> &mut Self {
+        self.try_set(seter)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
     }
 
-    pub fn add(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
-        assert!(lhs.dtype() == rhs.dtype());
-        self.buffer.set_dtype(lhs.dtype());
+    pub fn try_set(
+        &mut self,
+        seter: GpuTensorSetterFn<impl SetterFn>,
+    ) -> Result<&mut Self, GpuMatError> {
+        seter.0(self)?;
+        Ok(self)
+    }
 
-        assert!(lhs.shape == rhs.shape);
-        assert!(self.buffer.capacity_elements() as u32 >= lhs.shape.iter().product::<u32>());
-        self.shape.clear();
-        self.shape.extend_from_slice(&lhs.shape);
+    fn dispatch(
+        &self,
+        kernel: Kernel,
+        dtype: Dtype,
+        buffers: &[&Buffer],
+    ) -> Result<(), GpuMatError> {
+        self.context.dispatch(
+            kernel,
+            dtype,
+            self.info.buffer(),
+            buffers,
+            self.capacity_elements(),
+        )
+    }
 
-        self.info.set(&UniformTensorInfo::new(&self.shape));
-        let mut encoder = GlobalCommandEncoder::lock();
-        let mut compute_pass = encoder
-            .get()
-            .begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: None,
-                timestamp_writes: None,
-            });
+    /// Dispatches a binary kernel on `buffers`, followed by the strides of its operands if it is
+    /// one of the mixed kernels.
+    fn dispatch_binary(&self, binary: Binary, buffers: &[&Buffer]) -> Result<(), GpuMatError> {
+        match binary.strides {
+            Some(strides) => self.dispatch(
+                binary.kernel,
+                binary.dtype,
+                &[buffers, &[&strides]].concat(),
+            ),
+            None => self.dispatch(binary.kernel, binary.dtype, buffers),
+        }
+    }
+
+    /// The strides of operands of shapes `a`, `b` and `mask` broadcast to `shape`.
+    fn strides(
+        &self,
+        shape: &[u32],
+        a: &[u32],
+        b: &[u32],
+        mask: &[u32],
+    ) -> Result<Buffer, GpuMatError> {
+        let strides = UniformBroadcast::new(shape, a, b, mask);
+        vec::uniform_buffer(&self.context, bytemuck::bytes_of(&strides))
+    }
+
+    /// Prepares the output of a binary operation, and picks the kernel converting its operands
+    /// to their promoted dtype and broadcasting them to a common shape if they differ. The
+    /// [`Dtype::Bool`] `mask` of a select is broadcast along with them.
+    fn prepare_output(
+        &mut self,
+        lhs: &Self,
+        rhs: &Self,
+        mask: Option<&Self>,
+        uniform: Kernel,
+        mixed: fn(Dtype, Dtype) -> Kernel,
+    ) -> Result<Binary, GpuMatError> {
+        let dtype = GpuMatError::promote(lhs.dtype(), rhs.dtype())?;
+        GpuMatError::check_numeric(dtype)?;
+        self.prepare_broadcast(lhs, rhs, mask, dtype, uniform, mixed)
+    }
 
-        match self.dtype() {
-            Dtype::F16 => {
-                compute_pass.set_pipeline(&ADD_F16_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &abc_f16_bind_group(
-                        self.info.buffer(),
-                        &lhs.buffer(),
-                        &rhs.buffer(),
-                        &self.buffer(),
-                    ),
-                    &[],
-                );
+    /// Like [`GpuTensor::prepare_output`] for an output of `dtype` rather than the promoted one,
+    /// such as the mask of a comparison.
+    fn prepare_broadcast(
+        &mut self,
+        lhs: &Self,
+        rhs: &Self,
+        mask: Option<&Self>,
+        dtype: Dtype,
+        uniform: Kernel,
+        mixed: impl FnOnce(Dtype, Dtype) -> Kernel,
+    ) -> Result<Binary, GpuMatError> {
+        let promoted = GpuMatError::promote(lhs.dtype(), rhs.dtype())?;
+        let mut shape = GpuMatError::broadcast(&lhs.shape, &rhs.shape)?;
+        if let Some(mask) = mask {
+            if self.context != mask.context {
+                return Err(GpuMatError::ContextMismatch);
             }
-            Dtype::F32 => {
-                compute_pass.set_pipeline(&ADD_F32_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &abc_f32_bind_group(
-                        self.info.buffer(),
-                        &lhs.buffer(),
-                        &rhs.buffer(),
-                        &self.buffer(),
-                    ),
-                    &[],
-                );
+            GpuMatError::check_dtypes(Dtype::Bool, mask.dtype())?;
+            shape = GpuMatError::broadcast(&mask.shape, &shape)?;
+        }
+        if self.context != lhs.context || self.context != rhs.context {
+            return Err(GpuMatError::ContextMismatch);
+        }
+        GpuMatError::check_capacity(&shape, self.capacity() / dtype.size() as BufferAddress)?;
+
+        let mask = mask.map(|mask| &mask.shape[..]);
+        let binary = if lhs.dtype() == rhs.dtype()
+            && lhs.shape == shape
+            && rhs.shape == shape
+            && mask.is_none_or(|mask| *mask == shape[..])
+        {
+            Binary {
+                kernel: uniform,
+                dtype: promoted,
+                strides: None,
             }
-            Dtype::F64 => {
-                compute_pass.set_pipeline(&ADD_F64_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &abc_f64_bind_group(
-                        self.info.buffer(),
-                        &lhs.buffer(),
-                        &rhs.buffer(),
-                        &self.buffer(),
-                    ),
-                    &[],
-                );
+        } else {
+            let strides = self.strides(&shape, &lhs.shape, &rhs.shape, mask.unwrap_or_default())?;
+            Binary {
+                kernel: mixed(lhs.dtype(), rhs.dtype()),
+                dtype: promoted,
+                strides: Some(strides),
             }
+        };
+        self.set_output(&shape, dtype);
+        Ok(binary)
+    }
+
+    fn prepare_unary(&mut self, src: &Self, dtype: Dtype) -> Result<(), GpuMatError> {
+        if self.context != src.context {
+            return Err(GpuMatError::ContextMismatch);
         }
+        GpuMatError::check_capacity(&src.shape, self.capacity() / dtype.size() as BufferAddress)?;
 
-        let workgroup_count = self.capacity_elements().div_ceil(64);
-        compute_pass.dispatch_workgroups(workgroup_count as u32, 1, 1);
+        self.set_output(&src.shape, dtype);
+        Ok(())
+    }
 
-        self
+    /// Gives the tensor the `shape` and `dtype` of the result of an operation, once nothing but
+    /// its dispatch can fail so that errors leave the tensor as it was.
+    fn set_output(&mut self, shape: &[u32], dtype: Dtype) {
+        self.buffer.set_dtype(dtype);
+        self.shape.clear();
+        self.shape.extend_from_slice(shape);
+
+        self.info
+            .set(&self.context, &UniformTensorInfo::new(&self.shape));
     }
 
-    pub fn increment(&mut self, by: &Self) -> &mut Self {
-        assert!(self.dtype() == by.dtype());
-        assert!(self.shape == by.shape);
-
-        self.info.set(&UniformTensorInfo::new(&self.shape));
-        let mut encoder = GlobalCommandEncoder::lock();
-        let mut compute_pass = encoder
-            .get()
-            .begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: None,
-                timestamp_writes: None,
+    /// Like [`GpuTensor::prepare_output`], `by` may have another dtype and shape as long as they
+    /// promote and broadcast to those of the tensor.
+    fn prepare_in_place(
+        &self,
+        by: &Self,
+        uniform: Kernel,
+        mixed: fn(Dtype) -> Kernel,
+    ) -> Result<Binary, GpuMatError> {
+        if self.context != by.context {
+            return Err(GpuMatError::ContextMismatch);
+        }
+        GpuMatError::check_numeric(self.dtype())?;
+        if GpuMatError::promote(self.dtype(), by.dtype())? != self.dtype() {
+            return Err(GpuMatError::DtypeMismatch {
+                lhs: self.dtype(),
+                rhs: by.dtype(),
             });
+        }
+        GpuMatError::check_broadcast_to(&self.shape, &by.shape)?;
 
-        match self.dtype() {
-            Dtype::F16 => {
-                compute_pass.set_pipeline(&INCREMENT_F16_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &ab_f16_bind_group(self.info.buffer(), &self.buffer(), &by.buffer()),
-                    &[],
-                );
-            }
-            Dtype::F32 => {
-                compute_pass.set_pipeline(&INCREMENT_F32_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &ab_f32_bind_group(self.info.buffer(), &self.buffer(), &by.buffer()),
-                    &[],
-                );
+        let binary = if self.dtype() == by.dtype() && self.shape == by.shape {
+            Binary {
+                kernel: uniform,
+                dtype: self.dtype(),
+                strides: None,
             }
-            Dtype::F64 => {
-                compute_pass.set_pipeline(&INCREMENT_F64_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &ab_f64_bind_group(self.info.buffer(), &self.buffer(), &by.buffer()),
-                    &[],
-                );
+        } else {
+            Binary {
+                kernel: mixed(by.dtype()),
+                dtype: self.dtype(),
+                strides: Some(self.strides(&self.shape, &self.shape, &by.shape, &[])?),
             }
-        }
+        };
+        self.info
+            .set(&self.context, &UniformTensorInfo::new(&self.shape));
+        Ok(binary)
+    }
 
-        let workgroup_count = self.capacity_elements().div_ceil(64);
-        compute_pass.dispatch_workgroups(workgroup_count as u32, 1, 1);
+    pub fn add(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_add(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
 
-        self
+    /// Writes `lhs + rhs`. Operands of different dtypes are converted to the [promoted] dtype as
+    /// they are loaded, which is the dtype of the output.
+    ///
+    /// [promoted]: Dtype::promote
+    pub fn try_add(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        let binary = self.prepare_output(lhs, rhs, None, Kernel::Add, Kernel::AddMixed)?;
+        self.dispatch_binary(binary, &[lhs.buffer(), rhs.buffer(), self.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn increment(&mut self, by: &Self) -> &mut Self {
+        self.try_increment(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Adds `by` in place, which may have another dtype if it [promotes] to the dtype of the
+    /// tensor.
+    ///
+    /// [promotes]: Dtype::promote
+    pub fn try_increment(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        let binary = self.prepare_in_place(by, Kernel::Increment, Kernel::IncrementMixed)?;
+        self.dispatch_binary(binary, &[self.buffer(), by.buffer()])?;
+
+        Ok(self)
     }
 
     pub fn mul(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
-        assert!(lhs.dtype() == rhs.dtype());
-        self.buffer.set_dtype(lhs.dtype());
+        self.try_mul(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_mul(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        let binary = self.prepare_output(lhs, rhs, None, Kernel::Mul, Kernel::MulMixed)?;
+        self.dispatch_binary(binary, &[lhs.buffer(), rhs.buffer(), self.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn mul_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_mul_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_mul_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        let binary = self.prepare_in_place(by, Kernel::MulInPlace, Kernel::MulInPlaceMixed)?;
+        self.dispatch_binary(binary, &[self.buffer(), by.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn sub(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_sub(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_sub(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        let binary = self.prepare_output(lhs, rhs, None, Kernel::Sub, Kernel::SubMixed)?;
+        self.dispatch_binary(binary, &[lhs.buffer(), rhs.buffer(), self.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn decrement(&mut self, by: &Self) -> &mut Self {
+        self.try_decrement(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_decrement(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        let binary = self.prepare_in_place(by, Kernel::Decrement, Kernel::DecrementMixed)?;
+        self.dispatch_binary(binary, &[self.buffer(), by.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn rsub_in_place(&mut self, lhs: &Self) -> &mut Self {
+        self.try_rsub_in_place(lhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Replaces every element `x` of the tensor with `lhs - x`.
+    pub fn try_rsub_in_place(&mut self, lhs: &Self) -> Result<&mut Self, GpuMatError> {
+        let binary = self.prepare_in_place(lhs, Kernel::RsubInPlace, Kernel::RsubInPlaceMixed)?;
+        self.dispatch_binary(binary, &[self.buffer(), lhs.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn div(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_div(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `lhs / rhs`. Integers round toward zero, and dividing one by zero gives `lhs` as
+    /// WGSL specifies.
+    pub fn try_div(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        let binary = self.prepare_output(lhs, rhs, None, Kernel::Div, Kernel::DivMixed)?;
+        self.dispatch_binary(binary, &[lhs.buffer(), rhs.buffer(), self.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn div_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_div_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_div_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        let binary = self.prepare_in_place(by, Kernel::DivInPlace, Kernel::DivInPlaceMixed)?;
+        self.dispatch_binary(binary, &[self.buffer(), by.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn rdiv_in_place(&mut self, lhs: &Self) -> &mut Self {
+        self.try_rdiv_in_place(lhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Replaces every element `x` of the tensor with `lhs / x`.
+    pub fn try_rdiv_in_place(&mut self, lhs: &Self) -> Result<&mut Self, GpuMatError> {
+        let binary = self.prepare_in_place(lhs, Kernel::RdivInPlace, Kernel::RdivInPlaceMixed)?;
+        self.dispatch_binary(binary, &[self.buffer(), lhs.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn add_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.try_add_scalar(lhs, scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `lhs + scalar`. Float scalars convert to the float or complex dtype of `lhs`, so
+    /// `&a + 1.0` works on f32 tensors, while other scalars must have the dtype of `lhs`.
+    pub fn try_add_scalar<F: Dtyped>(
+        &mut self,
+        lhs: &Self,
+        scalar: F,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_scalar(lhs.dtype(), F::dtype())?;
+        let scalar = vec::scalar_uniform(&self.context, scalar, lhs.dtype())?;
+        self.prepare_unary(lhs, lhs.dtype())?;
+        self.dispatch(
+            Kernel::AddScalar,
+            lhs.dtype(),
+            &[lhs.buffer(), &scalar, self.buffer()],
+        )?;
+
+        Ok(self)
+    }
+
+    pub fn mul_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.try_mul_scalar(lhs, scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `lhs * scalar`, converting `scalar` like [`GpuTensor::try_add_scalar`].
+    pub fn try_mul_scalar<F: Dtyped>(
+        &mut self,
+        lhs: &Self,
+        scalar: F,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_scalar(lhs.dtype(), F::dtype())?;
+        let scalar = vec::scalar_uniform(&self.context, scalar, lhs.dtype())?;
+        self.prepare_unary(lhs, lhs.dtype())?;
+        self.dispatch(
+            Kernel::MulScalar,
+            lhs.dtype(),
+            &[lhs.buffer(), &scalar, self.buffer()],
+        )?;
+
+        Ok(self)
+    }
+
+    pub fn shift<F: Dtyped>(&mut self, scalar: F) -> &mut Self {
+        self.try_shift(scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Adds `scalar` to every element, converting it like [`GpuTensor::try_add_scalar`].
+    pub fn try_shift<F: Dtyped>(&mut self, scalar: F) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_scalar(self.dtype(), F::dtype())?;
+        self.info
+            .set(&self.context, &UniformTensorInfo::new(&self.shape));
+        let scalar = vec::scalar_uniform(&self.context, scalar, self.dtype())?;
+        self.dispatch(
+            Kernel::IncrementScalar,
+            self.dtype(),
+            &[self.buffer(), &scalar],
+        )?;
+
+        Ok(self)
+    }
+
+    pub fn scale<F: Dtyped>(&mut self, scalar: F) -> &mut Self {
+        self.try_scale(scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Multiplies every element by `scalar`, converting it like [`GpuTensor::try_add_scalar`].
+    pub fn try_scale<F: Dtyped>(&mut self, scalar: F) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_scalar(self.dtype(), F::dtype())?;
+        self.info
+            .set(&self.context, &UniformTensorInfo::new(&self.shape));
+        let scalar = vec::scalar_uniform(&self.context, scalar, self.dtype())?;
+        self.dispatch(
+            Kernel::MulInPlaceScalar,
+            self.dtype(),
+            &[self.buffer(), &scalar],
+        )?;
+
+        Ok(self)
+    }
+
+    pub fn minimum(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_minimum(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the smaller of `lhs` and `rhs` for every element, which must be floats. Operands
+    /// promote and broadcast like for [`GpuTensor::try_add`], and a NaN in either one gives NaN.
+    pub fn try_minimum(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(GpuMatError::promote(lhs.dtype(), rhs.dtype())?)?;
+        let binary = self.prepare_output(lhs, rhs, None, Kernel::Minimum, Kernel::MinimumMixed)?;
+        self.dispatch_binary(binary, &[lhs.buffer(), rhs.buffer(), self.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn maximum(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_maximum(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the larger of `lhs` and `rhs` for every element, see [`GpuTensor::try_minimum`].
+    pub fn try_maximum(&mut self, lhs: &Self, rhs: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(GpuMatError::promote(lhs.dtype(), rhs.dtype())?)?;
+        let binary = self.prepare_output(lhs, rhs, None, Kernel::Maximum, Kernel::MaximumMixed)?;
+        self.dispatch_binary(binary, &[lhs.buffer(), rhs.buffer(), self.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn minimum_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_minimum_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_minimum_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(self.dtype())?;
+        let binary =
+            self.prepare_in_place(by, Kernel::MinimumInPlace, Kernel::MinimumInPlaceMixed)?;
+        self.dispatch_binary(binary, &[self.buffer(), by.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn maximum_in_place(&mut self, by: &Self) -> &mut Self {
+        self.try_maximum_in_place(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_maximum_in_place(&mut self, by: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(self.dtype())?;
+        let binary =
+            self.prepare_in_place(by, Kernel::MaximumInPlace, Kernel::MaximumInPlaceMixed)?;
+        self.dispatch_binary(binary, &[self.buffer(), by.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn clamp(&mut self, src: &Self, lo: &Self, hi: &Self) -> &mut Self {
+        self.try_clamp(src, lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `src` clamped between `lo` and `hi`, as the maximum with `lo` then the minimum with
+    /// `hi`. Elements where `lo > hi` therefore get `hi`, and `hi` must broadcast to the shape of
+    /// the first step.
+    pub fn try_clamp(
+        &mut self,
+        src: &Self,
+        lo: &Self,
+        hi: &Self,
+    ) -> Result<&mut Self, GpuMatError> {
+        self.try_maximum(src, lo)?.try_minimum_in_place(hi)
+    }
+
+    pub fn clamp_in_place(&mut self, lo: &Self, hi: &Self) -> &mut Self {
+        self.try_clamp_in_place(lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_clamp_in_place(&mut self, lo: &Self, hi: &Self) -> Result<&mut Self, GpuMatError> {
+        self.try_maximum_in_place(lo)?.try_minimum_in_place(hi)
+    }
+
+    pub fn clamp_scalar<F: Dtyped>(&mut self, src: &Self, lo: F, hi: F) -> &mut Self {
+        self.try_clamp_scalar(src, lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Like [`GpuTensor::try_clamp`] with scalar bounds, converted like
+    /// [`GpuTensor::try_add_scalar`].
+    pub fn try_clamp_scalar<F: Dtyped>(
+        &mut self,
+        src: &Self,
+        lo: F,
+        hi: F,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(src.dtype())?;
+        GpuMatError::check_scalar(src.dtype(), F::dtype())?;
+        let lo = vec::scalar_uniform(&self.context, lo, src.dtype())?;
+        let hi = vec::scalar_uniform(&self.context, hi, src.dtype())?;
+        self.prepare_unary(src, src.dtype())?;
+        self.dispatch(
+            Kernel::MaximumScalar,
+            src.dtype(),
+            &[src.buffer(), &lo, self.buffer()],
+        )?;
+        self.dispatch(
+            Kernel::MinimumInPlaceScalar,
+            src.dtype(),
+            &[self.buffer(), &hi],
+        )?;
+
+        Ok(self)
+    }
+
+    pub fn clamp_scalar_in_place<F: Dtyped>(&mut self, lo: F, hi: F) -> &mut Self {
+        self.try_clamp_scalar_in_place(lo, hi)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_clamp_scalar_in_place<F: Dtyped>(
+        &mut self,
+        lo: F,
+        hi: F,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(self.dtype())?;
+        GpuMatError::check_scalar(self.dtype(), F::dtype())?;
+        self.info
+            .set(&self.context, &UniformTensorInfo::new(&self.shape));
+        let lo = vec::scalar_uniform(&self.context, lo, self.dtype())?;
+        let hi = vec::scalar_uniform(&self.context, hi, self.dtype())?;
+        self.dispatch(
+            Kernel::MaximumInPlaceScalar,
+            self.dtype(),
+            &[self.buffer(), &lo],
+        )?;
+        self.dispatch(
+            Kernel::MinimumInPlaceScalar,
+            self.dtype(),
+            &[self.buffer(), &hi],
+        )?;
+
+        Ok(self)
+    }
+
+    pub fn select(&mut self, mask: &Self, a: &Self, b: &Self) -> &mut Self {
+        self.try_select(mask, a, b)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the elements of `a` where the [`Dtype::Bool`] `mask` is set and those of `b`
+    /// elsewhere, like `where` in NumPy. The three broadcast to a common shape, and `a` and `b`
+    /// promote like for [`GpuTensor::try_add`].
+    pub fn try_select(
+        &mut self,
+        mask: &Self,
+        a: &Self,
+        b: &Self,
+    ) -> Result<&mut Self, GpuMatError> {
+        let binary = self.prepare_output(a, b, Some(mask), Kernel::Select, Kernel::SelectMixed)?;
+        self.dispatch_binary(
+            binary,
+            &[mask.buffer(), a.buffer(), b.buffer(), self.buffer()],
+        )?;
+
+        Ok(self)
+    }
+
+    pub fn compare(&mut self, op: CompareOp, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.try_compare(op, lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes a [`Dtype::Bool`] mask holding `lhs op rhs` for every element. Operands promote and
+    /// broadcast like for [`GpuTensor::try_add`], and complex ones cannot be compared.
+    pub fn try_compare(
+        &mut self,
+        op: CompareOp,
+        lhs: &Self,
+        rhs: &Self,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_ordered(GpuMatError::promote(lhs.dtype(), rhs.dtype())?)?;
+        let binary =
+            self.prepare_broadcast(lhs, rhs, None, Dtype::Bool, Kernel::Compare(op), |a, b| {
+                Kernel::CompareMixed(op, a, b)
+            })?;
+        self.dispatch_binary(binary, &[lhs.buffer(), rhs.buffer(), self.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn compare_scalar<F: Dtyped>(&mut self, op: CompareOp, lhs: &Self, scalar: F) -> &mut Self {
+        self.try_compare_scalar(op, lhs, scalar)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes a [`Dtype::Bool`] mask holding `lhs op scalar` for every element, converting
+    /// `scalar` like [`GpuTensor::try_add_scalar`].
+    pub fn try_compare_scalar<F: Dtyped>(
+        &mut self,
+        op: CompareOp,
+        lhs: &Self,
+        scalar: F,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_ordered(lhs.dtype())?;
+        GpuMatError::check_scalar(lhs.dtype(), F::dtype())?;
+        let scalar = vec::scalar_uniform(&self.context, scalar, lhs.dtype())?;
+        self.prepare_unary(lhs, Dtype::Bool)?;
+        self.dispatch(
+            Kernel::CompareScalar(op),
+            lhs.dtype(),
+            &[lhs.buffer(), &scalar, self.buffer()],
+        )?;
+
+        Ok(self)
+    }
+
+    pub fn eq(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Eq, lhs, rhs)
+    }
+
+    pub fn ne(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Ne, lhs, rhs)
+    }
+
+    pub fn lt(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Lt, lhs, rhs)
+    }
+
+    pub fn le(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Le, lhs, rhs)
+    }
+
+    pub fn gt(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Gt, lhs, rhs)
+    }
 
-        assert!(lhs.shape == rhs.shape);
-        assert!(self.buffer.capacity_elements() as u32 >= lhs.shape.iter().product::<u32>());
+    pub fn ge(&mut self, lhs: &Self, rhs: &Self) -> &mut Self {
+        self.compare(CompareOp::Ge, lhs, rhs)
+    }
+
+    pub fn eq_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Eq, lhs, scalar)
+    }
+
+    pub fn ne_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Ne, lhs, scalar)
+    }
+
+    pub fn lt_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Lt, lhs, scalar)
+    }
+
+    pub fn le_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Le, lhs, scalar)
+    }
+
+    pub fn gt_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Gt, lhs, scalar)
+    }
+
+    pub fn ge_scalar<F: Dtyped>(&mut self, lhs: &Self, scalar: F) -> &mut Self {
+        self.compare_scalar(CompareOp::Ge, lhs, scalar)
+    }
+
+    fn prepare_quantized(
+        &mut self,
+        lhs: &Self,
+        rhs: &QuantizedTensor,
+        dtype: Dtype,
+    ) -> Result<(), GpuMatError> {
+        if self.context != lhs.context || self.context != *rhs.context() {
+            return Err(GpuMatError::ContextMismatch);
+        }
+        GpuMatError::check_float(dtype)?;
+        GpuMatError::check_shapes(&lhs.shape, rhs.shape())?;
+        GpuMatError::check_capacity(&lhs.shape, self.capacity() / dtype.size() as BufferAddress)?;
+
+        self.buffer.set_dtype(dtype);
         self.shape.clear();
         self.shape.extend_from_slice(&lhs.shape);
 
-        self.info.set(&UniformTensorInfo::new(&self.shape));
-        let mut encoder = GlobalCommandEncoder::lock();
-        let mut compute_pass = encoder
-            .get()
-            .begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: None,
-                timestamp_writes: None,
-            });
+        self.info
+            .set(&self.context, &UniformTensorInfo::new(&self.shape));
+        Ok(())
+    }
 
-        match self.dtype() {
-            Dtype::F16 => {
-                compute_pass.set_pipeline(&MUL_F16_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &abc_f16_bind_group(
-                        self.info.buffer(),
-                        &lhs.buffer(),
-                        &rhs.buffer(),
-                        &self.buffer(),
-                    ),
-                    &[],
-                );
-            }
-            Dtype::F32 => {
-                compute_pass.set_pipeline(&MUL_F32_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &abc_f32_bind_group(
-                        self.info.buffer(),
-                        &lhs.buffer(),
-                        &rhs.buffer(),
-                        &self.buffer(),
-                    ),
-                    &[],
-                );
-            }
-            Dtype::F64 => {
-                compute_pass.set_pipeline(&MUL_F64_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &abc_f64_bind_group(
-                        self.info.buffer(),
-                        &lhs.buffer(),
-                        &rhs.buffer(),
-                        &self.buffer(),
-                    ),
-                    &[],
-                );
-            }
+    fn prepare_in_place_quantized(&self, by: &QuantizedTensor) -> Result<(), GpuMatError> {
+        if self.context != *by.context() {
+            return Err(GpuMatError::ContextMismatch);
         }
+        GpuMatError::check_float(self.dtype())?;
+        GpuMatError::check_shapes(&self.shape, by.shape())?;
 
-        let workgroup_count = self.capacity_elements().div_ceil(64);
-        compute_pass.dispatch_workgroups(workgroup_count as u32, 1, 1);
+        self.info
+            .set(&self.context, &UniformTensorInfo::new(&self.shape));
+        Ok(())
+    }
 
-        self
+    fn dispatch_quantized(
+        &self,
+        kernel: fn(QuantFormat) -> Kernel,
+        quantized: &QuantizedTensor,
+        operand: Option<&Self>,
+    ) -> Result<(), GpuMatError> {
+        // In place kernels read and write the target, the others write it after the scales.
+        let (first, output) = match operand {
+            Some(lhs) => (lhs.buffer(), Some(self.buffer())),
+            None => (self.buffer(), None),
+        };
+        let mut buffers = vec![first, quantized.data(), quantized.scales()];
+        buffers.extend(output);
+
+        self.dispatch(kernel(quantized.format()), self.dtype(), &buffers)
     }
 
-    pub fn mul_in_place(&mut self, by: &Self) -> &mut Self {
-        assert!(self.dtype() == by.dtype());
-        assert!(self.shape == by.shape);
-
-        self.info.set(&UniformTensorInfo::new(&self.shape));
-        let mut encoder = GlobalCommandEncoder::lock();
-        let mut compute_pass = encoder
-            .get()
-            .begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: None,
-                timestamp_writes: None,
-            });
+    pub fn cast(&mut self, src: &Self, to: Dtype) -> &mut Self {
+        self.try_cast(src, to)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
 
-        match self.dtype() {
-            Dtype::F16 => {
-                compute_pass.set_pipeline(&MUL_IN_PLACE_F16_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &ab_f16_bind_group(self.info.buffer(), &self.buffer(), &by.buffer()),
-                    &[],
-                );
-            }
-            Dtype::F32 => {
-                compute_pass.set_pipeline(&MUL_IN_PLACE_F32_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &ab_f32_bind_group(self.info.buffer(), &self.buffer(), &by.buffer()),
-                    &[],
-                );
-            }
-            Dtype::F64 => {
-                compute_pass.set_pipeline(&MUL_IN_PLACE_F64_PIPELINE);
-                compute_pass.set_bind_group(
-                    0,
-                    &ab_f64_bind_group(self.info.buffer(), &self.buffer(), &by.buffer()),
-                    &[],
-                );
-            }
+    /// Converts the float tensor `src` to the float dtype `to` on the device, rounding to nearest.
+    ///
+    /// Values pass through f32, rounded to odd from f64 so that f16 and bf16 still round once.
+    /// Casting to the same dtype copies the values.
+    pub fn try_cast(&mut self, src: &Self, to: Dtype) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_float(src.dtype())?;
+        GpuMatError::check_float(to)?;
+        self.prepare_unary(src, to)?;
+
+        if src.dtype() == to {
+            self.dispatch(Kernel::Copy, to, &[self.buffer(), src.buffer()])?;
+        } else {
+            self.dispatch(
+                Kernel::Cast(src.dtype()),
+                to,
+                &[src.buffer(), self.buffer()],
+            )?;
         }
 
-        let workgroup_count = self.capacity_elements().div_ceil(64);
-        compute_pass.dispatch_workgroups(workgroup_count as u32, 1, 1);
+        Ok(self)
+    }
 
-        self
+    /// Setter writing this tensor converted to `to`, see [`GpuTensor::cast`].
+    pub fn cast_to(&self, to: Dtype) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            target.try_cast(self, to)?;
+            Ok(())
+        })
+    }
+
+    pub fn unary(&mut self, op: UnaryOp, src: &Self) -> &mut Self {
+        self.try_unary(op, src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `op` applied to every element of `src`, see [`UnaryOp::supports`] for the dtypes
+    /// each op accepts.
+    pub fn try_unary(&mut self, op: UnaryOp, src: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_unary(op, src.dtype())?;
+        self.prepare_unary(src, src.dtype())?;
+        self.dispatch(
+            Kernel::Unary(op),
+            src.dtype(),
+            &[src.buffer(), self.buffer()],
+        )?;
+
+        Ok(self)
+    }
+
+    pub fn unary_in_place(&mut self, op: UnaryOp) -> &mut Self {
+        self.try_unary_in_place(op)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_unary_in_place(&mut self, op: UnaryOp) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_unary(op, self.dtype())?;
+        self.info
+            .set(&self.context, &UniformTensorInfo::new(&self.shape));
+        self.dispatch(Kernel::UnaryInPlace(op), self.dtype(), &[self.buffer()])?;
+
+        Ok(self)
+    }
+
+    unary_methods! {
+        Exp => exp, exp_in_place;
+        Log => log, log_in_place;
+        Log1p => log1p, log1p_in_place;
+        Sqrt => sqrt, sqrt_in_place;
+        Rsqrt => rsqrt, rsqrt_in_place;
+        Neg => neg, neg_in_place;
+        Sin => sin, sin_in_place;
+        Cos => cos, cos_in_place;
+        Tan => tan, tan_in_place;
+        Tanh => tanh, tanh_in_place;
+        Floor => floor, floor_in_place;
+        Ceil => ceil, ceil_in_place;
+        Round => round, round_in_place;
+        Sign => sign, sign_in_place;
+    }
+
+    pub fn abs_in_place(&mut self) -> &mut Self {
+        self.unary_in_place(UnaryOp::Abs)
+    }
+
+    pub fn activation(&mut self, activation: Activation, src: &Self) -> &mut Self {
+        self.try_activation(activation, src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes `activation` applied to every element of `src`, see [`Activation::supports`] for
+    /// the dtypes each activation accepts.
+    pub fn try_activation(
+        &mut self,
+        activation: Activation,
+        src: &Self,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_activation(activation, src.dtype())?;
+        self.prepare_unary(src, src.dtype())?;
+        if let Activation::LeakyRelu(slope) = activation {
+            let slope = vec::scalar_uniform(&self.context, slope, src.dtype())?;
+            self.dispatch(
+                Kernel::LeakyRelu,
+                src.dtype(),
+                &[src.buffer(), &slope, self.buffer()],
+            )?;
+        } else {
+            self.dispatch(
+                Kernel::Activation(activation.into()),
+                src.dtype(),
+                &[src.buffer(), self.buffer()],
+            )?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn activation_in_place(&mut self, activation: Activation) -> &mut Self {
+        self.try_activation_in_place(activation)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_activation_in_place(
+        &mut self,
+        activation: Activation,
+    ) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_activation(activation, self.dtype())?;
+        self.info
+            .set(&self.context, &UniformTensorInfo::new(&self.shape));
+        if let Activation::LeakyRelu(slope) = activation {
+            let slope = vec::scalar_uniform(&self.context, slope, self.dtype())?;
+            self.dispatch(
+                Kernel::LeakyReluInPlace,
+                self.dtype(),
+                &[self.buffer(), &slope],
+            )?;
+        } else {
+            self.dispatch(
+                Kernel::ActivationInPlace(activation.into()),
+                self.dtype(),
+                &[self.buffer()],
+            )?;
+        }
+
+        Ok(self)
+    }
+
+    activation_methods! {
+        Relu => relu, relu_in_place;
+        Gelu => gelu, gelu_in_place;
+        Silu => silu, silu_in_place;
+        Sigmoid => sigmoid, sigmoid_in_place;
+        Softplus => softplus, softplus_in_place;
+    }
+
+    pub fn leaky_relu(&mut self, src: &Self, slope: f32) -> &mut Self {
+        self.activation(Activation::LeakyRelu(slope), src)
+    }
+
+    pub fn leaky_relu_in_place(&mut self, slope: f32) -> &mut Self {
+        self.activation_in_place(Activation::LeakyRelu(slope))
+    }
+
+    pub fn conj(&mut self, src: &Self) -> &mut Self {
+        self.try_conj(src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the complex conjugate of every element of the complex tensor `src`.
+    pub fn try_conj(&mut self, src: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_complex(src.dtype())?;
+        self.prepare_unary(src, src.dtype())?;
+        self.dispatch(Kernel::Conj, src.dtype(), &[src.buffer(), self.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn abs(&mut self, src: &Self) -> &mut Self {
+        self.try_abs(src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the absolute value of every element of `src`. Complex values give their magnitude,
+    /// as their [`Dtype::real`].
+    pub fn try_abs(&mut self, src: &Self) -> Result<&mut Self, GpuMatError> {
+        if !src.dtype().is_complex() {
+            return self.try_unary(UnaryOp::Abs, src);
+        }
+        self.prepare_unary(src, src.dtype().real())?;
+        self.dispatch(Kernel::Abs, src.dtype(), &[src.buffer(), self.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn arg(&mut self, src: &Self) -> &mut Self {
+        self.try_arg(src)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Writes the angle in `(-pi, pi]` of every element of the complex tensor `src`, as its
+    /// [`Dtype::real`]. Angles are computed in f32, even for C128.
+    pub fn try_arg(&mut self, src: &Self) -> Result<&mut Self, GpuMatError> {
+        GpuMatError::check_complex(src.dtype())?;
+        self.prepare_unary(src, src.dtype().real())?;
+        self.dispatch(Kernel::Arg, src.dtype(), &[src.buffer(), self.buffer()])?;
+
+        Ok(self)
+    }
+
+    pub fn dequantize(&mut self, quantized: &QuantizedTensor, dtype: Dtype) -> &mut Self {
+        self.try_dequantize(quantized, dtype)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Expands `quantized` into a regular tensor of the float `dtype`.
+    pub fn try_dequantize(
+        &mut self,
+        quantized: &QuantizedTensor,
+        dtype: Dtype,
+    ) -> Result<&mut Self, GpuMatError> {
+        if self.context != *quantized.context() {
+            return Err(GpuMatError::ContextMismatch);
+        }
+        GpuMatError::check_float(dtype)?;
+        GpuMatError::check_capacity(
+            quantized.shape(),
+            self.capacity() / dtype.size() as BufferAddress,
+        )?;
+
+        self.buffer.set_dtype(dtype);
+        self.shape.clear();
+        self.shape.extend_from_slice(quantized.shape());
+        self.info
+            .set(&self.context, &UniformTensorInfo::new(&self.shape));
+
+        self.dispatch(
+            Kernel::Dequantize(quantized.format()),
+            dtype,
+            &[quantized.data(), quantized.scales(), self.buffer()],
+        )?;
+
+        Ok(self)
+    }
+
+    pub fn add_quantized(&mut self, lhs: &Self, rhs: &QuantizedTensor) -> &mut Self {
+        self.try_add_quantized(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Like [`GpuTensor::try_add`], dequantizing `rhs` on the fly.
+    pub fn try_add_quantized(
+        &mut self,
+        lhs: &Self,
+        rhs: &QuantizedTensor,
+    ) -> Result<&mut Self, GpuMatError> {
+        self.prepare_quantized(lhs, rhs, lhs.dtype())?;
+        self.dispatch_quantized(Kernel::AddQuantized, rhs, Some(lhs))?;
+
+        Ok(self)
+    }
+
+    pub fn increment_quantized(&mut self, by: &QuantizedTensor) -> &mut Self {
+        self.try_increment_quantized(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_increment_quantized(
+        &mut self,
+        by: &QuantizedTensor,
+    ) -> Result<&mut Self, GpuMatError> {
+        self.prepare_in_place_quantized(by)?;
+        self.dispatch_quantized(Kernel::IncrementQuantized, by, None)?;
+
+        Ok(self)
+    }
+
+    pub fn mul_quantized(&mut self, lhs: &Self, rhs: &QuantizedTensor) -> &mut Self {
+        self.try_mul_quantized(lhs, rhs)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    /// Like [`GpuTensor::try_mul`], dequantizing `rhs` on the fly.
+    pub fn try_mul_quantized(
+        &mut self,
+        lhs: &Self,
+        rhs: &QuantizedTensor,
+    ) -> Result<&mut Self, GpuMatError> {
+        self.prepare_quantized(lhs, rhs, lhs.dtype())?;
+        self.dispatch_quantized(Kernel::MulQuantized, rhs, Some(lhs))?;
+
+        Ok(self)
+    }
+
+    pub fn mul_in_place_quantized(&mut self, by: &QuantizedTensor) -> &mut Self {
+        self.try_mul_in_place_quantized(by)
+            .unwrap_or_else(|err| panic!("GpuMat: {err}"))
+    }
+
+    pub fn try_mul_in_place_quantized(
+        &mut self,
+        by: &QuantizedTensor,
+    ) -> Result<&mut Self, GpuMatError> {
+        self.prepare_in_place_quantized(by)?;
+        self.dispatch_quantized(Kernel::MulInPlaceQuantized, by, None)?;
+
+        Ok(self)
     }
 }
 
-pub struct GpuTensorSetterFn<Fn: FnOnce(&mut GpuTensor)>(Fn);
+pub struct GpuTensorSetterFn<Fn: SetterFn>(Fn);
+
+pub trait SetterFn: FnOnce(&mut GpuTensor) -> Result<(), GpuMatError> {}
+
+impl<Fn: FnOnce(&mut GpuTensor) -> Result<(), GpuMatError>> SetterFn for Fn {}
+
+impl<Fn: SetterFn> GpuTensorSetterFn<Fn> {
+    pub fn save_intermediate(self, name: &'static str) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_save_intermediate(name)?;
+            Ok(())
+        })
+    }
+
+    /// Applies `activation` in place once the expression is written, see
+    /// [`GpuTensor::activation`].
+    pub fn activation(self, activation: Activation) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_activation_in_place(activation)?;
+            Ok(())
+        })
+    }
+
+    pub fn relu(self) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::Relu)
+    }
+
+    pub fn leaky_relu(self, slope: f32) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::LeakyRelu(slope))
+    }
+
+    pub fn gelu(self) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::Gelu)
+    }
+
+    pub fn silu(self) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::Silu)
+    }
+
+    pub fn sigmoid(self) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::Sigmoid)
+    }
 
-impl<Fn: FnOnce(&mut GpuTensor)> GpuTensorSetterFn<Fn> {
-    pub fn save_intermediate(
-        self,
-        name: &'static str,
-    ) -> GpuTensorSetterFn<impl FnOnce(&mut GpuTensor)> {
-        GpuTensorSetterFn(move |target| {
-            self.0(target);
+    pub fn softplus(self) -> GpuTensorSetterFn<impl SetterFn> {
+        self.activation(Activation::Softplus)
+    }
+
+    /// Keeps the smaller of the expression and `by`, see [`GpuTensor::minimum`].
+    pub fn minimum(self, by: &GpuTensor) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
 
-            target.save_intermediate(name);
+            target.try_minimum_in_place(by)?;
+            Ok(())
+        })
+    }
+
+    pub fn maximum(self, by: &GpuTensor) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_maximum_in_place(by)?;
+            Ok(())
+        })
+    }
+
+    /// Clamps the expression once written, see [`GpuTensor::clamp`].
+    pub fn clamp(self, lo: &GpuTensor, hi: &GpuTensor) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_clamp_in_place(lo, hi)?;
+            Ok(())
+        })
+    }
+
+    pub fn clamp_scalar<F: Dtyped>(self, lo: F, hi: F) -> GpuTensorSetterFn<impl SetterFn> {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_clamp_scalar_in_place(lo, hi)?;
+            Ok(())
         })
     }
 }
 
 impl core::ops::Add for &GpuTensor {
-    type Output = GpuTensorSetterFn<impl FnOnce(&mut GpuTensor)>;
+    type Output = GpuTensorSetterFn<impl SetterFn>;
     fn add(self, rhs: Self) -> Self::Output {
-        GpuTensorSetterFn(|target| {
-            target.add(self, rhs);
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_add(self, rhs)?;
+            Ok(())
         })
     }
 }
 
-impl<'a, Fn: FnOnce(&mut GpuTensor) + 'a> core::ops::Add<&'a GpuTensor> for GpuTensorSetterFn<Fn> {
-    type Output = GpuTensorSetterFn<impl FnOnce(&mut GpuTensor) + 'a>;
+impl<'a, Fn: SetterFn + 'a> core::ops::Add<&'a GpuTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
 
     fn add(self, rhs: &'a GpuTensor) -> Self::Output {
-        GpuTensorSetterFn(move |target| {
-            self.0(target);
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
 
-            target.increment(rhs);
+            target.try_increment(rhs)?;
+            Ok(())
         })
     }
 }
 
-impl<'a, Fn: FnOnce(&mut GpuTensor) + 'a> core::ops::Add<GpuTensorSetterFn<Fn>> for &'a GpuTensor {
-    type Output = GpuTensorSetterFn<impl FnOnce(&mut GpuTensor)>;
+impl<'a, Fn: SetterFn + 'a> core::ops::Add<GpuTensorSetterFn<Fn>> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
 
     fn add(self, rhs: GpuTensorSetterFn<Fn>) -> Self::Output {
         rhs + self
@@ -376,30 +1485,198 @@ impl<'a, Fn: FnOnce(&mut GpuTensor) + 'a> core::ops::Add<GpuTensorSetterFn<Fn>>
 }
 
 impl core::ops::Mul for &GpuTensor {
-    type Output = GpuTensorSetterFn<impl FnOnce(&mut GpuTensor)>;
+    type Output = GpuTensorSetterFn<impl SetterFn>;
     fn mul(self, rhs: Self) -> Self::Output {
-        GpuTensorSetterFn(|target| {
-            target.mul(self, rhs);
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_mul(self, rhs)?;
+            Ok(())
         })
     }
 }
 
-impl<'a, Fn: FnOnce(&mut GpuTensor) + 'a> core::ops::Mul<&'a GpuTensor> for GpuTensorSetterFn<Fn> {
-    type Output = GpuTensorSetterFn<impl FnOnce(&mut GpuTensor) + 'a>;
+impl<'a, Fn: SetterFn + 'a> core::ops::Mul<&'a GpuTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
 
     fn mul(self, rhs: &'a GpuTensor) -> Self::Output {
-        GpuTensorSetterFn(move |target| {
-            self.0(target);
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
 
-            target.mul_in_place(rhs);
+            target.try_mul_in_place(rhs)?;
+            Ok(())
         })
     }
 }
 
-impl<'a, Fn: FnOnce(&mut GpuTensor) + 'a> core::ops::Mul<GpuTensorSetterFn<Fn>> for &'a GpuTensor {
-    type Output = GpuTensorSetterFn<impl FnOnce(&mut GpuTensor)>;
+impl<'a, Fn: SetterFn + 'a> core::ops::Mul<GpuTensorSetterFn<Fn>> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
 
     fn mul(self, rhs: GpuTensorSetterFn<Fn>) -> Self::Output {
-        rhs + self
+        rhs * self
+    }
+}
+
+impl core::ops::Sub for &GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+    fn sub(self, rhs: Self) -> Self::Output {
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_sub(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Sub<&'a GpuTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn sub(self, rhs: &'a GpuTensor) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_decrement(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Sub<GpuTensorSetterFn<Fn>> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn sub(self, rhs: GpuTensorSetterFn<Fn>) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            rhs.0(target)?;
+
+            target.try_rsub_in_place(self)?;
+            Ok(())
+        })
+    }
+}
+
+impl core::ops::Div for &GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+    fn div(self, rhs: Self) -> Self::Output {
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_div(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Div<&'a GpuTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn div(self, rhs: &'a GpuTensor) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_div_in_place(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Div<GpuTensorSetterFn<Fn>> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn div(self, rhs: GpuTensorSetterFn<Fn>) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            rhs.0(target)?;
+
+            target.try_rdiv_in_place(self)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped> core::ops::Add<F> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn add(self, rhs: F) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            target.try_add_scalar(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<Fn: SetterFn, F: Dtyped> core::ops::Add<F> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+
+    fn add(self, rhs: F) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_shift(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, F: Dtyped> core::ops::Mul<F> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn mul(self, rhs: F) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            target.try_mul_scalar(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<Fn: SetterFn, F: Dtyped> core::ops::Mul<F> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+
+    fn mul(self, rhs: F) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_scale(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a> core::ops::Add<&'a QuantizedTensor> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+    fn add(self, rhs: &'a QuantizedTensor) -> Self::Output {
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_add_quantized(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Add<&'a QuantizedTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn add(self, rhs: &'a QuantizedTensor) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_increment_quantized(rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a> core::ops::Mul<&'a QuantizedTensor> for &'a GpuTensor {
+    type Output = GpuTensorSetterFn<impl SetterFn>;
+    fn mul(self, rhs: &'a QuantizedTensor) -> Self::Output {
+        GpuTensorSetterFn(|target: &mut GpuTensor| {
+            target.try_mul_quantized(self, rhs)?;
+            Ok(())
+        })
+    }
+}
+
+impl<'a, Fn: SetterFn + 'a> core::ops::Mul<&'a QuantizedTensor> for GpuTensorSetterFn<Fn> {
+    type Output = GpuTensorSetterFn<impl SetterFn + 'a>;
+
+    fn mul(self, rhs: &'a QuantizedTensor) -> Self::Output {
+        GpuTensorSetterFn(move |target: &mut GpuTensor| {
+            self.0(target)?;
+
+            target.try_mul_in_place_quantized(rhs)?;
+            Ok(())
+        })
     }
 }
@@ -1,6 +1,6 @@
 use wgpu::{Buffer, BufferAddress, BufferDescriptor};
 
-use super::globals::DEVICE_QUEUE;
+use super::{context::GpuContext, error::GpuMatError};
 
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
@@ -12,7 +12,7 @@ pub struct UniformTensorInfo {
 }
 
 impl UniformTensorInfo {
-    pub fn new(shape: &Vec<u32>) -> Self {
+    pub fn new(shape: &[u32]) -> Self {
         let mut shape_arr = [1; 8];
         shape_arr[..shape.len()].copy_from_slice(shape);
         Self {
@@ -24,25 +24,61 @@ impl UniformTensorInfo {
     }
 }
 
+/// Strides in elements of the two operands of a mixed kernel and of the mask of a select, over
+/// the shape of its output. They are zero along the dimensions an operand is broadcast over,
+/// including those it lacks, so kernels without a mask see it as a 0-d one.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct UniformBroadcast {
+    pub a: [u32; 8],
+    pub b: [u32; 8],
+    pub mask: [u32; 8],
+}
+
+impl UniformBroadcast {
+    pub fn new(shape: &[u32], a: &[u32], b: &[u32], mask: &[u32]) -> Self {
+        Self {
+            a: broadcast_strides(shape, a),
+            b: broadcast_strides(shape, b),
+            mask: broadcast_strides(shape, mask),
+        }
+    }
+}
+
+fn broadcast_strides(shape: &[u32], operand: &[u32]) -> [u32; 8] {
+    let mut strides = [0; 8];
+    let offset = shape.len() - operand.len();
+    let mut stride = 1;
+    for (axis, &size) in operand.iter().enumerate().rev() {
+        if size != 1 {
+            strides[offset + axis] = stride;
+        }
+        stride *= size;
+    }
+    strides
+}
+
 pub struct TensorInfo {
     buffer: Buffer,
 }
 
 impl TensorInfo {
-    pub fn new() -> Self {
-        Self {
-            buffer: DEVICE_QUEUE.0.create_buffer(&BufferDescriptor {
-                label: None,
-                size: core::mem::size_of::<UniformTensorInfo>() as BufferAddress,
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            }),
-        }
+    pub fn new(context: &GpuContext) -> Result<Self, GpuMatError> {
+        Ok(Self {
+            buffer: context.scoped(|| {
+                context.device().create_buffer(&BufferDescriptor {
+                    label: None,
+                    size: core::mem::size_of::<UniformTensorInfo>() as BufferAddress,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            })?,
+        })
     }
 
-    pub fn set(&self, info: &UniformTensorInfo) {
-        DEVICE_QUEUE
-            .1
+    pub fn set(&self, context: &GpuContext, info: &UniformTensorInfo) {
+        context
+            .queue()
             .write_buffer(&self.buffer, 0, bytemuck::bytes_of(info));
     }
 
@@ -1,8 +1,11 @@
+use std::borrow::Cow;
 use wgpu::{Buffer, BufferAddress, util::DeviceExt};
 
 use super::{
-    dtype::{Dtype, Dtyped},
-    globals::DEVICE_QUEUE,
+    context::GpuContext,
+    df64,
+    dtype::{Dtype, DtypeVec, Dtyped},
+    error::GpuMatError,
 };
 
 pub struct GpuVec {
@@ -11,31 +14,44 @@ pub struct GpuVec {
 }
 
 impl GpuVec {
-    pub fn new_init<F: Dtyped>(value: &[F]) -> Self {
-        let buffer = DEVICE_QUEUE
-            .0
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(value),
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-            });
-        Self {
+    pub fn new_init<F: Dtyped>(context: &GpuContext, value: &[F]) -> Result<Self, GpuMatError> {
+        let contents = contents(context, value);
+        let buffer = context.scoped(|| {
+            context
+                .device()
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: None,
+                    contents: &contents,
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                })
+        })?;
+        Ok(Self {
             buffer,
             dtype: F::dtype(),
-        }
+        })
     }
 
-    pub fn new_uninit<F: Dtyped>(size: BufferAddress) -> Self {
-        let buffer = DEVICE_QUEUE.0.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-        Self {
+    pub fn new_uninit<F: Dtyped>(
+        context: &GpuContext,
+        size: BufferAddress,
+    ) -> Result<Self, GpuMatError> {
+        let buffer = context.scoped(|| {
+            context.device().create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                // Whole words, so packed dtypes and buffer copies never run past the end.
+                size: size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        })?;
+        Ok(Self {
             buffer,
             dtype: F::dtype(),
-        }
+        })
+    }
+
+    pub fn from_buffer(buffer: Buffer, dtype: Dtype) -> Self {
+        Self { buffer, dtype }
     }
 
     pub fn capacity(&self) -> BufferAddress {
@@ -58,3 +74,54 @@ impl GpuVec {
         &self.buffer
     }
 }
+
+/// The bytes of `values` as the device of `context` stores them.
+fn contents<'a, F: Dtyped>(context: &GpuContext, values: &'a [F]) -> Cow<'a, [u8]> {
+    match F::dtype() {
+        Dtype::F64 | Dtype::C128 if context.emulates_f64() => {
+            Cow::Owned(bytemuck::cast_slice(&df64::encode(bytemuck::cast_slice(values))).to_vec())
+        }
+        _ => Cow::Borrowed(bytemuck::cast_slice(values)),
+    }
+}
+
+/// A uniform buffer holding `value` as the first element of a storage word, padded to the 16
+/// bytes of the largest word.
+pub fn uniform<F: Dtyped>(context: &GpuContext, value: F) -> Result<Buffer, GpuMatError> {
+    let mut contents = contents(context, &[value]).into_owned();
+    contents.resize(16, 0);
+    uniform_buffer(context, &contents)
+}
+
+/// Like [`uniform`] with `value` converted to `dtype`, as scalar operands are.
+pub fn scalar_uniform<F: Dtyped>(
+    context: &GpuContext,
+    value: F,
+    dtype: Dtype,
+) -> Result<Buffer, GpuMatError> {
+    match DtypeVec::scalar(value).cast(dtype) {
+        DtypeVec::F16(values) => uniform(context, values[[]]),
+        DtypeVec::BF16(values) => uniform(context, values[[]]),
+        DtypeVec::F32(values) => uniform(context, values[[]]),
+        DtypeVec::F64(values) => uniform(context, values[[]]),
+        DtypeVec::I32(values) => uniform(context, values[[]]),
+        DtypeVec::U32(values) => uniform(context, values[[]]),
+        DtypeVec::C64(values) => uniform(context, values[[]]),
+        DtypeVec::C128(values) => uniform(context, values[[]]),
+        DtypeVec::Bool(_) => unreachable!("scalars are numeric"),
+    }
+}
+
+/// A uniform buffer holding `contents`. Each dispatch gets its own, as writes to a shared one
+/// would all land before the commands recorded so far are submitted.
+pub fn uniform_buffer(context: &GpuContext, contents: &[u8]) -> Result<Buffer, GpuMatError> {
+    context.scoped(|| {
+        context
+            .device()
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents,
+                usage: wgpu::BufferUsages::UNIFORM,
+            })
+    })
+}
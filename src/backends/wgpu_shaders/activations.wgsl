@@ -0,0 +1,13 @@
+fn sigmoid(x: f32) -> f32 {
+    return 1.0 / (1.0 + exp(-x));
+}
+
+fn gelu(x: f32) -> f32 {
+    // tanh saturates long before 10, where some implementations overflow to NaN.
+    let t = clamp(0.7978845608 * (x + 0.044715 * x * x * x), -10.0, 10.0);
+    return 0.5 * x * (1.0 + tanh(t));
+}
+
+fn softplus(x: f32) -> f32 {
+    return max(x, 0.0) + log1p(exp(-abs(x)));
+}
@@ -1,29 +0,0 @@
-enable f16;
-
-struct TensorInfo {
-    shape: array<vec4<u32>, 2>,
-    rank: u32,
-    length: u32,
-}
-
-@group(0) @binding(0)
-var<uniform> tensor_info: TensorInfo;
-
-@group(0) @binding(1)
-var<storage, read> a: array<f16>;
-@group(0) @binding(2)
-var<storage, read> b: array<f16>;
-
-@group(0) @binding(3)
-var<storage, read_write> output: array<f16>;
-
-@compute @workgroup_size(64)
-fn add(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let idx = global_id.x;
-
-    if (idx >= tensor_info.length) {
-        return;
-    }
-
-    output[idx] = a[idx] + b[idx];
-}
@@ -0,0 +1,34 @@
+struct TensorInfo {
+    shape: array<vec4<u32>, 2>,
+    rank: u32,
+    length: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> tensor_info: TensorInfo;
+
+@group(0) @binding(1)
+var<storage, read> a: array<S>;
+
+// The scalar is the first element of its word.
+@group(0) @binding(2)
+var<uniform> scalar: S;
+
+@group(0) @binding(3)
+var<storage, read_write> output: array<S>;
+
+@compute @workgroup_size(64)
+fn binary_scalar(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let idx = global_id.x;
+
+    if (idx * LANES >= tensor_info.length) {
+        return;
+    }
+
+    let b = unpack(scalar)[0];
+    var x = unpack(a[idx]);
+    for (var lane = 0u; lane < LANES; lane++) {
+        x[lane] = e_op(x[lane], b);
+    }
+    output[idx] = pack(x);
+}
@@ -0,0 +1,20 @@
+// Strides of the operands of a mixed kernel in elements, see `UniformBroadcast`.
+struct Broadcast {
+    a: array<vec4<u32>, 2>,
+    b: array<vec4<u32>, 2>,
+    mask: array<vec4<u32>, 2>,
+}
+
+// The index in an operand with the given strides of the output element `element`.
+fn broadcast_index(element: u32, operand_strides: array<vec4<u32>, 2>) -> u32 {
+    // Arrays are only indexed dynamically through references.
+    var strides = operand_strides;
+    var rest = element;
+    var index = 0u;
+    for (var axis = tensor_info.rank; axis > 0u; axis--) {
+        let size = tensor_info.shape[(axis - 1u) / 4u][(axis - 1u) % 4u];
+        index += rest % size * strides[(axis - 1u) / 4u][(axis - 1u) % 4u];
+        rest /= size;
+    }
+    return index;
+}
@@ -0,0 +1,29 @@
+struct TensorInfo {
+    shape: array<vec4<u32>, 2>,
+    rank: u32,
+    length: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> tensor_info: TensorInfo;
+
+@group(0) @binding(1)
+var<storage, read> src: array<SRC>;
+
+@group(0) @binding(2)
+var<storage, read_write> output: array<S>;
+
+@compute @workgroup_size(64)
+fn cast_to(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let idx = global_id.x;
+
+    if (idx * LANES >= tensor_info.length) {
+        return;
+    }
+
+    var x: array<E, LANES>;
+    for (var lane = 0u; lane < LANES; lane++) {
+        x[lane] = from_f32(load(min(idx * LANES + lane, tensor_info.length - 1u)));
+    }
+    output[idx] = pack(x);
+}
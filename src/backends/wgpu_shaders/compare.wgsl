@@ -0,0 +1,34 @@
+struct TensorInfo {
+    shape: array<vec4<u32>, 2>,
+    rank: u32,
+    length: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> tensor_info: TensorInfo;
+
+@group(0) @binding(1)
+var<storage, read> a: array<S>;
+@group(0) @binding(2)
+var<storage, read> b: array<S>;
+
+@group(0) @binding(3)
+var<storage, read_write> output: array<u32>;
+
+@compute @workgroup_size(64)
+fn compare(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let idx = global_id.x;
+
+    if (idx * LANES >= tensor_info.length) {
+        return;
+    }
+
+    var x = unpack(a[idx]);
+    var y = unpack(b[idx]);
+    for (var lane = 0u; lane < LANES; lane++) {
+        let element = idx * LANES + lane;
+        if (element < tensor_info.length) {
+            output[element] = u32(cmp(x[lane], y[lane]));
+        }
+    }
+}
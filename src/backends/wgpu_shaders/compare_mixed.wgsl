@@ -0,0 +1,38 @@
+struct TensorInfo {
+    shape: array<vec4<u32>, 2>,
+    rank: u32,
+    length: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> tensor_info: TensorInfo;
+
+@group(0) @binding(1)
+var<storage, read> a: array<A>;
+@group(0) @binding(2)
+var<storage, read> b: array<B>;
+
+@group(0) @binding(3)
+var<storage, read_write> output: array<u32>;
+
+@group(0) @binding(4)
+var<uniform> broadcast: Broadcast;
+
+@compute @workgroup_size(64)
+fn compare_mixed(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let idx = global_id.x;
+
+    if (idx * LANES >= tensor_info.length) {
+        return;
+    }
+
+    for (var lane = 0u; lane < LANES; lane++) {
+        let element = idx * LANES + lane;
+        if (element < tensor_info.length) {
+            output[element] = u32(cmp(
+                load_a(broadcast_index(element, broadcast.a)),
+                load_b(broadcast_index(element, broadcast.b)),
+            ));
+        }
+    }
+}
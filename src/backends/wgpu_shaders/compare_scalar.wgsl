@@ -0,0 +1,36 @@
+struct TensorInfo {
+    shape: array<vec4<u32>, 2>,
+    rank: u32,
+    length: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> tensor_info: TensorInfo;
+
+@group(0) @binding(1)
+var<storage, read> a: array<S>;
+
+// The scalar is the first element of its word.
+@group(0) @binding(2)
+var<uniform> scalar: S;
+
+@group(0) @binding(3)
+var<storage, read_write> output: array<u32>;
+
+@compute @workgroup_size(64)
+fn compare_scalar(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let idx = global_id.x;
+
+    if (idx * LANES >= tensor_info.length) {
+        return;
+    }
+
+    let y = unpack(scalar)[0];
+    var x = unpack(a[idx]);
+    for (var lane = 0u; lane < LANES; lane++) {
+        let element = idx * LANES + lane;
+        if (element < tensor_info.length) {
+            output[element] = u32(cmp(x[lane], y));
+        }
+    }
+}
@@ -8,20 +8,18 @@ struct TensorInfo {
 var<uniform> tensor_info: TensorInfo;
 
 @group(0) @binding(1)
-var<storage, read> a: array<f32>;
-@group(0) @binding(2)
-var<storage, read> b: array<f32>;
+var<storage, read> a: array<E>;
 
-@group(0) @binding(3)
-var<storage, read_write> output: array<f32>;
+@group(0) @binding(2)
+var<storage, read_write> output: array<R>;
 
 @compute @workgroup_size(64)
-fn mul(@builtin(global_invocation_id) global_id: vec3<u32>) {
+fn complex_part(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let idx = global_id.x;
 
     if (idx >= tensor_info.length) {
         return;
     }
 
-    output[idx] = a[idx] * b[idx];
+    output[idx] = part(a[idx]);
 }
@@ -8,19 +8,18 @@ struct TensorInfo {
 var<uniform> tensor_info: TensorInfo;
 
 @group(0) @binding(1)
-var<storage, read_write> a: array<f32>;
+var<storage, read_write> a: array<S>;
 
 @group(0) @binding(2)
-var<storage, read> b: array<f32>;
+var<storage, read> b: array<S>;
 
 @compute @workgroup_size(64)
-fn add(@builtin(global_invocation_id) global_id: vec3<u32>) {
+fn copy(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let idx = global_id.x;
 
-    let array_length = arrayLength(&a);
-    if (idx >= array_length) {
+    if (idx * LANES >= tensor_info.length) {
         return;
     }
 
-    a[idx] *= b[idx];
+    a[idx] = b[idx];
 }
@@ -0,0 +1,22 @@
+// Two bfloat16 values per u32 word, low half first, computed as f32.
+alias S = u32;
+alias E = f32;
+
+const LANES: u32 = 2u;
+
+fn unpack(word: S) -> array<E, LANES> {
+    return array<E, LANES>(bitcast<f32>(word << 16u), bitcast<f32>(word & 0xffff0000u));
+}
+
+// Rounds to nearest even, like `half::bf16::from_f32`.
+fn pack_bf16(value: f32) -> u32 {
+    let bits = bitcast<u32>(value);
+    if ((bits & 0x7fffffffu) > 0x7f800000u) {
+        return (bits >> 16u) | 0x40u;
+    }
+    return (bits + 0x7fffu + ((bits >> 16u) & 1u)) >> 16u;
+}
+
+fn pack(elements: array<E, LANES>) -> S {
+    return pack_bf16(elements[0]) | (pack_bf16(elements[1]) << 16u);
+}
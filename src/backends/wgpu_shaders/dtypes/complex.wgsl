@@ -0,0 +1,41 @@
+// Element operations for complex elements `E`, built on the real operations of their parts.
+
+fn e_add(a: E, b: E) -> E {
+    return complex(r_add(re(a), re(b)), r_add(im(a), im(b)));
+}
+
+fn e_sub(a: E, b: E) -> E {
+    return complex(r_sub(re(a), re(b)), r_sub(im(a), im(b)));
+}
+
+fn e_mul(a: E, b: E) -> E {
+    return complex(
+        r_sub(r_mul(re(a), re(b)), r_mul(im(a), im(b))),
+        r_add(r_mul(re(a), im(b)), r_mul(im(a), re(b))),
+    );
+}
+
+// Multiplies by the conjugate of `b` over its squared magnitude, like `num_complex`.
+fn e_div(a: E, b: E) -> E {
+    let norm = r_add(r_mul(re(b), re(b)), r_mul(im(b), im(b)));
+    return complex(
+        r_div(r_add(r_mul(re(a), re(b)), r_mul(im(a), im(b))), norm),
+        r_div(r_sub(r_mul(im(a), re(b)), r_mul(re(a), im(b))), norm),
+    );
+}
+
+fn from_f32(a: f32) -> E {
+    return complex(r_from_f32(a), r_from_f32(0.0));
+}
+
+fn e_conj(a: E) -> E {
+    return complex(re(a), r_neg(im(a)));
+}
+
+fn e_abs(a: E) -> R {
+    return r_hypot(re(a), im(a));
+}
+
+fn e_arg(a: E) -> R {
+    return r_atan2(im(a), re(a));
+}
@@ -0,0 +1,94 @@
+// f64 emulated as an unevaluated sum of two f32 (hi, lo) with |lo| <= ulp(hi) / 2, on devices
+// without shader f64. Algorithms from Joldes, Muller and Popescu, "Tight and rigorous error
+// bounds for basic building blocks of double-word arithmetic", 2017.
+
+// Hides a value from the shader compiler, which may otherwise simplify `(a + b) - a` to `b` and
+// drop exactly the rounding errors these algorithms recover. Ranks never exceed 8.
+fn keep(x: f32) -> f32 {
+    return select(x, 0.0, tensor_info.rank > 8u);
+}
+
+fn two_sum(a: f32, b: f32) -> vec2<f32> {
+    let s = keep(a + b);
+    let bb = keep(s - a);
+    return vec2<f32>(s, (a - keep(s - bb)) + (b - bb));
+}
+
+fn fast_two_sum(a: f32, b: f32) -> vec2<f32> {
+    let s = keep(a + b);
+    return vec2<f32>(s, b - keep(s - a));
+}
+
+// Veltkamp splitting into two 12 bit halves, exact below about 1e34.
+fn split(a: f32) -> vec2<f32> {
+    let c = keep(4097.0 * a);
+    let hi = keep(c - keep(c - a));
+    return vec2<f32>(hi, a - hi);
+}
+
+// Dekker's product, exact without relying on fma.
+fn two_prod(a: f32, b: f32) -> vec2<f32> {
+    let p = a * b;
+    let x = split(a);
+    let y = split(b);
+    return vec2<f32>(p, ((x.x * y.x - p) + x.x * y.y + x.y * y.x) + x.y * y.y);
+}
+
+// AccurateDWPlusDW, relative error below 3 * 2^-48.
+fn df64_add(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    let s = two_sum(a.x, b.x);
+    let t = two_sum(a.y, b.y);
+    let v = fast_two_sum(s.x, s.y + t.x);
+    return fast_two_sum(v.x, t.y + v.y);
+}
+
+// DWTimesDW1, relative error below 7 * 2^-48.
+fn df64_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    let c = two_prod(a.x, b.x);
+    return fast_two_sum(c.x, c.y + (a.x * b.y + a.y * b.x));
+}
+
+// DWDivDW2, a correction of the f32 quotient, relative error below 15 * 2^-48.
+fn df64_div(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    let t = a.x / b.x;
+    let r = df64_mul(b, vec2<f32>(t, 0.0));
+    let d = df64_add(a, -r);
+    return fast_two_sum(t, d.x / b.x);
+}
+
+// One Newton step from the f32 square root, which doubles its precision.
+fn df64_sqrt(a: vec2<f32>) -> vec2<f32> {
+    if (a.x <= 0.0) {
+        return vec2<f32>(sqrt(a.x), 0.0);
+    }
+    let s = sqrt(a.x);
+    let r = df64_add(a, -two_prod(s, s));
+    return fast_two_sum(s, r.x / (2.0 * s));
+}
+
+// Exact, the low part only matters once the high part is an integer.
+fn df64_floor(a: vec2<f32>) -> vec2<f32> {
+    let hi = floor(a.x);
+    if (hi != a.x) {
+        return vec2<f32>(hi, 0.0);
+    }
+    return fast_two_sum(hi, floor(a.y));
+}
+
+// Rounds half to even like WGSL `round`. Integers are odd when exactly one of their parts is.
+fn df64_round(a: vec2<f32>) -> vec2<f32> {
+    let r = df64_floor(df64_add(a, vec2<f32>(0.5, 0.0)));
+    let odd = (fract(r.x * 0.5) != 0.0) != (fract(r.y * 0.5) != 0.0);
+    if (odd && df64_eq(df64_add(r, -a), vec2<f32>(0.5, 0.0))) {
+        return df64_add(r, vec2<f32>(-1.0, 0.0));
+    }
+    return r;
+}
+
+fn df64_eq(a: vec2<f32>, b: vec2<f32>) -> bool {
+    return a.x == b.x && a.y == b.y;
+}
+
+fn df64_lt(a: vec2<f32>, b: vec2<f32>) -> bool {
+    return a.x < b.x || (a.x == b.x && a.y < b.y);
+}
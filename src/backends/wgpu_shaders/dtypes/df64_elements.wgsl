@@ -0,0 +1,33 @@
+// Element operations for df64 elements `E`.
+
+fn e_add(a: E, b: E) -> E {
+    return df64_add(a, b);
+}
+
+fn e_sub(a: E, b: E) -> E {
+    return df64_add(a, -b);
+}
+
+fn e_mul(a: E, b: E) -> E {
+    return df64_mul(a, b);
+}
+
+fn e_div(a: E, b: E) -> E {
+    return df64_div(a, b);
+}
+
+fn e_eq(a: E, b: E) -> bool {
+    return df64_eq(a, b);
+}
+
+fn e_lt(a: E, b: E) -> bool {
+    return df64_lt(a, b);
+}
+
+fn to_f32(a: E) -> f32 {
+    return a.x + a.y;
+}
+
+fn from_f32(a: f32) -> E {
+    return vec2<f32>(a, 0.0);
+}
@@ -0,0 +1,14 @@
+// Two f16 values per u32 word, low half first, computed as f32 on devices without shader f16.
+alias S = u32;
+alias E = f32;
+
+const LANES: u32 = 2u;
+
+fn unpack(word: S) -> array<E, LANES> {
+    let elements = unpack2x16float(word);
+    return array<E, LANES>(elements.x, elements.y);
+}
+
+fn pack(elements: array<E, LANES>) -> S {
+    return pack2x16float(vec2<f32>(elements[0], elements[1]));
+}
@@ -0,0 +1,13 @@
+// One element of the native type `T` per storage word.
+alias S = T;
+alias E = T;
+
+const LANES: u32 = 1u;
+
+fn unpack(word: S) -> array<E, LANES> {
+    return array<E, LANES>(word);
+}
+
+fn pack(elements: array<E, LANES>) -> S {
+    return elements[0];
+}
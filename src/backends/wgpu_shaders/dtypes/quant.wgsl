@@ -0,0 +1,11 @@
+// Symmetric blocks of BLOCK_SIZE signed BITS-wide integers, PER_WORD to a u32, sharing a scale.
+// Kernels including this declare the `q` words and the `scales`.
+
+fn dequant(element: u32) -> f32 {
+    let block = element / BLOCK_SIZE;
+    let scale = scale_of(scales[block / SCALES_PER_WORD], block % SCALES_PER_WORD);
+
+    let raw = q[element / PER_WORD] >> ((element % PER_WORD) * BITS);
+    let value = bitcast<i32>(raw << (32u - BITS)) >> (32u - BITS);
+    return f32(value) * scale;
+}
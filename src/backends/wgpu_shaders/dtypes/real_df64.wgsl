@@ -0,0 +1,47 @@
+// Real operations on df64 values, complex values are `vec4<f32>` (re hi, re lo, im hi, im lo).
+alias R = vec2<f32>;
+
+fn re(a: vec4<f32>) -> R {
+    return a.xy;
+}
+
+fn im(a: vec4<f32>) -> R {
+    return a.zw;
+}
+
+fn complex(x: R, y: R) -> vec4<f32> {
+    return vec4<f32>(x, y);
+}
+
+fn r_from_f32(a: f32) -> R {
+    return vec2<f32>(a, 0.0);
+}
+
+fn r_add(a: R, b: R) -> R {
+    return df64_add(a, b);
+}
+
+fn r_sub(a: R, b: R) -> R {
+    return df64_add(a, -b);
+}
+
+fn r_mul(a: R, b: R) -> R {
+    return df64_mul(a, b);
+}
+
+fn r_div(a: R, b: R) -> R {
+    return df64_div(a, b);
+}
+
+fn r_neg(a: R) -> R {
+    return -a;
+}
+
+fn r_hypot(x: R, y: R) -> R {
+    return df64_sqrt(df64_add(df64_mul(x, x), df64_mul(y, y)));
+}
+
+// Angles only have f32 precision.
+fn r_atan2(y: R, x: R) -> R {
+    return vec2<f32>(atan2(y.x + y.y, x.x + x.y), 0.0);
+}
@@ -0,0 +1,52 @@
+// Real operations on the native float type `R`, complex values are `vec2<R>` (re, im).
+
+fn re(a: vec2<R>) -> R {
+    return a.x;
+}
+
+fn im(a: vec2<R>) -> R {
+    return a.y;
+}
+
+fn complex(x: R, y: R) -> vec2<R> {
+    return vec2<R>(x, y);
+}
+
+fn r_from_f32(a: f32) -> R {
+    return R(a);
+}
+
+fn r_add(a: R, b: R) -> R {
+    return a + b;
+}
+
+fn r_sub(a: R, b: R) -> R {
+    return a - b;
+}
+
+fn r_mul(a: R, b: R) -> R {
+    return a * b;
+}
+
+fn r_div(a: R, b: R) -> R {
+    return a / b;
+}
+
+fn r_neg(a: R) -> R {
+    return -a;
+}
+
+// Scaled by the larger magnitude, so squaring cannot overflow.
+fn r_hypot(x: R, y: R) -> R {
+    let big = max(abs(x), abs(y));
+    if (big == R(0.0)) {
+        return big;
+    }
+    let small = min(abs(x), abs(y)) / big;
+    return big * sqrt(R(1.0) + small * small);
+}
+
+// WGSL has no f64 atan2, so f64 angles only have f32 precision.
+fn r_atan2(y: R, x: R) -> R {
+    return R(atan2(f32(y), f32(x)));
+}
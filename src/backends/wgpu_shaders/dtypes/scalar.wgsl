@@ -0,0 +1,30 @@
+// Element operations for preludes whose elements `E` are plain scalars, except `e_div` which
+// differs between floats and integers.
+
+fn e_add(a: E, b: E) -> E {
+    return a + b;
+}
+
+fn e_sub(a: E, b: E) -> E {
+    return a - b;
+}
+
+fn e_mul(a: E, b: E) -> E {
+    return a * b;
+}
+
+fn e_eq(a: E, b: E) -> bool {
+    return a == b;
+}
+
+fn e_lt(a: E, b: E) -> bool {
+    return a < b;
+}
+
+fn to_f32(a: E) -> f32 {
+    return f32(a);
+}
+
+fn from_f32(a: f32) -> E {
+    return E(a);
+}
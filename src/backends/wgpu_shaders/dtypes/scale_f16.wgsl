@@ -0,0 +1,17 @@
+// Two f16 scales per u32 word, packed without requiring shader f16 support.
+alias SC = u32;
+
+const SCALES_PER_WORD: u32 = 2u;
+
+fn scale_of(word: SC, sub: u32) -> f32 {
+    return unpack2x16float(word)[sub];
+}
+
+fn scale_word(scales: array<f32, SCALES_PER_WORD>) -> SC {
+    return pack2x16float(vec2<f32>(scales[0], scales[1]));
+}
+
+// Quantizes against the scale as stored, so dequantizing reproduces the same values.
+fn round_scale(scale: f32) -> f32 {
+    return unpack2x16float(pack2x16float(vec2<f32>(scale, 0.0))).x;
+}
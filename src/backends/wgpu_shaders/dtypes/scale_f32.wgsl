@@ -0,0 +1,16 @@
+// One f32 scale per word.
+alias SC = f32;
+
+const SCALES_PER_WORD: u32 = 1u;
+
+fn scale_of(word: SC, sub: u32) -> f32 {
+    return word;
+}
+
+fn scale_word(scales: array<f32, SCALES_PER_WORD>) -> SC {
+    return scales[0];
+}
+
+fn round_scale(scale: f32) -> f32 {
+    return scale;
+}
@@ -1,5 +1,3 @@
-enable f16;
-
 struct TensorInfo {
     shape: array<vec4<u32>, 2>,
     rank: u32,
@@ -10,13 +8,13 @@ struct TensorInfo {
 var<uniform> tensor_info: TensorInfo;
 
 @group(0) @binding(1)
-var<storage, read_write> a: array<f16>;
+var<storage, read_write> a: array<S>;
 
 @group(0) @binding(2)
-var<storage, read> b: array<f16>;
+var<storage, read> b: array<S>;
 
 @compute @workgroup_size(64)
-fn add(@builtin(global_invocation_id) global_id: vec3<u32>) {
+fn in_place(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let idx = global_id.x;
 
     let array_length = arrayLength(&a);
@@ -24,5 +22,10 @@ fn add(@builtin(global_invocation_id) global_id: vec3<u32>) {
         return;
     }
 
-    a[idx] += b[idx];
+    var x = unpack(a[idx]);
+    var y = unpack(b[idx]);
+    for (var lane = 0u; lane < LANES; lane++) {
+        x[lane] = e_op(x[lane], y[lane]);
+    }
+    a[idx] = pack(x);
 }
@@ -0,0 +1,32 @@
+struct TensorInfo {
+    shape: array<vec4<u32>, 2>,
+    rank: u32,
+    length: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> tensor_info: TensorInfo;
+
+@group(0) @binding(1)
+var<storage, read_write> a: array<S>;
+
+@group(0) @binding(2)
+var<storage, read> q: array<u32>;
+@group(0) @binding(3)
+var<storage, read> scales: array<SC>;
+
+@compute @workgroup_size(64)
+fn in_place_quantized(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let idx = global_id.x;
+
+    if (idx * LANES >= tensor_info.length) {
+        return;
+    }
+
+    var x = unpack(a[idx]);
+    for (var lane = 0u; lane < LANES; lane++) {
+        let element = min(idx * LANES + lane, tensor_info.length - 1u);
+        x[lane] = e_op(x[lane], from_f32(dequant(element)));
+    }
+    a[idx] = pack(x);
+}
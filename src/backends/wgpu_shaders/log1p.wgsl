@@ -0,0 +1,12 @@
+// log(1 + x) without rounding 1 + x, which loses the low bits of a small x and leaves log near 1
+// where it is least accurate. Near 0 it sums the series of 2 atanh(z) with z = x / (2 + x), whose
+// terms shrink at least 25 fold.
+fn log1p(x: f32) -> f32 {
+    if (x < -0.25 || x > 0.5) {
+        return log(1.0 + x);
+    }
+    let z = x / (2.0 + x);
+    let w = z * z;
+    let series = 1.0 + w * (1.0 / 3.0 + w * (0.2 + w * (1.0 / 7.0 + w * (1.0 / 9.0 + w / 11.0))));
+    return 2.0 * z * series;
+}
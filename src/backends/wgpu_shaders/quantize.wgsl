@@ -0,0 +1,59 @@
+struct TensorInfo {
+    shape: array<vec4<u32>, 2>,
+    rank: u32,
+    length: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> tensor_info: TensorInfo;
+
+@group(0) @binding(1)
+var<storage, read> a: array<S>;
+
+@group(0) @binding(2)
+var<storage, read_write> q: array<u32>;
+@group(0) @binding(3)
+var<storage, read_write> scales: array<SC>;
+
+fn load(element: u32) -> f32 {
+    if (element >= tensor_info.length) {
+        return 0.0;
+    }
+
+    var elements = unpack(a[element / LANES]);
+    return to_f32(elements[element % LANES]);
+}
+
+// Each invocation quantizes the blocks sharing one word of scales.
+@compute @workgroup_size(64)
+fn quantize(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let idx = global_id.x;
+
+    let blocks = (tensor_info.length + BLOCK_SIZE - 1u) / BLOCK_SIZE;
+    if (idx * SCALES_PER_WORD >= blocks) {
+        return;
+    }
+
+    var block_scales: array<f32, SCALES_PER_WORD>;
+    for (var sub = 0u; sub < SCALES_PER_WORD; sub++) {
+        let start = (idx * SCALES_PER_WORD + sub) * BLOCK_SIZE;
+
+        var absmax = 0.0;
+        for (var i = 0u; i < BLOCK_SIZE; i++) {
+            absmax = max(absmax, abs(load(start + i)));
+        }
+        let scale = round_scale(absmax / QMAX);
+        block_scales[sub] = scale;
+        let inverse = select(0.0, 1.0 / scale, scale > 0.0);
+
+        for (var word = 0u; word < BLOCK_SIZE / PER_WORD; word++) {
+            var packed = 0u;
+            for (var lane = 0u; lane < PER_WORD; lane++) {
+                let value = clamp(round(load(start + word * PER_WORD + lane) * inverse), -QMAX, QMAX);
+                packed |= (bitcast<u32>(i32(value)) & MASK) << (lane * BITS);
+            }
+            q[start / PER_WORD + word] = packed;
+        }
+    }
+    scales[idx] = scale_word(block_scales);
+}
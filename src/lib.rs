@@ -2,4 +2,18 @@
 
 mod backends;
 
-pub use backends::backend::GpuTensor;
+pub use backends::backend::{ComputeHandle, GpuTensor};
+pub use backends::dtype::{Dtype, DtypeVec, Dtyped};
+pub use backends::error::GpuMatError;
+pub use backends::ops::{Activation, CompareOp, UnaryOp};
+pub use backends::{Backend, Computed, transfer};
+#[cfg(any(feature = "backend-cpu", feature = "backend-wgpu"))]
+pub use backends::{
+    DefaultBackend,
+    typed::{ComputeHandleOf, GpuTensorOf, GpuTensorOfSetterFn, TypedSetterFn},
+};
+
+#[cfg(feature = "backend-cpu")]
+pub use backends::cpu;
+#[cfg(feature = "backend-wgpu")]
+pub use backends::wgpu;